@@ -0,0 +1,79 @@
+// Export a conformance fixture from the original C++ engine.
+//
+// Built against the MicropolisEngine sources of https://github.com/SimHacker/micropolis
+// (MicropolisCore/src/MicropolisEngine/src), see generate.sh.
+//
+// Usage: export_fixture NAME CITY_FILE SEED SPEED FUNDS TICK...
+//
+// Loads the city file, overrides its simulation speed and funds, seeds the
+// random number generator and runs one `simLoop(true)` per frame tick,
+// printing the JSON fixture (see ../README.md) on the standard output.
+// NAME is written as is in the JSON output, so it must not need any escaping.
+
+#include <cstdio>
+#include <cstdlib>
+#include <vector>
+
+#include "micropolis.h"
+
+static void write_tiles(Micropolis &engine) {
+    printf("[");
+    for (int x = 0; x < WORLD_W; x++) {
+        printf(x == 0 ? "[" : ",[");
+        for (int y = 0; y < WORLD_H; y++) {
+            printf(y == 0 ? "%u" : ",%u", (unsigned)engine.map[x][y]);
+        }
+        printf("]");
+    }
+    printf("]");
+}
+
+static void write_checkpoint(Micropolis &engine, long tick, bool first) {
+    printf(first ? "{" : ",{");
+    printf("\"tick\":%ld,\"tiles\":", tick);
+    write_tiles(engine);
+    printf(",\"funds\":%ld", (long)engine.totalFunds);
+    printf(",\"population\":{\"residential\":%d,\"commercial\":%d,\"industrial\":%d}}",
+           (int)engine.resPop, (int)engine.comPop, (int)engine.indPop);
+}
+
+int main(int argc, char **argv) {
+    if (argc < 7) {
+        fprintf(stderr, "usage: %s NAME CITY_FILE SEED SPEED FUNDS TICK...\n", argv[0]);
+        return 2;
+    }
+    const char *name = argv[1];
+    const char *city_file = argv[2];
+    int seed = atoi(argv[3]);
+    short speed = (short)atoi(argv[4]);
+    long funds = atol(argv[5]);
+    std::vector<long> ticks;
+    for (int i = 6; i < argc; i++) {
+        ticks.push_back(atol(argv[i]));
+    }
+
+    Micropolis engine;
+    if (!engine.loadFile(city_file)) {
+        fprintf(stderr, "cannot load city file %s\n", city_file);
+        return 1;
+    }
+    engine.setPasses(1);
+    engine.setSpeed(speed);
+    engine.setFunds((int)funds);
+    engine.seedRandom(seed);
+
+    printf("{\"name\":\"%s\",\"seed\":%d,\"simulation_speed\":%d,\"funds\":%ld,\"tiles\":",
+           name, seed, (int)speed, funds);
+    write_tiles(engine);
+    printf(",\"checkpoints\":[");
+    long tick = 0;
+    for (size_t i = 0; i < ticks.size(); i++) {
+        while (tick < ticks[i]) {
+            engine.simLoop(true);
+            tick++;
+        }
+        write_checkpoint(engine, tick, i == 0);
+    }
+    printf("]}\n");
+    return 0;
+}
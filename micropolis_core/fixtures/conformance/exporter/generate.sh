@@ -0,0 +1,26 @@
+#!/bin/sh
+# Regenerate the conformance fixtures with the original C++ engine.
+#
+# Usage:
+#   MICROPOLIS_ENGINE_DIR=<micropolis>/MicropolisCore/src/MicropolisEngine/src \
+#   MICROPOLIS_CITIES_DIR=<micropolis>/micropolis-activity/cities \
+#   ./generate.sh
+set -eu
+
+: "${MICROPOLIS_ENGINE_DIR:?path to the MicropolisEngine C++ sources}"
+: "${MICROPOLIS_CITIES_DIR:?path to the directory of the classic city files}"
+
+EXPORTER_DIR=$(cd "$(dirname "$0")" && pwd)
+FIXTURES_DIR=$(dirname "$EXPORTER_DIR")
+BUILD_DIR=$(mktemp -d)
+trap 'rm -rf "$BUILD_DIR"' EXIT
+
+g++ -O2 -I"$MICROPOLIS_ENGINE_DIR" \
+    "$EXPORTER_DIR/export_fixture.cpp" "$MICROPOLIS_ENGINE_DIR"/*.cpp \
+    -o "$BUILD_DIR/export_fixture"
+
+# name, city file, seed, simulation speed, funds, checkpoint ticks
+"$BUILD_DIR/export_fixture" haight "$MICROPOLIS_CITIES_DIR/haight.cty" \
+    1234 3 20000 0 16 160 1600 >"$FIXTURES_DIR/haight.json"
+"$BUILD_DIR/export_fixture" kobe "$MICROPOLIS_CITIES_DIR/kobe.cty" \
+    4321 2 10000 0 48 480 >"$FIXTURES_DIR/kobe.json"
@@ -1,29 +1,68 @@
 pub mod budget;
+pub mod conformance;
 pub mod disasters;
 pub mod evaluate;
+pub mod events;
+pub mod file;
+pub mod fire;
 pub mod meta;
+pub mod options;
 pub mod population;
 pub mod power;
+pub mod replay;
 pub mod reports;
+pub mod scenario;
 pub mod simulation;
+pub mod snapshot;
 pub mod sprite;
+pub mod state_hash;
 pub mod traffic;
+pub mod treasury;
 pub mod zoning;
 
-use budget::MoneyValue;
-use population::CityPopulation;
-use power::CityPower;
+use std::{
+    cmp::{max, min},
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    mem,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use budget::{CityBudget, MoneyValue};
+use evaluate::{CityEvaluationReport, CityEvaluator};
+use events::{CityEvents, GameEvent};
+use file::CityFileData;
+use options::CityOptions;
+use population::{CityDemand, CityPopulation};
+use power::{network::PowerNetworks, CityPower};
+use reports::{CityReportBuilder, TileStatusReport};
+use scenario::{CityScenario, ScenarioDefinition};
 use simulation::Simulation;
+use snapshot::CitySnapshot;
 use sprite::ActiveSpritesList;
+use state_hash::CityStateHash;
+use treasury::{CityTreasury, TransactionCategory};
 
 use crate::{
+    error::MicropolisError,
     game::{GameLevelDifficulty, GameScenario},
-    map::{animations::TileMapAnimator, Map, MapRectangle, TileMap, TileType},
-    utils::random::MicropolisRandom,
+    map::{
+        animations::TileMapAnimator, tools::ToolResult, Map, MapPosition, MapRectangle, TileMap,
+        TileType,
+    },
+    resources::ResourcePack,
+    utils::{random::MicropolisRandom, Percentage},
 };
 
-use self::{disasters::CityDisasters, traffic::CityTraffic};
+use self::{
+    disasters::{CityDisasters, DisasterEnvironment, DisasterKind},
+    fire::CityFires,
+    traffic::CityTraffic,
+};
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CityInitializationState {
     Initialized = 0,
     JustCreated = 1,
@@ -31,6 +70,7 @@ pub enum CityInitializationState {
 }
 
 /// A Micropolis city.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct City {
     /// Stateful PseudoRandom Number Generator.
     rng: MicropolisRandom,
@@ -41,9 +81,11 @@ pub struct City {
     /// Game difficulty.
     difficulty: GameLevelDifficulty,
     /// Game scenario.
-    scenario: GameScenario,
+    scenario: CityScenario,
     /// Current simulation speed. From 0 to 3.
     simulation_speed: u8,
+    /// Game options saved along with the city.
+    options: CityOptions,
     /// TileMap describing the city and its surroundings.
     map: TileMap,
     /// TileMap animator.
@@ -52,8 +94,12 @@ pub struct City {
     name: String,
     /// Starting year of the city.
     starting_year: i16,
+    /// Funds available to the city, and where the money went.
+    treasury: CityTreasury,
     /// Cash flow of the city.
     cash_flow: MoneyValue,
+    /// Budget of the city services.
+    budget: CityBudget,
     /// City time counter, incremented once every 16 runs through the simulator
     /// (at fast speed).
     ///
@@ -72,61 +118,291 @@ pub struct City {
     fires_count: u32,
     /// Disasters simulation,
     disasters: CityDisasters,
+    /// Fire spread and firefighting simulation.
+    fires: CityFires,
     /// Population counts.
     population: CityPopulation,
+    /// City evaluation.
+    evaluator: CityEvaluator,
     /// Electricity simulation.
     power: CityPower,
     /// Traffic simulation.
     traffic: CityTraffic,
     /// Global simulation.
     sim: Simulation,
+    /// Events waiting to be handled by the front-end.
+    events: CityEvents,
 }
 
 impl City {
-    pub fn new(name: String, scenario: GameScenario) -> Result<Self, String> {
-        let map = Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt)?; // TODO: loading
+    pub fn new(name: String, scenario: GameScenario) -> Result<Self, MicropolisError> {
+        let map = Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt)?;
+        Self::with_map(name, scenario, map)
+    }
+
+    /// Create a new city on the given map.
+    pub fn with_map(
+        name: String,
+        scenario: GameScenario,
+        map: TileMap,
+    ) -> Result<Self, MicropolisError> {
         let population = CityPopulation::from_map(&map);
         let power = CityPower::from_map(&map);
         let traffic = CityTraffic::from_map(&map);
+        let fires = CityFires::from_map(&map);
         let sim = Simulation::new(&map);
+        let difficulty = GameLevelDifficulty::Normal;
         Ok(City {
             rng: MicropolisRandom::from_random_system_seed(),
             sprites: ActiveSpritesList::new(),
             init_status: CityInitializationState::JustCreated,
-            difficulty: GameLevelDifficulty::Normal,
-            scenario: GameScenario::None,
+            treasury: CityTreasury::new(difficulty.starting_funds()),
+            difficulty,
+            disasters: CityDisasters::new(&scenario),
+            fires,
+            scenario: CityScenario::new(&scenario),
             simulation_speed: 0,
+            options: CityOptions::default(),
             map,
             map_animator: TileMapAnimator::load()?,
             name,
             starting_year: 1900,
             cash_flow: 0,
+            budget: CityBudget::new(),
             city_time: 0,
             roads_total: 0,
             rail_total: 0,
             fires_count: 0,
-            disasters: CityDisasters::new(&scenario),
             population,
+            evaluator: CityEvaluator::new(),
             power,
             traffic,
             sim,
+            events: CityEvents::new(),
         })
     }
 
+    /// Load a saved city from the classic Micropolis city file at the given path.
+    ///
+    /// The name of the city is deduced from the file name.
+    pub fn load_from_path(path: &Path) -> Result<Self, MicropolisError> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file = File::open(path).map_err(|source| MicropolisError::ResourceLoad {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::load(name, &mut BufReader::new(file))
+    }
+
+    /// Load a saved city from a classic Micropolis city file.
+    pub fn load<R: Read>(name: String, reader: &mut R) -> Result<Self, MicropolisError> {
+        Self::from_file_data(name, CityFileData::read_from(reader)?)
+    }
+
+    /// Create a city from the contents of a saved city file.
+    ///
+    /// Out-of-range settings are reset to sensible values. Negative funds are kept
+    /// as debt.
+    ///
+    /// See `Micropolis::loadFile` in `fileio.cpp`.
+    pub fn from_file_data(name: String, data: CityFileData) -> Result<Self, MicropolisError> {
+        let mut city = Self::with_map(name, GameScenario::None, data.map.clone())?;
+        city.init_status = CityInitializationState::JustLoaded;
+        city.sim.get_census_mut().load_from_file_data(&data);
+
+        city.treasury = CityTreasury::new(data.get_total_funds() as MoneyValue);
+        city.city_time = max(0, data.get_city_time()) as u32;
+        city.sim.get_taxes_mut().city_tax = match data.get_city_tax() {
+            tax if (0..=20).contains(&tax) => tax as u16,
+            _ => 7,
+        };
+        city.set_simulation_speed(match data.get_simulation_speed() {
+            speed if (0..=3).contains(&speed) => speed as u8,
+            _ => 3,
+        });
+        city.budget
+            .get_police_department_mut()
+            .set_percentage(Percentage::from(data.get_police_percentage()));
+        city.budget
+            .get_fire_department_mut()
+            .set_percentage(Percentage::from(data.get_fire_percentage()));
+        city.budget
+            .get_roads_mut()
+            .set_percentage(Percentage::from(data.get_road_percentage()));
+        city.options = CityOptions {
+            auto_bulldoze: data.is_auto_bulldoze_enabled(),
+            auto_budget: data.is_auto_budget_enabled(),
+            auto_goto: data.is_auto_goto_enabled(),
+            sound_enabled: data.is_sound_enabled(),
+        };
+        city.invalidate_map();
+
+        Ok(city)
+    }
+
+    /// Load the starting city of a scenario from the given directory.
+    pub fn load_scenario_from_directory(
+        scenario: &GameScenario,
+        directory: &Path,
+    ) -> Result<Self, MicropolisError> {
+        let definition = ScenarioDefinition::from_scenario(scenario).ok_or_else(|| {
+            MicropolisError::InvalidArgument(format!("no definition for scenario {:?}", scenario))
+        })?;
+        let path = directory.join(definition.file_name);
+        let file =
+            File::open(&path).map_err(|source| MicropolisError::ResourceLoad { path, source })?;
+        Self::load_scenario(
+            scenario,
+            CityFileData::read_from(&mut BufReader::new(file))?,
+        )
+    }
+
+    /// Create the starting city of a scenario from the contents of its city file.
+    ///
+    /// The starting date, funds and time limit come from the scenario definition,
+    /// and the scenario disaster is scheduled.
+    ///
+    /// See `Micropolis::loadScenario` in `fileio.cpp`.
+    pub fn load_scenario(
+        scenario: &GameScenario,
+        data: CityFileData,
+    ) -> Result<Self, MicropolisError> {
+        let definition = ScenarioDefinition::from_scenario(scenario).ok_or_else(|| {
+            MicropolisError::InvalidArgument(format!("no definition for scenario {:?}", scenario))
+        })?;
+        let mut city = Self::with_map(definition.name.into(), scenario.clone(), data.map.clone())?;
+        city.init_status = CityInitializationState::JustLoaded;
+        city.difficulty = GameLevelDifficulty::Easy;
+        city.sim.get_census_mut().load_from_file_data(&data);
+
+        city.city_time = definition.get_starting_city_time();
+        city.treasury = CityTreasury::new(definition.starting_funds);
+        city.sim.get_taxes_mut().city_tax = 7;
+        city.set_simulation_speed(3);
+        city.invalidate_map();
+
+        Ok(city)
+    }
+
+    /// Save the city to the classic Micropolis city file at the given path.
+    pub fn save_to_path(&self, path: &Path) -> Result<(), MicropolisError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Save the city as a classic Micropolis city file.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<(), MicropolisError> {
+        self.to_file_data().write_to(writer)
+    }
+
+    /// Build the contents of a city file from the current state of the city.
+    ///
+    /// See `Micropolis::saveFile` in `fileio.cpp`.
+    pub fn to_file_data(&self) -> CityFileData {
+        let census = self.sim.get_census();
+        let mut data = CityFileData {
+            residential_history: vec![],
+            commercial_history: vec![],
+            industrial_history: vec![],
+            crime_history: vec![],
+            pollution_history: vec![],
+            money_history: vec![],
+            misc_history: vec![],
+            map: self.map.clone(),
+        };
+        census.save_to_file_data(&mut data);
+
+        data.set_total_funds(
+            self.total_funds()
+                .clamp(i32::MIN as MoneyValue, i32::MAX as MoneyValue) as i32,
+        );
+        data.set_city_time(min(self.city_time, i32::MAX as u32) as i32);
+        data.set_city_tax(self.sim.get_taxes().city_tax as i16);
+        data.set_simulation_speed(self.simulation_speed as i16);
+        data.set_police_percentage(self.budget.get_police_department().get_percentage().value());
+        data.set_fire_percentage(self.budget.get_fire_department().get_percentage().value());
+        data.set_road_percentage(self.budget.get_roads().get_percentage().value());
+        data.set_auto_bulldoze_enabled(self.options.auto_bulldoze);
+        data.set_auto_budget_enabled(self.options.auto_budget);
+        data.set_auto_goto_enabled(self.options.auto_goto);
+        data.set_sound_enabled(self.options.sound_enabled);
+
+        data
+    }
+
+    /// Capture a versioned snapshot of the whole state of the city.
+    pub fn snapshot(&self) -> CitySnapshot {
+        CitySnapshot::capture(self)
+    }
+
+    /// Restore a city from a snapshot.
+    pub fn from_snapshot(snapshot: CitySnapshot) -> Result<Self, MicropolisError> {
+        snapshot.restore()
+    }
+
+    /// Compute the canonical hash of the whole state of the city.
+    pub fn state_hash(&self) -> CityStateHash {
+        CityStateHash::compute(self)
+    }
+
+    /// Use the tile animation sequences of the given resource pack.
+    pub fn load_resources(&mut self, resources: &ResourcePack) -> Result<(), MicropolisError> {
+        self.map_animator = TileMapAnimator::from_resources(resources)?;
+        Ok(())
+    }
+
     pub fn get_map(&self) -> &TileMap {
         &self.map
     }
-    pub fn get_map_mut(&mut self) -> &mut TileMap {
+    pub(crate) fn get_map_mut(&mut self) -> &mut TileMap {
         &mut self.map
     }
 
     pub fn get_sim(&self) -> &Simulation {
         &self.sim
     }
-    pub fn get_sim_mut(&mut self) -> &mut Simulation {
+    pub(crate) fn get_sim_mut(&mut self) -> &mut Simulation {
         &mut self.sim
     }
 
+    /// Events emitted since they were last drained.
+    pub fn get_events(&self) -> &CityEvents {
+        &self.events
+    }
+    pub fn get_events_mut(&mut self) -> &mut CityEvents {
+        &mut self.events
+    }
+
+    /// Random number generator driving the whole game.
+    pub fn get_rng(&self) -> &MicropolisRandom {
+        &self.rng
+    }
+    pub(crate) fn get_rng_mut(&mut self) -> &mut MicropolisRandom {
+        &mut self.rng
+    }
+
+    /// Remove and return the events emitted since the last call, oldest first.
+    ///
+    /// Front-ends should call this after each simulation step.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain()
+    }
+
+    /// Advance the city by one frame tick.
+    ///
+    /// The simulation needs the whole city, so it is detached from the city while
+    /// it runs. Use `Engine::tick` from outside of the crate.
+    pub(crate) fn step(&mut self) -> Result<(), MicropolisError> {
+        let mut sim = mem::replace(&mut self.sim, Simulation::detached());
+        let result = sim.step(self);
+        self.sim = sim;
+        result
+    }
+
     pub fn invalidate_map(&mut self) {
         self.sim.on_map_updated();
     }
@@ -141,11 +417,249 @@ impl City {
         // TODO: pause handling
     }
 
+    pub fn get_options(&self) -> &CityOptions {
+        &self.options
+    }
+    pub fn set_options(&mut self, options: CityOptions) {
+        self.options = options;
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_city_time(&self) -> u32 {
+        self.city_time
+    }
+
+    pub fn get_difficulty(&self) -> &GameLevelDifficulty {
+        &self.difficulty
+    }
+
+    pub fn get_scenario(&self) -> &CityScenario {
+        &self.scenario
+    }
+
+    pub fn get_disasters(&self) -> &CityDisasters {
+        &self.disasters
+    }
+    pub fn get_disasters_mut(&mut self) -> &mut CityDisasters {
+        &mut self.disasters
+    }
+
+    /// Trigger a disaster right away, at the given map position or at a random one.
+    pub fn trigger_disaster(
+        &mut self,
+        kind: DisasterKind,
+        at: Option<MapPosition>,
+    ) -> Result<(), MicropolisError> {
+        let mut environment = DisasterEnvironment {
+            map: &mut self.map,
+            sprites: &mut self.sprites,
+            events: &mut self.events,
+            maximum_pollution_at: self.sim.get_statistics().maximum_pollution_at,
+            city_center: *self.sim.get_city_center(),
+        };
+        self.disasters
+            .trigger_disaster(&mut self.rng, &mut environment, kind, at.as_ref())
+    }
+
+    pub fn get_fires(&self) -> &CityFires {
+        &self.fires
+    }
+    pub fn get_fires_mut(&mut self) -> &mut CityFires {
+        &mut self.fires
+    }
+
+    /// Number of burning tiles found by the last complete map scan.
+    pub fn get_fires_count(&self) -> u32 {
+        self.fires_count
+    }
+
+    pub fn get_evaluator(&self) -> &CityEvaluator {
+        &self.evaluator
+    }
+
+    pub fn get_population(&self) -> &CityPopulation {
+        &self.population
+    }
+
+    /// Get the current RCI demand, as drawn by the RCI indicator.
+    pub fn get_demand(&self) -> CityDemand {
+        self.population.get_demand()
+    }
+
+    pub fn get_budget(&self) -> &CityBudget {
+        &self.budget
+    }
+    pub fn get_budget_mut(&mut self) -> &mut CityBudget {
+        &mut self.budget
+    }
+
     pub fn total_funds(&self) -> MoneyValue {
-        todo!()
+        self.treasury.get_balance()
+    }
+
+    /// Deduct the construction cost of a successful tool application from the city funds.
+    pub(crate) fn charge_tool_result(
+        &mut self,
+        result: &ToolResult,
+    ) -> Result<(), MicropolisError> {
+        if let ToolResult::Succeeded(effects) = result {
+            self.treasury.try_spend(
+                self.city_time,
+                TransactionCategory::Construction,
+                effects.get_cost(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_treasury(&self) -> &CityTreasury {
+        &self.treasury
     }
+    pub fn get_treasury_mut(&mut self) -> &mut CityTreasury {
+        &mut self.treasury
+    }
+
+    /// Report of the last yearly evaluation of the city, if any.
+    pub fn get_last_evaluation(&self) -> Option<&CityEvaluationReport> {
+        self.evaluator.get_last_report()
+    }
+
+    pub fn get_power(&self) -> &CityPower {
+        &self.power
+    }
+
+    pub fn get_traffic(&self) -> &CityTraffic {
+        &self.traffic
+    }
+    pub fn get_traffic_mut(&mut self) -> &mut CityTraffic {
+        &mut self.traffic
+    }
+
+    pub fn get_sprites(&self) -> &ActiveSpritesList {
+        &self.sprites
+    }
+    pub fn get_sprites_mut(&mut self) -> &mut ActiveSpritesList {
+        &mut self.sprites
+    }
+
+    /// Partition the power grid of the city into its connected networks.
+    ///
+    /// Unlike the power scan, this looks at the current state of the map, and
+    /// checks each network against the capacity of its own power plants.
+    pub fn analyze_power_networks(&self) -> PowerNetworks {
+        CityPower::analyze_networks(&self.map)
+    }
+
+    /// Query the status of the tile at the given position, like the "Query" tool.
+    pub fn query(&self, at: &MapPosition) -> Option<TileStatusReport> {
+        CityReportBuilder::new(self).tile_status(at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{options::CityOptions, treasury::CityTreasury, City};
+    use crate::{
+        game::GameScenario,
+        map::{tiles::TILE_ZONE_BIT, Tile, TileType},
+        utils::Percentage,
+    };
+
+    #[test]
+    fn test_city_file_save_and_load() {
+        let mut city = City::new("Testville".into(), GameScenario::None).unwrap();
+        city.map
+            .set_tile_at(&(10, 20).into(), Tile::from_type(TileType::River).unwrap());
+        city.map.set_tile_at(
+            &(119, 99).into(),
+            Tile::from_raw(TileType::Nuclear.to_u16().unwrap() | 0x4400).unwrap(),
+        );
+        city.treasury = CityTreasury::new(123_456);
+        city.city_time = 48 * 12 + 7;
+        city.sim.get_taxes_mut().city_tax = 11;
+        city.set_simulation_speed(2);
+        city.budget
+            .get_fire_department_mut()
+            .set_percentage(Percentage::from_integer(50).unwrap());
+        city.set_options(CityOptions {
+            auto_bulldoze: false,
+            auto_budget: true,
+            auto_goto: false,
+            sound_enabled: true,
+        });
+
+        let mut bytes = Vec::new();
+        city.save(&mut bytes).unwrap();
+        let loaded = City::load("Testville".into(), &mut &bytes[..]).unwrap();
+
+        assert_eq!(loaded.get_map(), city.get_map());
+        assert_eq!(loaded.total_funds(), 123_456);
+        assert_eq!(loaded.get_city_time(), 48 * 12 + 7);
+        assert_eq!(loaded.get_sim().get_taxes().city_tax, 11);
+        assert_eq!(loaded.get_simulation_speed(), 2);
+        assert_eq!(
+            loaded.get_budget().get_fire_department().get_percentage(),
+            &Percentage::from_integer(50).unwrap()
+        );
+        assert_eq!(
+            loaded.get_budget().get_roads().get_percentage(),
+            &Percentage::from_integer(100).unwrap()
+        );
+        assert_eq!(loaded.get_options(), city.get_options());
+
+        let mut reloaded_bytes = Vec::new();
+        loaded.save(&mut reloaded_bytes).unwrap();
+        assert_eq!(reloaded_bytes, bytes);
+    }
+
+    #[test]
+    fn test_city_file_fixture_round_trip() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/cities/riverton.cty");
+        let bytes = fs::read(&path).unwrap();
+        let city = City::load_from_path(&path).unwrap();
+
+        assert_eq!(city.get_name(), "riverton");
+        assert_eq!(city.get_city_time(), 48 * 20 + 9);
+        assert_eq!(city.get_sim().get_taxes().city_tax, 8);
+        assert_eq!(city.get_simulation_speed(), 3);
+        assert_eq!(city.total_funds(), 6_250);
+        assert_eq!(
+            city.get_options(),
+            &CityOptions {
+                auto_bulldoze: true,
+                auto_budget: true,
+                auto_goto: false,
+                sound_enabled: false,
+            }
+        );
+        let tile_at = |x, y| city.get_map().get_tile_at(&(x, y).into()).unwrap();
+        assert_eq!(tile_at(61, 50).get_type(), &Some(TileType::River));
+        assert_eq!(tile_at(35, 25).get_type(), &Some(TileType::Intersection));
+        assert_eq!(tile_at(20, 18).get_type(), &Some(TileType::PowerPlant));
+        assert_ne!(tile_at(20, 18).get_raw() & TILE_ZONE_BIT, 0);
+        assert_eq!(tile_at(25, 19).get_type(), &Some(TileType::FreeZoneCenter));
+
+        let mut saved_bytes = Vec::new();
+        city.save(&mut saved_bytes).unwrap();
+        assert_eq!(saved_bytes, bytes);
+    }
+
+    #[test]
+    fn test_city_file_invalid_settings() {
+        let city = City::new("Testville".into(), GameScenario::None).unwrap();
+        let mut data = city.to_file_data();
+        data.set_city_tax(42);
+        data.set_simulation_speed(-1);
+        data.set_total_funds(-500);
 
-    pub fn evaluate(&self) -> Result<(), String> {
-        todo!()
+        let loaded = City::from_file_data("Testville".into(), data).unwrap();
+        assert_eq!(loaded.get_sim().get_taxes().city_tax, 7);
+        assert_eq!(loaded.get_simulation_speed(), 3);
+        assert_eq!(loaded.total_funds(), -500);
     }
 }
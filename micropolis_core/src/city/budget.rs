@@ -1,13 +1,17 @@
-use std::convert::TryInto;
+use serde::{Deserialize, Serialize};
 
 use crate::utils::Percentage;
 
 /// Integer-based money storage type. Must be copy by default.
 ///
 /// Corresponds to the `Quad` typedef in the C++ code, implemented as a `long`.
-pub type MoneyValue = u32;
+/// Signed since the city funds can go into debt.
+pub type MoneyValue = i64;
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BudgetLine {
+    /// Amount of money requested to fully fund this budget line.
+    funds: MoneyValue,
     /// Absolute amount of money granted for this budget line.
     value: MoneyValue,
     /// Percentage of requested costs relative to the funding level.
@@ -17,13 +21,65 @@ pub struct BudgetLine {
 impl BudgetLine {
     pub fn new() -> Self {
         BudgetLine {
+            funds: 0,
             value: 0,
             percentage: Percentage::from_integer(100).unwrap(),
         }
     }
+
+    pub fn get_funds(&self) -> MoneyValue {
+        self.funds
+    }
+    pub fn set_funds(&mut self, funds: MoneyValue) {
+        self.funds = funds;
+    }
+
+    pub fn get_value(&self) -> MoneyValue {
+        self.value
+    }
+
+    pub fn get_percentage(&self) -> &Percentage {
+        &self.percentage
+    }
+    pub fn set_percentage(&mut self, percentage: Percentage) {
+        self.percentage = percentage;
+    }
+
+    /// Amount of money wanted for this budget line, at the current funding level.
+    fn requested(&self) -> MoneyValue {
+        (self.funds as f64 * self.percentage.value()) as MoneyValue
+    }
+
+    /// Partially fund this budget line with the given amount of money,
+    /// scaling back its funding level accordingly.
+    fn fund_partially(&mut self, available: MoneyValue) {
+        self.value = available;
+        self.percentage = if available > 0 {
+            Percentage::from(available as f64 / self.funds as f64)
+        } else {
+            Percentage::from_integer(0).unwrap()
+        };
+    }
+
+    /// Remove all funding from this budget line.
+    fn defund(&mut self) {
+        self.value = 0;
+        self.percentage = Percentage::from_integer(0).unwrap();
+    }
+}
+
+/// Budget lines the player can fund.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetLineKind {
+    FireDepartment,
+    PoliceDepartment,
+    Roads,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityBudget {
+    /// Taxes collected during the last year.
+    tax_funds: MoneyValue,
     fire_department: BudgetLine,
     police_department: BudgetLine,
     roads: BudgetLine,
@@ -32,32 +88,84 @@ pub struct CityBudget {
 impl CityBudget {
     pub fn new() -> Self {
         CityBudget {
+            tax_funds: 0,
             fire_department: BudgetLine::new(),
             police_department: BudgetLine::new(),
             roads: BudgetLine::new(),
         }
     }
 
-    pub fn update_budget_lines(&mut self, tax_funds: MoneyValue, total_funds: MoneyValue) {
+    pub fn get_tax_funds(&self) -> MoneyValue {
+        self.tax_funds
+    }
+    pub fn set_tax_funds(&mut self, tax_funds: MoneyValue) {
+        self.tax_funds = tax_funds;
+    }
+
+    pub fn get_fire_department(&self) -> &BudgetLine {
+        &self.fire_department
+    }
+    pub fn get_fire_department_mut(&mut self) -> &mut BudgetLine {
+        &mut self.fire_department
+    }
+
+    pub fn get_police_department(&self) -> &BudgetLine {
+        &self.police_department
+    }
+    pub fn get_police_department_mut(&mut self) -> &mut BudgetLine {
+        &mut self.police_department
+    }
+
+    pub fn get_roads(&self) -> &BudgetLine {
+        &self.roads
+    }
+    pub fn get_roads_mut(&mut self) -> &mut BudgetLine {
+        &mut self.roads
+    }
+
+    pub fn get_line(&self, kind: BudgetLineKind) -> &BudgetLine {
+        match kind {
+            BudgetLineKind::FireDepartment => &self.fire_department,
+            BudgetLineKind::PoliceDepartment => &self.police_department,
+            BudgetLineKind::Roads => &self.roads,
+        }
+    }
+    pub fn get_line_mut(&mut self, kind: BudgetLineKind) -> &mut BudgetLine {
+        match kind {
+            BudgetLineKind::FireDepartment => &mut self.fire_department,
+            BudgetLineKind::PoliceDepartment => &mut self.police_department,
+            BudgetLineKind::Roads => &mut self.roads,
+        }
+    }
+
+    /// Total amount of money granted to the budget lines.
+    pub fn get_total_spending(&self) -> MoneyValue {
+        self.fire_department.value + self.police_department.value + self.roads.value
+    }
+
+    /// Grant money to the budget lines, from the collected taxes and the city funds.
+    ///
+    /// When there is not enough money to fund everything, roads are funded first,
+    /// then the fire department and finally the police department.
+    ///
+    /// See `Micropolis::doBudgetNow` in the C++ code.
+    pub fn update_budget_lines(&mut self, total_funds: MoneyValue) {
         let [budget_roads, budget_fire, budget_police] = [
-            (self.roads.value as f64 * self.roads.percentage.value()) as MoneyValue,
-            (self.fire_department.value as f64 * self.fire_department.percentage.value())
-                as MoneyValue,
-            (self.police_department.value as f64 * self.police_department.percentage.value())
-                as MoneyValue,
+            self.roads.requested(),
+            self.fire_department.requested(),
+            self.police_department.requested(),
         ];
         let total = budget_roads + budget_fire + budget_police;
-        let mut available_budget = tax_funds + total_funds;
+        let mut available_budget = (self.tax_funds + total_funds).max(0);
 
         if available_budget > total {
-            // TODO: why are we not subtracting from the available budget as below
+            // Enough budget to fully fund everything.
             self.fire_department.value = budget_fire;
             self.police_department.value = budget_police;
             self.roads.value = budget_roads;
         } else if total > 0 {
             // Not enough available budget to fund everything.
             // First spend on roads, then on fire, then on police.
-
             if available_budget > budget_roads {
                 // Enough budget to fully fund roads.
                 self.roads.value = budget_roads;
@@ -68,61 +176,85 @@ impl CityBudget {
                     self.fire_department.value = budget_fire;
                     available_budget -= budget_fire;
 
-                    if available_budget > budget_police {
-                        // Enough budget to fully fund police.
-                        // FIXME: Hey what are we doing here? Should never get here.
-                        // We tested for available_budget > total above
-                        // (where total = fireInt + policeInt + roadInt),
-                        // so this should never happen.
-                        self.police_department.value = budget_police;
-                        available_budget -= budget_police;
-                    } else {
-                        // Fuly funded roads and fire.
-                        // Partially fund police.
-                        self.police_department.value = available_budget;
-                        if available_budget > 0 {
-                            // Scale back police percentage to available cash.
-                            self.police_department.percentage = Percentage::from_integer(
-                                (available_budget / self.police_department.value)
-                                    .try_into()
-                                    .unwrap(),
-                            )
-                            .unwrap();
-                        } else {
-                            // Exactly nothing left, so scale back police percentage to zero.
-                            self.police_department.percentage =
-                                Percentage::from_integer(0).unwrap();
-                        }
-                    }
+                    // Partially fund police.
+                    self.police_department.fund_partially(available_budget);
                 } else {
-                    // Not enough budget to fully fund fire.
-                    self.fire_department.value = available_budget;
-
-                    // No police after funding roads and fire.
-                    self.police_department.value = 0;
-                    self.police_department.percentage = Percentage::from_integer(0).unwrap();
-
-                    if available_budget > 0 {
-                        // Scale back fire percentage to available cash.
-                        self.fire_department.percentage = Percentage::from_integer(
-                            (available_budget / self.fire_department.value)
-                                .try_into()
-                                .unwrap(),
-                        )
-                        .unwrap();
-                    } else {
-                        // Exactly nothing left, so scale back fire percentage to zero.
-                        self.fire_department.percentage = Percentage::from_integer(0).unwrap();
-                    }
+                    // Partially fund fire, no police after funding roads and fire.
+                    self.fire_department.fund_partially(available_budget);
+                    self.police_department.defund();
                 }
             } else {
-                assert!(available_budget == total);
-                assert!(total == 0);
-                // Zero funding, so no values but full percentages.
-                self.roads = BudgetLine::new();
-                self.fire_department = BudgetLine::new();
-                self.police_department = BudgetLine::new();
+                // Partially fund roads, no fire nor police.
+                self.roads.fund_partially(available_budget);
+                self.fire_department.defund();
+                self.police_department.defund();
             }
+        } else {
+            // Nothing to pay for, so no values but full percentages.
+            self.roads = BudgetLine {
+                funds: self.roads.funds,
+                ..BudgetLine::new()
+            };
+            self.fire_department = BudgetLine {
+                funds: self.fire_department.funds,
+                ..BudgetLine::new()
+            };
+            self.police_department = BudgetLine {
+                funds: self.police_department.funds,
+                ..BudgetLine::new()
+            };
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CityBudget;
+    use crate::utils::Percentage;
+
+    fn build_budget() -> CityBudget {
+        let mut budget = CityBudget::new();
+        budget.set_tax_funds(500);
+        budget.get_roads_mut().set_funds(400);
+        budget.get_fire_department_mut().set_funds(300);
+        budget.get_police_department_mut().set_funds(200);
+        budget
+    }
+
+    #[test]
+    fn test_budget_fully_funded() {
+        let mut budget = build_budget();
+        budget
+            .get_police_department_mut()
+            .set_percentage(Percentage::from_integer(50).unwrap());
+        budget.update_budget_lines(1000);
+        assert_eq!(budget.get_roads().get_value(), 400);
+        assert_eq!(budget.get_fire_department().get_value(), 300);
+        assert_eq!(budget.get_police_department().get_value(), 100);
+        assert_eq!(budget.get_total_spending(), 800);
+    }
+
+    #[test]
+    fn test_budget_partially_funded() {
+        let mut budget = build_budget();
+        budget.update_budget_lines(300);
+        assert_eq!(budget.get_roads().get_value(), 400);
+        assert_eq!(budget.get_fire_department().get_value(), 300);
+        assert_eq!(budget.get_police_department().get_value(), 100);
+        assert_eq!(budget.get_police_department().get_percentage().value(), 0.5);
+
+        let mut budget = build_budget();
+        budget.update_budget_lines(0);
+        assert_eq!(budget.get_roads().get_value(), 400);
+        assert_eq!(budget.get_fire_department().get_value(), 100);
+        assert_eq!(budget.get_police_department().get_value(), 0);
+        assert_eq!(budget.get_police_department().get_percentage().value(), 0.0);
+
+        let mut budget = build_budget();
+        budget.set_tax_funds(100);
+        budget.update_budget_lines(0);
+        assert_eq!(budget.get_roads().get_value(), 100);
+        assert_eq!(budget.get_roads().get_percentage().value(), 0.25);
+        assert_eq!(budget.get_total_spending(), 100);
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::Engine,
+    error::MicropolisError,
+    game::GameScenario,
+    map::{MapClusteringStrategy, MapPosition, Tile, TileMap},
+};
+
+use super::{budget::MoneyValue, treasury::CityTreasury, City};
+
+/// Raw tile values of a whole map, X-major (`tiles[x][y]`) like `map` in the C++ code.
+pub type RawTiles = Vec<Vec<u16>>;
+
+/// Residential, commercial and industrial populations (`resPop`, `comPop` and `indPop`
+/// in the C++ code).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformancePopulation {
+    pub residential: u16,
+    pub commercial: u16,
+    pub industrial: u16,
+}
+
+impl fmt::Display for ConformancePopulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R={} C={} I={}",
+            self.residential, self.commercial, self.industrial
+        )
+    }
+}
+
+/// Expected state of the city after a given number of frame ticks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceCheckpoint {
+    /// Number of frame ticks run since the start of the fixture.
+    pub tick: u64,
+    pub tiles: RawTiles,
+    pub funds: MoneyValue,
+    pub population: ConformancePopulation,
+}
+
+/// A game recorded with the original C++ engine, to check the port against.
+///
+/// See `fixtures/conformance/README.md` for the format and the exporter producing it.
+/// Like the C++ engine, the port draws every random number from a single generator,
+/// the one of the city, seeded with `seed` once the starting city is built.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceFixture {
+    /// Name of the fixture, used in the reports.
+    pub name: String,
+    /// Seed of the random number generator (`Micropolis::seedRandom`).
+    pub seed: i32,
+    /// Simulation speed, from 0 to 3.
+    pub simulation_speed: u8,
+    /// Starting funds of the city.
+    pub funds: MoneyValue,
+    /// Starting tile map.
+    pub tiles: RawTiles,
+    /// Expected states of the city, in tick order.
+    pub checkpoints: Vec<ConformanceCheckpoint>,
+}
+
+impl ConformanceFixture {
+    /// Run the Rust engine on the fixture, and compare the city with every checkpoint.
+    pub fn run(&self) -> Result<ConformanceReport, MicropolisError> {
+        let mut engine = self.start()?;
+        let mut report = ConformanceReport {
+            fixture: self.name.clone(),
+            checkpoints_count: self.checkpoints.len(),
+            mismatches: vec![],
+        };
+        for checkpoint in self.checkpoints.iter() {
+            if checkpoint.tick < engine.get_ticks() {
+                return Err(MicropolisError::InvalidArgument(format!(
+                    "conformance fixture {}: checkpoint at tick {} is out of order",
+                    self.name, checkpoint.tick
+                )));
+            }
+            while engine.get_ticks() < checkpoint.tick {
+                engine.tick()?;
+            }
+            let mismatch = CheckpointMismatch::compare(checkpoint, engine.get_city())?;
+            if !mismatch.is_empty() {
+                report.mismatches.push(mismatch);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Build the engine running the starting city of the fixture.
+    fn start(&self) -> Result<Engine, MicropolisError> {
+        let map = tilemap_from_raw(&self.tiles)?;
+        let mut city = City::with_map(self.name.clone(), GameScenario::None, map)?;
+        city.treasury = CityTreasury::new(self.funds);
+        city.set_simulation_speed(self.simulation_speed);
+        Ok(Engine::with_seed(city, self.seed))
+    }
+}
+
+fn tilemap_from_raw(tiles: &RawTiles) -> Result<TileMap, MicropolisError> {
+    let data = tiles
+        .iter()
+        .map(|column| column.iter().map(|raw| Tile::from_raw(*raw)).collect())
+        .collect::<Result<Vec<Vec<Tile>>, MicropolisError>>()?;
+    Ok(TileMap::with_data(data, MapClusteringStrategy::BlockSize1))
+}
+
+fn raw_tiles(map: &TileMap) -> RawTiles {
+    map.tiles()
+        .iter()
+        .map(|column| column.iter().map(|tile| tile.get_raw()).collect())
+        .collect()
+}
+
+fn population(city: &City) -> ConformancePopulation {
+    ConformancePopulation {
+        residential: city.population.get_residential(),
+        commercial: city.population.get_commercial(),
+        industrial: city.population.get_industrial(),
+    }
+}
+
+/// A value differing from the one recorded in a fixture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueMismatch<T> {
+    pub expected: T,
+    pub actual: T,
+}
+
+impl<T: PartialEq> ValueMismatch<T> {
+    fn compare(expected: T, actual: T) -> Option<Self> {
+        if expected == actual {
+            None
+        } else {
+            Some(ValueMismatch { expected, actual })
+        }
+    }
+}
+
+/// A tile differing from the one recorded in a fixture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileMismatch {
+    pub position: MapPosition,
+    pub expected: u16,
+    pub actual: u16,
+}
+
+impl fmt::Display for TileMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |raw: u16| match Tile::from_raw(raw).map(|tile| tile.get_type().clone()) {
+            Ok(Some(tile_type)) => format!("{:0>4X} ({:?})", raw, tile_type),
+            _ => format!("{:0>4X}", raw),
+        };
+        write!(
+            f,
+            "({}, {}): expected {}, got {}",
+            self.position.get_x(),
+            self.position.get_y(),
+            describe(self.expected),
+            describe(self.actual)
+        )
+    }
+}
+
+/// Differences between a checkpoint of a fixture and the city run by the port.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointMismatch {
+    pub tick: u64,
+    /// Differing tiles, in X-major order.
+    pub tiles: Vec<TileMismatch>,
+    pub funds: Option<ValueMismatch<MoneyValue>>,
+    pub population: Option<ValueMismatch<ConformancePopulation>>,
+}
+
+impl CheckpointMismatch {
+    fn compare(checkpoint: &ConformanceCheckpoint, city: &City) -> Result<Self, MicropolisError> {
+        let actual_tiles = raw_tiles(&city.map);
+        if actual_tiles.len() != checkpoint.tiles.len()
+            || actual_tiles
+                .iter()
+                .zip(checkpoint.tiles.iter())
+                .any(|(actual, expected)| actual.len() != expected.len())
+        {
+            return Err(MicropolisError::InvalidArgument(format!(
+                "conformance checkpoint tile map dimensions differ at tick {}",
+                checkpoint.tick
+            )));
+        }
+        let mut tiles = vec![];
+        for (x, (expected_column, actual_column)) in
+            checkpoint.tiles.iter().zip(actual_tiles.iter()).enumerate()
+        {
+            for (y, (expected, actual)) in
+                expected_column.iter().zip(actual_column.iter()).enumerate()
+            {
+                if expected != actual {
+                    tiles.push(TileMismatch {
+                        position: (x, y).into(),
+                        expected: *expected,
+                        actual: *actual,
+                    });
+                }
+            }
+        }
+        Ok(CheckpointMismatch {
+            tick: checkpoint.tick,
+            tiles,
+            funds: ValueMismatch::compare(checkpoint.funds, city.total_funds()),
+            population: ValueMismatch::compare(checkpoint.population, population(city)),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty() && self.funds.is_none() && self.population.is_none()
+    }
+}
+
+impl fmt::Display for CheckpointMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tick {}:", self.tick)?;
+        if let Some(funds) = &self.funds {
+            writeln!(
+                f,
+                "  funds: expected {}, got {}",
+                funds.expected, funds.actual
+            )?;
+        }
+        if let Some(population) = &self.population {
+            writeln!(
+                f,
+                "  population: expected {}, got {}",
+                population.expected, population.actual
+            )?;
+        }
+        if !self.tiles.is_empty() {
+            writeln!(f, "  {} tile(s) mismatch:", self.tiles.len())?;
+            for tile in self.tiles.iter() {
+                writeln!(f, "    {}", tile)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of running the port on a conformance fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub fixture: String,
+    pub checkpoints_count: usize,
+    /// Checkpoints the port does not reproduce, in tick order.
+    pub mismatches: Vec<CheckpointMismatch>,
+}
+
+impl ConformanceReport {
+    pub fn is_conformant(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "fixture \"{}\": {} of {} checkpoint(s) mismatch",
+            self.fixture,
+            self.mismatches.len(),
+            self.checkpoints_count
+        )?;
+        for mismatch in self.mismatches.iter() {
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{
+        population, raw_tiles, ConformanceCheckpoint, ConformanceFixture, ConformancePopulation,
+        ValueMismatch,
+    };
+    use crate::map::TileType;
+
+    /// Record a regression baseline with the port itself, capturing the state of
+    /// the city after each of the given ticks.
+    ///
+    /// Not a conformance fixture: it only checks the port against itself.
+    fn record_regression_baseline(checkpoint_ticks: &[u64]) -> ConformanceFixture {
+        let dirt = TileType::Dirt.to_u16().unwrap();
+        let mut tiles = vec![vec![dirt; 100]; 120];
+        tiles[10][20] = TileType::River.to_u16().unwrap();
+        tiles[60][50] = TileType::Fire.to_u16().unwrap();
+        let mut fixture = ConformanceFixture {
+            name: "Conformville".into(),
+            seed: 1234,
+            simulation_speed: 3,
+            funds: 10000,
+            tiles,
+            checkpoints: vec![],
+        };
+        let mut engine = fixture.start().unwrap();
+        for checkpoint_tick in checkpoint_ticks {
+            while engine.get_ticks() < *checkpoint_tick {
+                engine.tick().unwrap();
+            }
+            let city = engine.get_city();
+            fixture.checkpoints.push(ConformanceCheckpoint {
+                tick: engine.get_ticks(),
+                tiles: raw_tiles(&city.map),
+                funds: city.total_funds(),
+                population: population(city),
+            });
+        }
+        fixture
+    }
+
+    #[test]
+    #[ignore] // FIXME: remove once the fixtures of `exporter/generate.sh` are committed
+    fn test_conformance_fixtures_run() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/conformance");
+        let mut fixtures_count = 0;
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let json = fs::read_to_string(&path).unwrap();
+            let fixture: ConformanceFixture = serde_json::from_str(&json).unwrap();
+            fixtures_count += 1;
+            // the port does not conform yet: the differences are reported by
+            // `micropolis_utils check-conformance`, but the starting city must match
+            let report = fixture.run().unwrap();
+            assert_eq!(report.checkpoints_count, fixture.checkpoints.len());
+            if let Some(mismatch) = report.mismatches.iter().find(|mismatch| mismatch.tick == 0) {
+                panic!(
+                    "fixture {}: starting city differs\n{}",
+                    fixture.name, mismatch
+                );
+            }
+        }
+        assert!(fixtures_count > 0, "no conformance fixture found");
+    }
+
+    #[test]
+    fn test_regression_baseline_matches() {
+        let fixture = record_regression_baseline(&[0, 50, 100]);
+        assert_eq!(fixture.checkpoints.len(), 3);
+        let json = serde_json::to_string(&fixture).unwrap();
+        let fixture: ConformanceFixture = serde_json::from_str(&json).unwrap();
+
+        let report = fixture.run().unwrap();
+        assert!(report.is_conformant());
+        assert_eq!(
+            report.to_string(),
+            "fixture \"Conformville\": 0 of 3 checkpoint(s) mismatch\n"
+        );
+    }
+
+    #[test]
+    fn test_regression_baseline_mismatches() {
+        let mut fixture = record_regression_baseline(&[0, 50, 100]);
+        let river = TileType::River.to_u16().unwrap();
+        fixture.checkpoints[1].tiles[3][4] = river;
+        fixture.checkpoints[1].funds += 100;
+        fixture.checkpoints[2].population.residential += 10;
+
+        let report = fixture.run().unwrap();
+        assert!(!report.is_conformant());
+        assert_eq!(report.mismatches.len(), 2);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(mismatch.tick, 50);
+        assert_eq!(mismatch.tiles.len(), 1);
+        assert_eq!(mismatch.tiles[0].position, (3, 4).into());
+        assert_eq!(mismatch.tiles[0].expected, river);
+        assert_eq!(
+            mismatch.funds.unwrap().expected,
+            mismatch.funds.unwrap().actual + 100
+        );
+        assert_eq!(mismatch.population, None);
+        assert_eq!(
+            report.mismatches[1].population,
+            Some(ValueMismatch {
+                expected: ConformancePopulation {
+                    residential: 10,
+                    ..Default::default()
+                },
+                actual: ConformancePopulation::default(),
+            })
+        );
+
+        let text = report.to_string();
+        assert!(text.starts_with("fixture \"Conformville\": 2 of 3 checkpoint(s) mismatch\n"));
+        assert!(text.contains(
+            "  1 tile(s) mismatch:\n    (3, 4): expected 0002 (River), got 0000 (Dirt)\n"
+        ));
+        assert!(text.contains("  population: expected R=10 C=0 I=0, got R=0 C=0 I=0\n"));
+    }
+}
@@ -1,21 +1,31 @@
-use std::todo;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    error::MicropolisError,
     game::{GameLevelDifficulty, GameScenario},
     map::tiles::TILE_ANIM_BIT,
     map::tiles::TILE_BULL_BIT,
     map::tiles::TILE_BURN_BIT,
     map::tiles::TILE_LOW_MASK,
     map::tiles::TILE_ZONE_BIT,
+    map::Map,
     map::MapPosition,
     map::Tile,
     map::TileMap,
     map::TileType,
-    utils::random::MicropolisRandom,
+    messages::GameMessage,
+    utils::{
+        hash::{StableHash, StableHasher},
+        random::MicropolisRandom,
+    },
 };
 
 use super::{
-    simulation::sprites::{make_monster, make_tornado},
+    events::{CityEvents, GameSound, SoundChannel, UiCallback},
+    simulation::{
+        sprites::{make_monster, make_monster_at, make_sprite, make_tornado},
+        Simulation,
+    },
     sprite::{ActiveSpritesList, Sprite, SpriteType},
 };
 
@@ -26,9 +36,47 @@ const DISASTER_CHANCE: [i16; 3] = [
     60,      // Game level 2
 ];
 
+/// Number of disaster ticks between two bombs of a fire bombing raid.
+const FIRE_BOMB_INTERVAL: u16 = 2;
+
 const FLOOD_DX: [i32; 4] = [0, 1, 0, -1];
 const FLOOD_DY: [i32; 4] = [-1, 0, 1, 0];
 
+/// Disasters that can be triggered on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisasterKind {
+    Fire,
+    Flood,
+    Tornado,
+    Earthquake,
+    Monster,
+    Meltdown,
+    PlaneCrash,
+    FireBombing,
+}
+
+/// A disaster planned for a future city time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledDisaster {
+    pub kind: DisasterKind,
+    /// City time at which the disaster happens.
+    pub city_time: u32,
+    /// Map position of the disaster, or None for a random one.
+    pub at: Option<MapPosition>,
+}
+
+/// Parts of the city the disasters can strike.
+pub struct DisasterEnvironment<'a> {
+    pub map: &'a mut TileMap,
+    pub sprites: &'a mut ActiveSpritesList,
+    pub events: &'a mut CityEvents,
+    /// Where the monster heads to.
+    pub maximum_pollution_at: MapPosition,
+    /// Epicenter of the earthquakes.
+    pub city_center: MapPosition,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityDisasters {
     /// Size of flooding disaster.
     flood_count: i16,
@@ -36,6 +84,14 @@ pub struct CityDisasters {
     disaster_event: GameScenario,
     /// Count-down timer for the disaster.
     disaster_timer: u16,
+    /// Can random disasters happen?
+    disasters_enabled: bool,
+    /// Disasters waiting for their city time, in scheduling order.
+    scheduled: Vec<ScheduledDisaster>,
+    /// Bombs still to be dropped by the current fire bombing raid.
+    fire_bombs_left: u16,
+    /// Count-down before the next bomb of the current raid.
+    fire_bomb_timer: u16,
 }
 
 impl CityDisasters {
@@ -43,27 +99,109 @@ impl CityDisasters {
         Self {
             flood_count: 0,
             disaster_event: scenario.clone(),
-            disaster_timer: 0,
+            disaster_timer: scenario.get_disaster_timer(),
+            disasters_enabled: true,
+            scheduled: vec![],
+            fire_bombs_left: 0,
+            fire_bomb_timer: 0,
         }
     }
 
+    /// Remaining count-down before flood tiles start to recede.
+    pub fn get_flood_count(&self) -> i16 {
+        self.flood_count
+    }
+
+    /// Bombs still to be dropped by the current fire bombing raid.
+    pub fn get_fire_bombs_left(&self) -> u16 {
+        self.fire_bombs_left
+    }
+
+    /// Can random disasters happen?
+    ///
+    /// Scenario and scheduled disasters happen anyway.
+    pub fn are_disasters_enabled(&self) -> bool {
+        self.disasters_enabled
+    }
+    pub fn set_disasters_enabled(&mut self, enabled: bool) {
+        self.disasters_enabled = enabled;
+    }
+
+    pub fn get_scheduled_disasters(&self) -> &Vec<ScheduledDisaster> {
+        &self.scheduled
+    }
+
+    /// Plan a disaster for the given city time, at the given map position or at a random one.
+    pub fn schedule_disaster(
+        &mut self,
+        kind: DisasterKind,
+        city_time: u32,
+        at: Option<MapPosition>,
+    ) {
+        self.scheduled.push(ScheduledDisaster {
+            kind,
+            city_time,
+            at,
+        });
+    }
+
+    /// Cancel all the scheduled disasters.
+    pub fn clear_scheduled_disasters(&mut self) {
+        self.scheduled.clear();
+    }
+
+    pub fn get_disaster_event(&self) -> &GameScenario {
+        &self.disaster_event
+    }
+
+    /// Remaining count-down before the end of the scenario disaster.
+    pub fn get_disaster_timer(&self) -> u16 {
+        self.disaster_timer
+    }
+
     /// Let disasters happen.
     pub fn do_disasters(
         &mut self,
         rng: &mut MicropolisRandom,
-        map: &mut TileMap,
-        sprites: &mut ActiveSpritesList,
+        environment: &mut DisasterEnvironment,
         difficulty: &GameLevelDifficulty,
         scenario: &GameScenario,
         average_pollution: u16,
-        maximum_pollution_at: &MapPosition,
-    ) -> Result<(), String> {
+        city_time: u32,
+    ) -> Result<(), MicropolisError> {
         if self.flood_count > 0 {
             self.flood_count -= 1;
         }
 
+        self.drop_fire_bombs(
+            rng,
+            environment.map,
+            environment.sprites,
+            environment.events,
+        )?;
+
         if self.disaster_event != GameScenario::None {
-            self.scenario_disaster(rng, map, sprites, maximum_pollution_at)?;
+            self.scenario_disaster(
+                rng,
+                environment.map,
+                environment.sprites,
+                environment.events,
+                &environment.maximum_pollution_at,
+                &environment.city_center,
+            )?;
+        }
+
+        let (due, pending): (Vec<ScheduledDisaster>, Vec<ScheduledDisaster>) = self
+            .scheduled
+            .drain(..)
+            .partition(|disaster| disaster.city_time <= city_time);
+        self.scheduled = pending;
+        for disaster in due {
+            self.trigger_disaster(rng, environment, disaster.kind, disaster.at.as_ref())?;
+        }
+
+        if !self.disasters_enabled {
+            return Ok(());
         }
 
         let mut x = difficulty.clone() as usize;
@@ -71,18 +209,25 @@ impl CityDisasters {
             x = GameLevelDifficulty::Easy as usize;
         }
 
-        if rng.get_random(DISASTER_CHANCE[x]) == 0 {
+        if rng.get_random(DISASTER_CHANCE[x]) != 0 {
             return Ok(());
         }
 
+        let DisasterEnvironment {
+            map,
+            sprites,
+            events,
+            maximum_pollution_at,
+            city_center,
+        } = environment;
         match rng.get_random(8) {
             0 | 1 => {
                 // 2/9 chance a fire breaks out
-                Self::set_fire(rng, map)?;
+                Self::set_fire(rng, map, events)?;
             }
             2 | 3 => {
                 // 2/9 chance for a flood
-                self.make_flood(rng, map)?;
+                self.make_flood(rng, map, events)?;
             }
             4 => {
                 // 1/9 chance nothing happens
@@ -91,16 +236,16 @@ impl CityDisasters {
             }
             5 => {
                 // 1/9 chance tornado
-                make_tornado(rng, sprites, map)?;
+                make_tornado(rng, sprites, events, map, None)?;
             }
             6 => {
                 // 1/9 chance earthquake
-                Self::make_earthquake(rng, map)?;
+                Self::make_earthquake(rng, map, events, city_center)?;
             }
             7 | 8 => {
                 // 2/9 chance a scary monster arrives in a dirty town
                 if average_pollution > 60 {
-                    make_monster(rng, sprites, map, maximum_pollution_at)?;
+                    make_monster(rng, sprites, events, map, maximum_pollution_at)?;
                 }
             }
             _ => unreachable!(),
@@ -109,40 +254,98 @@ impl CityDisasters {
         Ok(())
     }
 
+    /// Trigger a disaster right away, at the given map position or at a random one.
+    ///
+    /// Earthquakes always shake the whole city. Fails if the given position
+    /// cannot be struck by the disaster (e.g. a fire on a tile which cannot burn).
+    pub fn trigger_disaster(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        environment: &mut DisasterEnvironment,
+        kind: DisasterKind,
+        at: Option<&MapPosition>,
+    ) -> Result<(), MicropolisError> {
+        let DisasterEnvironment {
+            map,
+            sprites,
+            events,
+            maximum_pollution_at,
+            city_center,
+        } = environment;
+        if let Some(at) = at {
+            if !map.in_bounds(at) {
+                return Err(MicropolisError::OutOfBounds(*at));
+            }
+        }
+
+        match (kind, at) {
+            (DisasterKind::Fire, Some(at)) => Self::start_fire_at(rng, map, events, at),
+            (DisasterKind::Fire, None) => Self::make_fire(rng, map, events),
+            (DisasterKind::Flood, Some(at)) => self.flood_at(map, events, at),
+            (DisasterKind::Flood, None) => self.make_flood(rng, map, events),
+            (DisasterKind::Tornado, at) => make_tornado(rng, sprites, events, map, at),
+            (DisasterKind::Earthquake, at) => {
+                Self::make_earthquake(rng, map, events, at.unwrap_or(&*city_center))
+            }
+            (DisasterKind::Monster, Some(at)) => {
+                make_monster_at(rng, sprites, events, at, maximum_pollution_at)
+            }
+            (DisasterKind::Monster, None) => {
+                make_monster(rng, sprites, events, map, maximum_pollution_at)
+            }
+            (DisasterKind::Meltdown, Some(at)) => {
+                if map.get_tile_char_at(at) != TileType::Nuclear.to_u16() {
+                    return Err(MicropolisError::InvalidArgument(format!(
+                        "no nuclear power plant at ({}, {})",
+                        at.get_x(),
+                        at.get_y()
+                    )));
+                }
+                Self::do_meltdown(rng, map, sprites, events, at)
+            }
+            (DisasterKind::Meltdown, None) => Self::make_meltdown(rng, map, sprites, events),
+            (DisasterKind::PlaneCrash, at) => Self::make_air_crash(rng, map, sprites, events, at),
+            (DisasterKind::FireBombing, Some(at)) => Self::fire_bomb(rng, map, sprites, events, at),
+            (DisasterKind::FireBombing, None) => self.make_fire_bombs(rng, map, sprites, events),
+        }
+    }
+
     fn scenario_disaster(
         &mut self,
         rng: &mut MicropolisRandom,
         map: &mut TileMap,
         sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
         maximum_pollution_at: &MapPosition,
-    ) -> Result<(), String> {
+        city_center: &MapPosition,
+    ) -> Result<(), MicropolisError> {
         match self.disaster_event {
             GameScenario::Dullsville => (),
             GameScenario::SanFrancisco => {
                 if self.disaster_timer == 1 {
-                    Self::make_earthquake(rng, map)?;
+                    Self::make_earthquake(rng, map, events, city_center)?;
                 }
             }
             GameScenario::Hamburg => {
                 if self.disaster_timer % 10 == 0 {
-                    Self::make_fire_bombs(rng, map)?;
+                    self.make_fire_bombs(rng, map, sprites, events)?;
                 }
             }
             GameScenario::Bern => (),
             GameScenario::Tokyo => {
                 if self.disaster_timer == 1 {
-                    make_monster(rng, sprites, map, maximum_pollution_at)?;
+                    make_monster(rng, sprites, events, map, maximum_pollution_at)?;
                 }
             }
             GameScenario::Detroit => (),
             GameScenario::Boston => {
                 if self.disaster_timer == 1 {
-                    Self::make_meltdown(rng, map)?;
+                    Self::make_meltdown(rng, map, sprites, events)?;
                 }
             }
             GameScenario::Rio => {
                 if (self.disaster_timer % 24) == 0 {
-                    self.make_flood(rng, map)?;
+                    self.make_flood(rng, map, events)?;
                 }
             }
             GameScenario::None => unreachable!(),
@@ -157,50 +360,60 @@ impl CityDisasters {
         Ok(())
     }
 
-    /// Make a nuclear power plant melt.
-    /// TODO: randomize which nuke plant melts down
-    fn make_meltdown(rng: &mut MicropolisRandom, map: &mut TileMap) -> Result<(), String> {
+    /// Make a random nuclear power plant of the city melt.
+    fn make_meltdown(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
         let bounds = map.bounds();
+        let mut plants: Vec<MapPosition> = vec![];
         for x in 0..bounds.get_width() - 1 {
             for y in 0..bounds.get_height() - 1 {
                 let position: MapPosition = (x, y).into();
-                let tile = map.get_tile_at(&position).ok_or(format!(
-                    "CityDisasters::make_meltdown cannot get tile at position {}.",
-                    position,
-                ))?;
+                let tile = map
+                    .get_tile_at(&position)
+                    .ok_or(MicropolisError::OutOfBounds(position))?;
                 if tile.get_raw() & TILE_LOW_MASK == TileType::Nuclear.to_u16().unwrap() {
-                    return Self::do_meltdown(rng, map, &position);
+                    plants.push(position);
                 }
             }
         }
 
-        Ok(())
+        if plants.is_empty() {
+            return Ok(());
+        }
+        let plant = plants[rng.get_random(plants.len() as i16 - 1) as usize];
+        Self::do_meltdown(rng, map, sprites, events, &plant)
     }
 
     /// Perform a nuclear melt-down disaster at the given nuclear power plant position.
     pub fn do_meltdown(
         rng: &mut MicropolisRandom,
         map: &mut TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
         position: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         let (x, y) = position.as_tuple();
-        Self::make_explosion(map, &(x - 1, y - 1).into());
-        Self::make_explosion(map, &(x - 1, y + 2).into());
-        Self::make_explosion(map, &(x + 2, y - 1).into());
-        Self::make_explosion(map, &(x + 2, y + 2).into());
+        Self::make_explosion(rng, map, sprites, &(x - 1, y - 1).into())?;
+        Self::make_explosion(rng, map, sprites, &(x - 1, y + 2).into())?;
+        Self::make_explosion(rng, map, sprites, &(x + 2, y - 1).into())?;
+        Self::make_explosion(rng, map, sprites, &(x + 2, y + 2).into())?;
 
         // whole power plant is on fire
         for temp_x in x - 1..x + 3 {
             for temp_y in y - 1..y + 3 {
                 map.set_tile_at(
                     &(temp_x, temp_y).into(),
-                    Tile::from_raw(Self::random_fire(rng)).unwrap(),
+                    Tile::from_raw(Self::random_fire(rng))?,
                 );
             }
         }
 
         // add lots of radiation tiles around the plant
-        for z in 0..200 {
+        for _ in 0..200 {
             let radiation_position: MapPosition = (
                 x - 20 + rng.get_random(40) as i32,
                 y - 15 + rng.get_random(30) as i32,
@@ -224,49 +437,113 @@ impl CityDisasters {
         }
 
         // report disaster to the user
-        // TODO: sendMessage(MESSAGE_NUCLEAR_MELTDOWN, position, true, true)
+        events.send_message(
+            GameMessage::MessageNuclearMeltdown,
+            Some(*position),
+            true,
+            true,
+        );
 
         Ok(())
     }
 
-    /// Let a fire bomb explode at a random location.
-    fn fire_bomb(rng: &mut MicropolisRandom, map: &TileMap) -> Result<(), String> {
+    /// Let a fire bomb explode at the given location.
+    fn fire_bomb(
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+        crash_position: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        Self::make_explosion(rng, map, sprites, crash_position)?;
+        events.send_message(
+            GameMessage::MessageFirebombing,
+            Some(*crash_position),
+            true,
+            true,
+        );
+        Ok(())
+    }
+
+    /// Start a fire bombing raid over the city.
+    ///
+    /// The first bomb falls right away, the other ones every few disaster ticks.
+    fn make_fire_bombs(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
+        self.fire_bombs_left += 2 + (rng.get_random_16() & 0x01) as u16;
+        self.fire_bomb_timer = 0;
+        self.drop_fire_bombs(rng, map, sprites, events)
+    }
+
+    /// Drop the next bomb of the current fire bombing raid, if it is time to.
+    fn drop_fire_bombs(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
+        if self.fire_bombs_left == 0 {
+            return Ok(());
+        }
+        if self.fire_bomb_timer > 0 {
+            self.fire_bomb_timer -= 1;
+            return Ok(());
+        }
+
         let bounds = map.bounds();
         let crash_position: MapPosition = (
             rng.get_random((bounds.get_width() - 1) as i16) as i32,
             rng.get_random((bounds.get_height() - 1) as i16) as i32,
         )
             .into();
-        Self::make_explosion(map, &crash_position)?;
-        // TODO: sendMessage(MESSAGE_FIREBOMBING, crash_position, true, true)
-        Ok(())
-    }
-
-    /// Throw several bombs onto the city.
-    fn make_fire_bombs(rng: &mut MicropolisRandom, map: &TileMap) -> Result<(), String> {
-        let mut count = 2 + (rng.get_random_16() & 0x01);
-        while count > 0 {
-            Self::fire_bomb(rng, map)?;
-            count -= 1;
-        }
-
-        // TODO: schedule periodic fire bombs over time, every few ticks
+        Self::fire_bomb(rng, map, sprites, events, &crash_position)?;
+        self.fire_bombs_left -= 1;
+        self.fire_bomb_timer = FIRE_BOMB_INTERVAL;
         Ok(())
     }
 
     /// Tell the front-end to show an earthquake to the user
     /// (shaking the map for some time).
-    fn do_earthquake(strength: i16) {
-        // TODO: makeSound("city", "ExplosionLow")
-        // TODO: callback("startEarthquake", "d", strength)
+    fn do_earthquake(events: &mut CityEvents, strength: i16) {
+        events.make_sound(SoundChannel::City, GameSound::ExplosionLow, None);
+        events.callback(UiCallback::StartEarthquake { strength });
     }
 
-    /// Change random tiles to fire or dirt as a result of the earthquake.
-    fn make_earthquake(rng: &mut MicropolisRandom, map: &mut TileMap) -> Result<(), String> {
-        let bounds = map.bounds();
+    /// Make an earthquake of random strength shake the city.
+    fn make_earthquake(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+        city_center: &MapPosition,
+    ) -> Result<(), MicropolisError> {
         let strength = rng.get_random(700) + 300;
-        Self::do_earthquake(strength);
-        // TODO: sendMessage(MESSAGE_EARTHQUAKE, city_center, true)
+        Self::earthquake(rng, map, events, city_center, strength)
+    }
+
+    /// Change random tiles to fire or rubble as a result of the earthquake.
+    ///
+    /// The stronger the earthquake, the more tiles get hit.
+    fn earthquake(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+        city_center: &MapPosition,
+        strength: i16,
+    ) -> Result<(), MicropolisError> {
+        let bounds = map.bounds();
+        Self::do_earthquake(events, strength);
+        events.send_message(
+            GameMessage::MessageEarthquake,
+            Some(*city_center),
+            true,
+            false,
+        );
         for z in 0..strength {
             let position: MapPosition = (
                 rng.get_random((bounds.get_width() - 1) as i16) as i32,
@@ -293,35 +570,43 @@ impl CityDisasters {
     }
 
     /// Start a fire at a random place, random disaster or scenario.
-    fn set_fire(rng: &mut MicropolisRandom, map: &mut TileMap) -> Result<(), String> {
+    fn set_fire(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
         let at = MapPosition::new_random(rng, &map.bounds());
         if let Some(tile) = map.get_tile_at(&at) {
-            let mut z = tile.get_raw() & TILE_ZONE_BIT;
-            if z != 0x00 {
+            let raw = tile.get_raw();
+            if raw & TILE_ZONE_BIT != 0x00 {
                 return Ok(());
             }
-            z = z & TILE_LOW_MASK;
+            let z = raw & TILE_LOW_MASK;
             if z > TileType::House.to_u16().unwrap() && z < TileType::LastZone.to_u16().unwrap() {
                 map.set_tile_at(&at, Tile::from_raw(Self::random_fire(rng))?);
-                // TODO: sendMessage(MESSAGE_FIRE_REPORTED, at, true)
+                events.send_message(GameMessage::MessageFireReported, Some(at), true, false);
             }
         }
         Ok(())
     }
 
     /// Start a fire at a random place, requested by the user.
-    fn make_fire(rng: &mut MicropolisRandom, map: &mut TileMap) -> Result<(), String> {
-        for t in 0..40 {
+    fn make_fire(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
+        for _ in 0..40 {
             let at = MapPosition::new_random(rng, &map.bounds());
             if let Some(tile) = map.get_tile_at(&at) {
                 let mut z = tile.get_raw();
-                if z & TILE_ZONE_BIT == 0x00 || z & TILE_BURN_BIT == 0x00 {
-                    return Ok(());
+                if z & TILE_ZONE_BIT != 0x00 || z & TILE_BURN_BIT == 0x00 {
+                    continue;
                 }
                 z = z & TILE_LOW_MASK;
                 if z > 21 && z < TileType::LastZone.to_u16().unwrap() {
                     map.set_tile_at(&at, Tile::from_raw(Self::random_fire(rng))?);
-                    // TODO: sendMessage(MESSAGE_FIRE_REPORTED, at, true)
+                    events.send_message(GameMessage::MessageFireReported, Some(at), true, false);
                     return Ok(());
                 }
             }
@@ -329,13 +614,39 @@ impl CityDisasters {
         Ok(())
     }
 
-    /// Flood around the given position.
+    /// Start a fire at the given position, failing if the tile there cannot burn.
+    fn start_fire_at(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        let raw = map
+            .get_tile_at(at)
+            .ok_or(MicropolisError::OutOfBounds(*at))?
+            .get_raw();
+        if raw & TILE_ZONE_BIT != 0x00 || raw & TILE_BURN_BIT == 0x00 {
+            return Err(MicropolisError::InvalidArgument(format!(
+                "no burnable tile at ({}, {})",
+                at.get_x(),
+                at.get_y()
+            )));
+        }
+        map.set_tile_at(at, Tile::from_raw(Self::random_fire(rng))?);
+        events.send_message(GameMessage::MessageFireReported, Some(*at), true, false);
+        Ok(())
+    }
+
+    /// Flood around the given position, destroying the zones in the way.
+    ///
+    /// See `Micropolis::doFlood` in the C++ code.
     pub fn do_flood(
         &mut self,
         rng: &mut MicropolisRandom,
         map: &mut TileMap,
+        rate_of_growth: &mut Map<i16>,
         at: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         if self.flood_count > 0 {
             // flood is not over yet
             for z in 0..4 {
@@ -344,7 +655,7 @@ impl CityDisasters {
                 }
                 // 12.5% chance
                 let current_position = *at + (FLOOD_DX[z], FLOOD_DY[z]).into();
-                if let Some(tile) = map.get_tile_at(&current_position) {
+                if let Some(tile) = map.get_tile_at(&current_position).cloned() {
                     let c = tile.get_raw();
                     let t = c & TILE_LOW_MASK;
                     if c & TILE_BURN_BIT == TILE_BURN_BIT
@@ -352,7 +663,9 @@ impl CityDisasters {
                         || (t >= TileType::Woods5.to_u16().unwrap()
                             && t < TileType::Flood.to_u16().unwrap())
                     {
-                        if c & TILE_ZONE_BIT == TILE_ZONE_BIT {}
+                        if c & TILE_ZONE_BIT == TILE_ZONE_BIT {
+                            Simulation::fire_zone(rate_of_growth, map, &current_position, &tile)?;
+                        }
                         map.set_tile_at(
                             &current_position,
                             Tile::from_raw(
@@ -372,15 +685,17 @@ impl CityDisasters {
 
     /// Flood many tiles.
     /// TODO: use direction and some form of XYPosition class here
-    fn make_flood(&mut self, rng: &mut MicropolisRandom, map: &mut TileMap) -> Result<(), String> {
-        for z in 0..300 {
+    fn make_flood(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+    ) -> Result<(), MicropolisError> {
+        for _ in 0..300 {
             let at = MapPosition::new_random(rng, &map.bounds());
-            let mut c = map
+            let c = map
                 .get_tile_at(&at)
-                .ok_or(format!(
-                    "CityDisasters::make_flood cannot get tile at {}",
-                    at
-                ))
+                .ok_or(MicropolisError::OutOfBounds(at))
                 .map(|t| t.get_raw() & TILE_LOW_MASK)?;
             if c <= TileType::Channel.to_u16().unwrap()
                 || c > TileType::LastRiverEdge.to_u16().unwrap()
@@ -393,7 +708,12 @@ impl CityDisasters {
                     if tile.is_floodable() {
                         map.set_tile_at(&current_position, Tile::from_type(TileType::Flood)?);
                         self.flood_count = 30;
-                        // TODO: sendMessage(MESSAGE_FLOODING_REPORTED, current_position, true)
+                        events.send_message(
+                            GameMessage::MessageFloodingReported,
+                            Some(current_position),
+                            true,
+                            false,
+                        );
                         return Ok(());
                     }
                 }
@@ -402,16 +722,69 @@ impl CityDisasters {
         Ok(())
     }
 
-    /// Construct an explosion sprite at the given position.
-    fn make_explosion(map: &TileMap, at: &MapPosition) -> Result<(), String> {
-        todo!()
-        // if !map.in_bounds(at) {
-        //     return;
-        // }
-        // Self::make_explosion_at(&MapPosition::new(
-        //     (at.get_x() << 4) + 8,
-        //     (at.get_y() << 4) + 8,
-        // ))
+    /// Flood the given position, failing if the tile there cannot be flooded.
+    fn flood_at(
+        &mut self,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        if !map.get_tile_at(at).is_some_and(Tile::is_floodable) {
+            return Err(MicropolisError::InvalidArgument(format!(
+                "no floodable tile at ({}, {})",
+                at.get_x(),
+                at.get_y()
+            )));
+        }
+        map.set_tile_at(at, Tile::from_type(TileType::Flood)?);
+        self.flood_count = 30;
+        events.send_message(GameMessage::MessageFloodingReported, Some(*at), true, false);
+        Ok(())
+    }
+
+    /// Crash an airplane at the given position or where it currently is,
+    /// making a new one first if needed.
+    ///
+    /// See `Micropolis::makeAirCrash` in the C++ code.
+    fn make_air_crash(
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+        at: Option<&MapPosition>,
+    ) -> Result<(), MicropolisError> {
+        if sprites.get_sprite(&SpriteType::Airplane).is_none() {
+            let bounds = map.bounds();
+            let position: MapPosition = (
+                rng.get_random((bounds.get_width() - 1) as i16) as i32,
+                rng.get_random((bounds.get_height() - 1) as i16) as i32,
+            )
+                .into();
+            make_sprite(
+                rng,
+                sprites,
+                &SpriteType::Airplane,
+                &((position << 4) + (8, 8).into()),
+            )?;
+        }
+        if let (Some(at), Some(airplane)) = (at, sprites.get_sprite_mut(&SpriteType::Airplane)) {
+            airplane.position = (*at << 4) + (8, 8).into() - airplane.hot_offset;
+        }
+        sprites.explode_sprite(rng, events, &SpriteType::Airplane)?;
+        Ok(())
+    }
+
+    /// Construct an explosion sprite at the given map position.
+    fn make_explosion(
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        sprites: &mut ActiveSpritesList,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        if !map.in_bounds(at) {
+            return Ok(());
+        }
+        Self::make_explosion_at(rng, sprites, &((*at << 4) + (8, 8).into()))
     }
 
     /// Construct an explosion sprite.
@@ -419,7 +792,7 @@ impl CityDisasters {
         rng: &mut MicropolisRandom,
         sprites: &mut ActiveSpritesList,
         at: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         sprites.add_sprite(Sprite::new(
             rng,
             "".into(),
@@ -430,7 +803,7 @@ impl CityDisasters {
         Ok(())
     }
 
-    fn random_fire(rng: &mut MicropolisRandom) -> u16 {
+    pub(crate) fn random_fire(rng: &mut MicropolisRandom) -> u16 {
         TileType::Fire.to_u16().unwrap() + ((rng.get_random_16() & 0x07) as u16 | TILE_ANIM_BIT)
     }
 
@@ -438,3 +811,466 @@ impl CityDisasters {
         TileType::Rubble.to_u16().unwrap() + ((rng.get_random_16() & 0x03) as u16 | TILE_BULL_BIT)
     }
 }
+
+impl StableHash for ScheduledDisaster {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(self.kind as u8);
+        hasher.write_u32(self.city_time);
+        self.at.stable_hash(hasher);
+    }
+}
+
+impl StableHash for CityDisasters {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_i16(self.flood_count);
+        hasher.write_u8(self.disaster_event.clone() as u8);
+        hasher.write_u16(self.disaster_timer);
+        hasher.write_bool(self.disasters_enabled);
+        self.scheduled.stable_hash(hasher);
+        hasher.write_u16(self.fire_bombs_left);
+        hasher.write_u16(self.fire_bomb_timer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityDisasters, DisasterEnvironment, DisasterKind};
+    use crate::{
+        city::{
+            events::{CityEvents, GameEvent, MessageEvent},
+            simulation::Simulation,
+            sprite::{ActiveSpritesList, SpriteType},
+        },
+        error::MicropolisError,
+        game::{GameLevelDifficulty, GameScenario},
+        map::{
+            tiles::{TILE_BULL_BIT, TILE_BURN_BIT, TILE_LOW_MASK, TILE_ZONE_BIT},
+            Map, MapPosition, MapRectangle, Tile, TileType,
+        },
+        messages::GameMessage,
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_disaster_control() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let woods = TileType::Woods.to_u16().unwrap() | TILE_BURN_BIT | TILE_BULL_BIT;
+        map.set_tile_at(&(10, 10).into(), Tile::from_raw(woods).unwrap());
+        let mut sprites = ActiveSpritesList::new();
+        let mut events = CityEvents::new();
+        let mut rng = seeded_rng(1234);
+        let mut disasters = CityDisasters::new(&GameScenario::None);
+        let center = MapPosition::new(60, 50);
+        let mut environment = DisasterEnvironment {
+            map: &mut map,
+            sprites: &mut sprites,
+            events: &mut events,
+            maximum_pollution_at: center,
+            city_center: center,
+        };
+
+        disasters
+            .trigger_disaster(
+                &mut rng,
+                &mut environment,
+                DisasterKind::Fire,
+                Some(&(10, 10).into()),
+            )
+            .unwrap();
+        let fire = environment.map.get_tile_char_at(&(10, 10).into()).unwrap();
+        assert!(
+            fire >= TileType::Fire.to_u16().unwrap()
+                && fire <= TileType::LastFire.to_u16().unwrap()
+        );
+
+        disasters
+            .trigger_disaster(
+                &mut rng,
+                &mut environment,
+                DisasterKind::Monster,
+                Some(&(30, 30).into()),
+            )
+            .unwrap();
+        let monster = environment
+            .sprites
+            .get_sprite(&SpriteType::Monster)
+            .unwrap();
+        assert_eq!(monster.destination, center << 4);
+
+        disasters
+            .trigger_disaster(
+                &mut rng,
+                &mut environment,
+                DisasterKind::PlaneCrash,
+                Some(&(40, 40).into()),
+            )
+            .unwrap();
+        assert!(environment
+            .sprites
+            .get_sprite(&SpriteType::Airplane)
+            .is_none());
+        assert!(environment
+            .sprites
+            .get_sprite(&SpriteType::Explosion)
+            .is_some());
+
+        // the given position must be fit for the disaster
+        for (kind, at) in [
+            (DisasterKind::Fire, (11, 11)),
+            (DisasterKind::Meltdown, (70, 70)),
+            (DisasterKind::Flood, (120, 0)),
+        ] {
+            let snapshot = environment.map.clone();
+            assert!(disasters
+                .trigger_disaster(&mut rng, &mut environment, kind, Some(&at.into()))
+                .is_err());
+            assert_eq!(*environment.map, snapshot);
+        }
+        // an in-bounds tile that cannot be flooded
+        environment
+            .map
+            .set_tile_at(&(12, 12).into(), Tile::from_type(TileType::River).unwrap());
+        let flood_count = disasters.get_flood_count();
+        assert!(matches!(
+            disasters.trigger_disaster(
+                &mut rng,
+                &mut environment,
+                DisasterKind::Flood,
+                Some(&(12, 12).into())
+            ),
+            Err(MicropolisError::InvalidArgument(_))
+        ));
+        assert_eq!(
+            environment.map.get_tile_at(&(12, 12).into()),
+            Some(&Tile::from_type(TileType::River).unwrap())
+        );
+        assert_eq!(disasters.get_flood_count(), flood_count);
+
+        let messages: Vec<GameMessage> = environment
+            .events
+            .drain()
+            .into_iter()
+            .filter_map(|event| match event {
+                GameEvent::Message(message) => Some(message.message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                GameMessage::MessageFireReported,
+                GameMessage::MessageMonsterSighted,
+                GameMessage::MessagePlaneCrashed,
+            ]
+        );
+
+        // no random disasters, but scheduled ones still happen
+        disasters.set_disasters_enabled(false);
+        disasters.schedule_disaster(DisasterKind::Flood, 500, Some((20, 20).into()));
+        let snapshot = environment.map.clone();
+        for city_time in 0..500 {
+            disasters
+                .do_disasters(
+                    &mut rng,
+                    &mut environment,
+                    &GameLevelDifficulty::Hard,
+                    &GameScenario::None,
+                    100,
+                    city_time,
+                )
+                .unwrap();
+        }
+        assert_eq!(*environment.map, snapshot);
+        assert!(environment.events.is_empty());
+        assert_eq!(disasters.get_scheduled_disasters().len(), 1);
+
+        disasters
+            .do_disasters(
+                &mut rng,
+                &mut environment,
+                &GameLevelDifficulty::Hard,
+                &GameScenario::None,
+                100,
+                500,
+            )
+            .unwrap();
+        assert!(disasters.get_scheduled_disasters().is_empty());
+        assert_eq!(
+            environment.map.get_tile_char_at(&(20, 20).into()),
+            TileType::Flood.to_u16()
+        );
+    }
+
+    fn seeded_rng(seed: i32) -> MicropolisRandom {
+        MicropolisRandom::from_seed(seed)
+    }
+
+    fn drain_messages(events: &mut CityEvents) -> Vec<MessageEvent> {
+        events
+            .drain()
+            .into_iter()
+            .filter_map(|event| match event {
+                GameEvent::Message(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn tick(
+        disasters: &mut CityDisasters,
+        rng: &mut MicropolisRandom,
+        map: &mut Map<Tile>,
+        sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
+        city_time: u32,
+    ) {
+        let center = MapPosition::new(60, 50);
+        let mut environment = DisasterEnvironment {
+            map,
+            sprites,
+            events,
+            maximum_pollution_at: center,
+            city_center: center,
+        };
+        disasters
+            .do_disasters(
+                rng,
+                &mut environment,
+                &GameLevelDifficulty::Easy,
+                &GameScenario::None,
+                0,
+                city_time,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_meltdown_picks_a_random_plant() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let plants: [MapPosition; 2] = [(20, 20).into(), (80, 60).into()];
+        for plant in plants.iter() {
+            let nuclear = TileType::Nuclear.to_u16().unwrap() | TILE_ZONE_BIT;
+            map.set_tile_at(plant, Tile::from_raw(nuclear).unwrap());
+        }
+
+        let mut melted = vec![];
+        for seed in 0..16 {
+            let mut rng = seeded_rng(seed);
+            let mut map = map.clone();
+            let mut sprites = ActiveSpritesList::new();
+            let mut events = CityEvents::new();
+            CityDisasters::new(&GameScenario::None)
+                .trigger_disaster(
+                    &mut rng,
+                    &mut DisasterEnvironment {
+                        map: &mut map,
+                        sprites: &mut sprites,
+                        events: &mut events,
+                        maximum_pollution_at: plants[0],
+                        city_center: plants[0],
+                    },
+                    DisasterKind::Meltdown,
+                    None,
+                )
+                .unwrap();
+
+            let messages = drain_messages(&mut events);
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].message, GameMessage::MessageNuclearMeltdown);
+            let plant = messages[0].position.unwrap();
+            assert!(plants.contains(&plant));
+            let fire = map.get_tile_char_at(&plant).unwrap();
+            assert!(
+                fire >= TileType::Fire.to_u16().unwrap()
+                    && fire <= TileType::LastFire.to_u16().unwrap()
+            );
+            assert!(sprites.get_sprite(&SpriteType::Explosion).is_some());
+            melted.push(plant);
+        }
+        assert!(plants.iter().all(|plant| melted.contains(plant)));
+    }
+
+    #[test]
+    fn test_hamburg_fire_bombing() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let mut sprites = ActiveSpritesList::new();
+        let mut events = CityEvents::new();
+        let mut rng = seeded_rng(42);
+        let mut disasters = CityDisasters::new(&GameScenario::Hamburg);
+        disasters.set_disasters_enabled(false);
+
+        let mut bombs_per_tick = vec![];
+        for city_time in 0..50 {
+            tick(
+                &mut disasters,
+                &mut rng,
+                &mut map,
+                &mut sprites,
+                &mut events,
+                city_time,
+            );
+            let messages = drain_messages(&mut events);
+            assert!(messages
+                .iter()
+                .all(|message| message.message == GameMessage::MessageFirebombing));
+            bombs_per_tick.push(messages.len());
+        }
+
+        // 5 raids of 2 or 3 bombs each, one bomb at a time
+        let bombs: usize = bombs_per_tick.iter().sum();
+        assert!((10..=15).contains(&bombs), "{} bombs", bombs);
+        assert!(bombs_per_tick.iter().all(|count| *count <= 1));
+        assert_eq!(bombs_per_tick[0], 1);
+        assert_eq!(bombs_per_tick[1], 0);
+        assert_eq!(bombs_per_tick[3], 1);
+        assert_eq!(disasters.get_fire_bombs_left(), 0);
+        assert_eq!(disasters.get_disaster_event(), &GameScenario::None);
+    }
+
+    #[test]
+    fn test_earthquake_strength() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let house = TileType::ResidentialBase.to_u16().unwrap() + 1;
+        let bounds = map.bounds();
+        for x in 0..bounds.get_width() {
+            for y in 0..bounds.get_height() {
+                map.set_tile_at(
+                    &(x, y).into(),
+                    Tile::from_raw(house | TILE_BURN_BIT | TILE_BULL_BIT).unwrap(),
+                );
+            }
+        }
+        let center = MapPosition::new(60, 50);
+        let zone_center = Tile::from_raw(house | TILE_ZONE_BIT).unwrap();
+        map.set_tile_at(&center, zone_center.clone());
+
+        let hits = |strength: i16| {
+            let mut map = map.clone();
+            let mut events = CityEvents::new();
+            CityDisasters::earthquake(&mut seeded_rng(7), &mut map, &mut events, &center, strength)
+                .unwrap();
+            assert_eq!(
+                drain_messages(&mut events)[0].message,
+                GameMessage::MessageEarthquake
+            );
+            assert_eq!(map.get_tile_at(&center), Some(&zone_center));
+            map.tiles()
+                .iter()
+                .flatten()
+                .filter(|tile| tile.get_raw() & TILE_LOW_MASK != house)
+                .count()
+        };
+        let weak = hits(100);
+        let strong = hits(1000);
+        assert!(weak > 0 && weak <= 100);
+        assert!(strong > 5 * weak, "{} vs. {}", strong, weak);
+    }
+
+    #[test]
+    fn test_flood_destroys_zones() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let residential = TileType::ResidentialBase.to_u16().unwrap();
+        let zone_center: MapPosition = (21, 20).into();
+        for x in 21..24 {
+            for y in 20..23 {
+                map.set_tile_at(&(x, y).into(), Tile::from_raw(residential).unwrap());
+            }
+        }
+        map.set_tile_at(
+            &zone_center,
+            Tile::from_raw(residential | TILE_ZONE_BIT | TILE_BURN_BIT).unwrap(),
+        );
+        let mut rate_of_growth = Simulation::new(&map).get_rate_of_growth_map().clone();
+        let mut rng = seeded_rng(1234);
+        let mut disasters = CityDisasters::new(&GameScenario::None);
+        disasters.flood_count = 30;
+
+        let flood = TileType::Flood.to_u16().unwrap()..=TileType::LastFlood.to_u16().unwrap();
+        let at = MapPosition::new(20, 20);
+        for _ in 0..100 {
+            disasters
+                .do_flood(&mut rng, &mut map, &mut rate_of_growth, &at)
+                .unwrap();
+            if flood.contains(&(map.get_tile_at(&zone_center).unwrap().get_raw() & TILE_LOW_MASK)) {
+                break;
+            }
+        }
+        assert!(flood.contains(&(map.get_tile_at(&zone_center).unwrap().get_raw() & TILE_LOW_MASK)));
+        // the rest of the zone is left in ruins
+        assert_eq!(rate_of_growth.get_tile_at(&zone_center), Some(&-20));
+        let ruin = map.get_tile_at(&(22, 21).into()).unwrap().get_raw();
+        assert_eq!(ruin, residential | TILE_BULL_BIT);
+    }
+
+    #[test]
+    fn test_flood_recedes() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(40, 40), TileType::Dirt).unwrap();
+        let mut sprites = ActiveSpritesList::new();
+        let mut events = CityEvents::new();
+        let mut rng = seeded_rng(1234);
+        let mut disasters = CityDisasters::new(&GameScenario::None);
+        disasters.set_disasters_enabled(false);
+        let center = MapPosition::new(20, 20);
+        disasters
+            .trigger_disaster(
+                &mut rng,
+                &mut DisasterEnvironment {
+                    map: &mut map,
+                    sprites: &mut sprites,
+                    events: &mut events,
+                    maximum_pollution_at: center,
+                    city_center: center,
+                },
+                DisasterKind::Flood,
+                Some(&center),
+            )
+            .unwrap();
+        assert_eq!(disasters.get_flood_count(), 30);
+
+        let flood = TileType::Flood.to_u16().unwrap()..=TileType::LastFlood.to_u16().unwrap();
+        let mut rate_of_growth = Simulation::new(&map).get_rate_of_growth_map().clone();
+        let mut flooded = 1;
+        let mut largest_flood = 0;
+        let mut spreading = true;
+        for city_time in 0..1000 {
+            let flood_tiles: Vec<MapPosition> = (0..40)
+                .flat_map(|x| (0..40).map(move |y| MapPosition::new(x, y)))
+                .filter(|position| {
+                    flood.contains(&(map.get_tile_at(position).unwrap().get_raw() & TILE_LOW_MASK))
+                })
+                .collect();
+            if spreading {
+                // the flood spreads...
+                assert!(flood_tiles.len() >= flooded);
+            } else {
+                // ...then recedes
+                assert!(flood_tiles.len() <= flooded);
+            }
+            flooded = flood_tiles.len();
+            largest_flood = largest_flood.max(flooded);
+            spreading = disasters.get_flood_count() > 0;
+            for position in flood_tiles.iter() {
+                disasters
+                    .do_flood(&mut rng, &mut map, &mut rate_of_growth, position)
+                    .unwrap();
+            }
+            tick(
+                &mut disasters,
+                &mut rng,
+                &mut map,
+                &mut sprites,
+                &mut events,
+                city_time,
+            );
+        }
+        assert!(largest_flood > 1);
+        assert_eq!(disasters.get_flood_count(), 0);
+        assert_eq!(flooded, 0);
+    }
+}
@@ -1,5 +1,7 @@
-use std::collections::HashMap;
-use std::{cmp, thread::current};
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
     city::simulation::parameters::{MAX_FIRE_EFFECT, MAX_ROAD_EFFECT},
@@ -8,6 +10,7 @@ use crate::{
 };
 
 use super::{
+    budget::MoneyValue,
     population::CityClass,
     power::CityPower,
     simulation::{
@@ -25,8 +28,11 @@ const COMPLAINTS_COUNT: u8 = 4;
 const PROBLEMS_COUNT: u8 = 7;
 const PROBLEMS_NUMBER: u8 = 10;
 
+/// Number of yearly evaluation reports kept in the history.
+const EVALUATION_HISTORY_LENGTH: usize = 120;
+
 /// Problems in the city where citizens vote on.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CityVotingProblem {
     Crime = 0,
     Pollution = 1,
@@ -79,7 +85,7 @@ pub const CITY_VOTING_PROBLEMS: [CityVotingProblem; PROBLEMS_COUNT as usize] = [
 ];
 
 /// City score.
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct CityScore {
     /// Affected by average of problems, residential cap, commercial cap,
     /// industrial cap, road effect, police effect, fire effect,
@@ -88,11 +94,50 @@ pub struct CityScore {
     /// zones.
     current: u16,
     /// Change in city score.
-    delta: u16,
+    delta: i16,
+}
+
+impl CityScore {
+    pub fn get_current(&self) -> u16 {
+        self.current
+    }
+    pub fn get_delta(&self) -> i16 {
+        self.delta
+    }
+}
+
+/// Number of citizens who voted for one of the problems of the city.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemVotes {
+    pub problem: CityVotingProblem,
+    pub votes: u16,
+}
+
+/// Evaluation of the city, as shown in the evaluation window.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CityEvaluationReport {
+    /// City time at which the evaluation was made.
+    pub city_time: u32,
+    /// City score, from 0 to 1000.
+    pub score: u16,
+    /// Change in city score since the previous evaluation.
+    pub score_delta: i16,
+    /// Percentage of citizens who think the mayor is doing a good job.
+    pub approval_rate: u8,
+    /// Worst problems of the city, in decreasing order of priority.
+    pub problems: Vec<ProblemVotes>,
+    /// City assessed value.
+    pub assessed_value: MoneyValue,
+    /// City population.
+    pub population: i64,
+    /// Change in city population since the previous evaluation.
+    pub population_delta: i64,
+    /// City class, affected by the city population.
+    pub class: CityClass,
 }
 
 /// City population for scoring.
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct CityScoringPopulation {
     /// Depends on residential, commercial and industrial populations.
     current: u16,
@@ -100,6 +145,7 @@ pub struct CityScoringPopulation {
     delta: u16,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityEvaluator {
     /// City score.
     ///
@@ -117,7 +163,7 @@ pub struct CityEvaluator {
     // Depends on total number of roads, rails, police stations,
     // fire stations, hospitals, stadiums, seaports, airports,
     // coal and nuclear plants.
-    assessed_value: u32,
+    assessed_value: MoneyValue,
     /// City class, affected by the city population.
     class: CityClass,
     /// Average traffic.
@@ -132,6 +178,8 @@ pub struct CityEvaluator {
     mayor_approval_rate: Percentage,
     /// Should the evaluation window be shown to the user?
     evaluation_updated: bool,
+    /// Yearly evaluation reports, oldest first.
+    reports: VecDeque<CityEvaluationReport>,
 }
 
 impl CityEvaluator {
@@ -146,9 +194,77 @@ impl CityEvaluator {
             problems_order: [None, None, None, None],
             mayor_approval_rate: Percentage::from_integer(0).unwrap(),
             evaluation_updated: false,
+            reports: VecDeque::new(),
+        }
+    }
+
+    pub fn get_score(&self) -> &CityScore {
+        &self.score
+    }
+
+    pub fn get_class(&self) -> &CityClass {
+        &self.class
+    }
+
+    pub fn get_traffic_average(&self) -> u16 {
+        self.traffic_average
+    }
+
+    pub fn get_assessed_value(&self) -> MoneyValue {
+        self.assessed_value
+    }
+
+    /// Percentage of people who think the mayor is doing a good job, from 0 to 100.
+    pub fn get_mayor_approval_rate(&self) -> u8 {
+        (self.mayor_approval_rate.value() * 100.0).round() as u8
+    }
+
+    /// Worst problems of the city with their number of votes, in decreasing order of priority.
+    pub fn get_problems(&self) -> Vec<ProblemVotes> {
+        (0..COMPLAINTS_COUNT)
+            .filter_map(|priority_index| {
+                Some(ProblemVotes {
+                    problem: self.get_problem_from_priority(priority_index)?.clone(),
+                    votes: self.get_problem_votes(priority_index)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Report of the last evaluation.
+    pub fn get_report(&self, city_time: u32, population: &CityPopulation) -> CityEvaluationReport {
+        CityEvaluationReport {
+            city_time,
+            score: self.score.current,
+            score_delta: self.score.delta,
+            approval_rate: self.get_mayor_approval_rate(),
+            problems: self.get_problems(),
+            assessed_value: self.assessed_value,
+            population: population.total_population(),
+            population_delta: population.delta_population(),
+            class: self.class.clone(),
         }
     }
 
+    /// Yearly evaluation reports, oldest first.
+    pub fn get_reports(&self) -> &VecDeque<CityEvaluationReport> {
+        &self.reports
+    }
+
+    /// Report of the last recorded evaluation, if any.
+    pub fn get_last_report(&self) -> Option<&CityEvaluationReport> {
+        self.reports.back()
+    }
+
+    /// Add the report of the last evaluation to the history.
+    pub fn record_report(&mut self, city_time: u32, population: &CityPopulation) {
+        if self.reports.len() >= EVALUATION_HISTORY_LENGTH {
+            self.reports.pop_front();
+        }
+        self.reports
+            .push_back(self.get_report(city_time, population));
+    }
+
     /// Initialize evaluation variables.
     fn init(&mut self) {
         self.score = CityScore {
@@ -187,7 +303,11 @@ impl CityEvaluator {
         power: &CityPower,
         traffic: &CityTraffic,
     ) {
-        if population.total_population() > 0 {
+        let normalized_population = population.get_residential() as u32
+            / parameters.get_residential_population_denominator()
+            + population.get_commercial() as u32
+            + population.get_industrial() as u32;
+        if normalized_population > 0 {
             let mut problems_table = HashMap::with_capacity(PROBLEMS_NUMBER as usize);
             for z in 0..PROBLEMS_NUMBER {
                 problems_table.insert(z, 0u16);
@@ -226,17 +346,20 @@ impl CityEvaluator {
     }
 
     /// Access the value of the city.
-    pub fn compute_accessed_value(power: &CityPower, statistics: &SimulationStatistics) -> u32 {
-        let mut z = statistics.road_total as u32 * 5;
-        z += (statistics.rail_total as u32) * 10;
-        z += (statistics.police_station_count as u32) * 1000;
-        z += (statistics.fire_station_count as u32) * 1000;
-        z += (statistics.hospital_count as u32) * 400;
-        z += (statistics.stadium_count as u32) * 3000;
-        z += (statistics.seaport_count as u32) * 5000;
-        z += (statistics.airport_count as u32) * 10000;
-        z += (power.coal_generators_count as u32) * 3000;
-        z += (power.nuclear_generators_count as u32) * 6000;
+    pub fn compute_accessed_value(
+        power: &CityPower,
+        statistics: &SimulationStatistics,
+    ) -> MoneyValue {
+        let mut z = statistics.road_total as MoneyValue * 5;
+        z += (statistics.rail_total as MoneyValue) * 10;
+        z += (statistics.police_station_count as MoneyValue) * 1000;
+        z += (statistics.fire_station_count as MoneyValue) * 1000;
+        z += (statistics.hospital_count as MoneyValue) * 400;
+        z += (statistics.stadium_count as MoneyValue) * 3000;
+        z += (statistics.seaport_count as MoneyValue) * 5000;
+        z += (statistics.airport_count as MoneyValue) * 10000;
+        z += (power.coal_generators_count as MoneyValue) * 3000;
+        z += (power.nuclear_generators_count as MoneyValue) * 6000;
 
         z * 1000
     }
@@ -312,15 +435,15 @@ impl CityEvaluator {
             sm = 1.0;
         } else if delta_population == population {
             // city sprang into existence or doubled in size
-            sm == 1.0;
+            sm = 1.0;
         } else if delta_population > 0 {
             sm = 1.0 + delta_population as f64 / population as f64;
         } else if delta_population < 0 {
-            sm = 0.95 + population as f64 / (population as f64 - delta_population as f64);
+            sm = 0.95 + delta_population as f64 / (population as f64 - delta_population as f64);
         }
         partial_score = ((partial_score as f64) * sm).floor() as i32;
 
-        partial_score -= Self::get_fire_severity(statistics) as i32 - taxes.city_tax as i32; // fires and taxes decrease the score
+        partial_score -= Self::get_fire_severity(statistics) as i32 + taxes.city_tax as i32; // fires and taxes decrease the score
 
         let tm = power.get_unpowered_zone_count() + power.get_powered_zone_count(); // decreasing score for unpowered zones
         if tm > 0 {
@@ -331,11 +454,10 @@ impl CityEvaluator {
 
         partial_score = clamp(partial_score, 0, 1000);
 
-        let last_city_score = self.score.current;
-        let city_score = ((self.score.current as i32 + partial_score) / 2) as u16;
+        let city_score = ((last_city_score as i32 + partial_score) / 2) as u16;
         CityScore {
             current: city_score,
-            delta: city_score - last_city_score,
+            delta: city_score as i16 - last_city_score as i16,
         }
     }
 
@@ -377,10 +499,8 @@ impl CityEvaluator {
             statistics.average_land_value * 7 / 10,
         );
         problems_table.insert(CityVotingProblem::Taxes as u8, taxes.city_tax * 10);
-        problems_table.insert(
-            CityVotingProblem::Traffic as u8,
-            Self::compute_traffic_average(land_value_map, traffic),
-        );
+        self.traffic_average = Self::compute_traffic_average(land_value_map, traffic);
+        problems_table.insert(CityVotingProblem::Traffic as u8, self.traffic_average);
         problems_table.insert(
             CityVotingProblem::Unemployment as u8,
             Self::compute_unemployment(population),
@@ -443,7 +563,7 @@ impl CityEvaluator {
             }
 
             voting_on_problem_number += 1;
-            if voting_on_problem_number > PROBLEMS_NUMBER {
+            if voting_on_problem_number >= PROBLEMS_NUMBER {
                 voting_on_problem_number = 0;
             }
 
@@ -462,8 +582,10 @@ impl CityEvaluator {
     }
 
     fn get_problem_from_priority(&self, priority_index: u8) -> Option<&CityVotingProblem> {
-        if priority_index >= COMPLAINTS_COUNT {
-            self.problems_order.get(priority_index as usize)
+        if priority_index < COMPLAINTS_COUNT {
+            self.problems_order
+                .get(priority_index as usize)
+                .filter(|problem| **problem != CityVotingProblem::None)
         } else {
             Option::None
         }
@@ -478,7 +600,7 @@ impl CityEvaluator {
 
     /// Compute the average traffic in the city.
     fn compute_traffic_average(land_value_map: &Map<u8>, traffic: &CityTraffic) -> u16 {
-        let mut traffic_total = 0;
+        let mut traffic_total: u32 = 0;
         let mut count = 1;
 
         let traffic_density_map = traffic.get_density_map();
@@ -488,7 +610,8 @@ impl CityEvaluator {
                 let position = (x, y).into();
                 if let Some(land_value) = land_value_map.get_tile_at(&position) {
                     if *land_value > 0 {
-                        traffic_total += traffic_density_map.get_tile_at(&position).unwrap_or(&0);
+                        traffic_total +=
+                            *traffic_density_map.get_tile_at(&position).unwrap_or(&0) as u32;
                         count += 1;
                     }
                 }
@@ -500,7 +623,7 @@ impl CityEvaluator {
 
     /// Compute the severity of unemployment in the city.
     fn compute_unemployment(population: &CityPopulation) -> u16 {
-        let b = (population.get_commercial() + population.get_industrial()) * 8;
+        let b = (population.get_commercial() as u32 + population.get_industrial() as u32) * 8;
         if b == 0 {
             return 0;
         }
@@ -511,8 +634,89 @@ impl CityEvaluator {
         cmp::min(unemployment, 255)
     }
 
-    /// Compute the severity of fire in the city.
+    /// Compute the severity of fire in the city, from its number of burning tiles.
     fn get_fire_severity(statistics: &SimulationStatistics) -> u16 {
-        cmp::min(statistics.fire_station_count * 5, 255)
+        cmp::min(statistics.fires_count.saturating_mul(5), 255)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CityEvaluationReport, CityEvaluator, CityVotingProblem, EVALUATION_HISTORY_LENGTH,
+    };
+    use crate::{
+        city::{population::CityClass, simulation::statistics::SimulationStatistics, City},
+        game::GameScenario,
+    };
+
+    /// Run the yearly evaluation of the simulation on the given city.
+    fn evaluate(city: &mut City) -> CityEvaluationReport {
+        city.sim.evaluate_city(
+            &mut city.rng,
+            &mut city.evaluator,
+            &mut city.population,
+            &city.power,
+            &city.traffic,
+        );
+        city.evaluator
+            .record_report(city.city_time, &city.population);
+        city.get_last_evaluation().unwrap().clone()
+    }
+
+    #[test]
+    fn test_empty_city_evaluation() {
+        let mut city = City::new("Evalville".into(), GameScenario::None).unwrap();
+        assert_eq!(city.get_last_evaluation(), None);
+        let report = evaluate(&mut city);
+        assert_eq!(report.score, 500);
+        assert_eq!(report.score_delta, 0);
+        assert_eq!(report.approval_rate, 50);
+        assert_eq!(report.population, 0);
+        assert_eq!(report.class, CityClass::Village);
+        assert!(report.problems.is_empty());
+    }
+
+    #[test]
+    fn test_city_evaluation_report() {
+        let mut city = City::new("Evalville".into(), GameScenario::None).unwrap();
+        city.rng.seed(42);
+        city.population.add_residential(800);
+        city.population.add_commercial(50);
+        city.population.add_industrial(50);
+
+        let report = evaluate(&mut city);
+        assert_eq!(report.population, (800 + (50 + 50) * 8) * 20);
+        assert_eq!(report.population_delta, report.population);
+        assert_eq!(report.class, CityClass::City);
+        assert!(report.score <= 1000);
+        assert!(report.approval_rate <= 100);
+        // taxes are the only problem of this city
+        assert_eq!(report.problems.len(), 1);
+        assert_eq!(report.problems[0].problem, CityVotingProblem::Taxes);
+        assert!(report.problems[0].votes > 0);
+        assert_eq!(city.get_evaluator().get_reports().len(), 1);
+
+        for city_time in 0..=EVALUATION_HISTORY_LENGTH as u32 {
+            city.evaluator.record_report(city_time, &city.population);
+        }
+        let reports = city.get_evaluator().get_reports();
+        assert_eq!(reports.len(), EVALUATION_HISTORY_LENGTH);
+        assert_eq!(reports.front().unwrap().city_time, 1);
+        assert_eq!(reports.back().unwrap().problems, report.problems);
+    }
+
+    #[test]
+    fn test_fire_severity() {
+        let mut statistics = SimulationStatistics::default();
+        assert_eq!(CityEvaluator::get_fire_severity(&statistics), 0);
+        statistics.fire_station_count = 10;
+        assert_eq!(CityEvaluator::get_fire_severity(&statistics), 0);
+        statistics.fires_count = 12;
+        assert_eq!(CityEvaluator::get_fire_severity(&statistics), 60);
+        statistics.fire_station_count = 30;
+        assert_eq!(CityEvaluator::get_fire_severity(&statistics), 60);
+        statistics.fires_count = 100;
+        assert_eq!(CityEvaluator::get_fire_severity(&statistics), 255);
     }
 }
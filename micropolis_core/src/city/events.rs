@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{map::MapPosition, messages::GameMessage};
+
+/// A message to display to the player.
+///
+/// See `Micropolis::sendMessage` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageEvent {
+    /// Message to display.
+    pub message: GameMessage,
+    /// Map position the message refers to, if any.
+    pub position: Option<MapPosition>,
+    /// Should the front-end show a picture with the message?
+    pub picture: bool,
+    /// Is the message important enough to interrupt the player?
+    pub important: bool,
+}
+
+/// Channel on which a sound is played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SoundChannel {
+    /// Sounds coming from the city itself.
+    City,
+    /// Feedback sounds of the user interface.
+    Interface,
+}
+
+/// Sound effects the front-end is asked to play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameSound {
+    ExplosionHigh,
+    ExplosionLow,
+    HeavyTraffic,
+    HonkHonkLow,
+    HonkHonkMedium,
+    HonkHonkHigh,
+    Monster,
+    Siren,
+    /// Tool cannot be applied here.
+    UhUh,
+    /// Not enough funds.
+    Sorry,
+}
+
+/// A sound to play.
+///
+/// See `Micropolis::makeSound` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoundEvent {
+    pub channel: SoundChannel,
+    pub sound: GameSound,
+    /// Map position the sound comes from, if any.
+    pub position: Option<MapPosition>,
+}
+
+/// Front-end effects requested by the simulation.
+///
+/// See `Micropolis::callback` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiCallback {
+    /// Shake the map for a duration depending on the earthquake strength.
+    StartEarthquake { strength: i16 },
+    /// A tool has been applied at the given position (`Micropolis::didTool`).
+    DidTool { name: String, position: MapPosition },
+}
+
+/// Something that happened in the city and that the front-end should know about.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    Message(MessageEvent),
+    Sound(SoundEvent),
+    Callback(UiCallback),
+}
+
+/// Queue of the events emitted by the city, waiting to be drained by the front-end.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CityEvents {
+    queue: VecDeque<GameEvent>,
+}
+
+impl CityEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Iterate over the pending events, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &GameEvent> {
+        self.queue.iter()
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.queue.push_back(event);
+    }
+
+    /// Queue a message for the player.
+    pub fn send_message(
+        &mut self,
+        message: GameMessage,
+        position: Option<MapPosition>,
+        picture: bool,
+        important: bool,
+    ) {
+        self.push(GameEvent::Message(MessageEvent {
+            message,
+            position,
+            picture,
+            important,
+        }));
+    }
+
+    /// Queue a sound to play.
+    pub fn make_sound(
+        &mut self,
+        channel: SoundChannel,
+        sound: GameSound,
+        position: Option<MapPosition>,
+    ) {
+        self.push(GameEvent::Sound(SoundEvent {
+            channel,
+            sound,
+            position,
+        }));
+    }
+
+    /// Queue a front-end callback.
+    pub fn callback(&mut self, callback: UiCallback) {
+        self.push(GameEvent::Callback(callback));
+    }
+
+    /// Move all the events of `other` at the end of this queue.
+    pub fn append(&mut self, other: &mut CityEvents) {
+        self.queue.append(&mut other.queue);
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Remove and return all the pending events, oldest first.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        self.queue.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityEvents, GameEvent, GameSound, MessageEvent, SoundChannel, UiCallback};
+    use crate::messages::GameMessage;
+
+    #[test]
+    fn test_events_queue() {
+        let mut events = CityEvents::new();
+        assert!(events.is_empty());
+
+        events.send_message(
+            GameMessage::MessageEarthquake,
+            Some((60, 50).into()),
+            true,
+            false,
+        );
+        events.make_sound(SoundChannel::City, GameSound::ExplosionLow, None);
+        events.callback(UiCallback::StartEarthquake { strength: 500 });
+        assert_eq!(events.len(), 3);
+
+        let drained = events.drain();
+        assert!(events.is_empty());
+        assert_eq!(
+            drained[0],
+            GameEvent::Message(MessageEvent {
+                message: GameMessage::MessageEarthquake,
+                position: Some((60, 50).into()),
+                picture: true,
+                important: false,
+            })
+        );
+        assert_eq!(
+            drained[2],
+            GameEvent::Callback(UiCallback::StartEarthquake { strength: 500 })
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::io::{Read, Write};
+
+use crate::{
+    error::MicropolisError,
+    map::{Map, MapClusteringStrategy, Tile, TileMap},
+};
+
+/// Number of entries of each census history stored in a city file.
+///
+/// The C++ code defines `HISTORY_LENGTH` as 480 bytes, i.e. 240 shorts.
+pub const CITY_FILE_HISTORY_LENGTH: usize = 240;
+/// Number of entries of the miscellaneous history stored in a city file.
+///
+/// The C++ code defines `MISC_HISTORY_LENGTH` as 240 bytes, i.e. 120 shorts.
+pub const CITY_FILE_MISC_HISTORY_LENGTH: usize = 120;
+/// Width of the map stored in a city file.
+pub const CITY_FILE_MAP_WIDTH: usize = 120;
+/// Height of the map stored in a city file.
+pub const CITY_FILE_MAP_HEIGHT: usize = 100;
+/// Size in bytes of a standard city file.
+pub const CITY_FILE_SIZE: usize = 2
+    * (6 * CITY_FILE_HISTORY_LENGTH
+        + CITY_FILE_MISC_HISTORY_LENGTH
+        + CITY_FILE_MAP_WIDTH * CITY_FILE_MAP_HEIGHT);
+/// Size in bytes of the resource header prepended by the original Mac version.
+pub const CITY_FILE_MAC_HEADER_SIZE: usize = 128;
+
+/// Miscellaneous history index of the city time (32 bits).
+const MISC_CITY_TIME: usize = 8;
+/// Miscellaneous history index of the total funds (32 bits).
+const MISC_TOTAL_FUNDS: usize = 50;
+/// Miscellaneous history index of the auto-bulldoze flag.
+const MISC_AUTO_BULLDOZE: usize = 52;
+/// Miscellaneous history index of the auto-budget flag.
+const MISC_AUTO_BUDGET: usize = 53;
+/// Miscellaneous history index of the auto-goto flag.
+const MISC_AUTO_GOTO: usize = 54;
+/// Miscellaneous history index of the sound flag.
+const MISC_SOUND_ENABLED: usize = 55;
+/// Miscellaneous history index of the city tax rate.
+const MISC_CITY_TAX: usize = 56;
+/// Miscellaneous history index of the simulation speed.
+const MISC_SIMULATION_SPEED: usize = 57;
+/// Miscellaneous history index of the police funding percentage (32 bits, 16.16 fixed point).
+const MISC_POLICE_PERCENTAGE: usize = 58;
+/// Miscellaneous history index of the fire funding percentage (32 bits, 16.16 fixed point).
+const MISC_FIRE_PERCENTAGE: usize = 60;
+/// Miscellaneous history index of the road funding percentage (32 bits, 16.16 fixed point).
+const MISC_ROAD_PERCENTAGE: usize = 62;
+
+/// Raw contents of a classic Micropolis city file (`.cty`).
+///
+/// All the values are stored as big-endian 16-bit words: the six census
+/// histories, the miscellaneous history (which holds the game settings),
+/// then the map in column-major order.
+///
+/// See `loadFileData` and `saveFile` in `fileio.cpp` in the C++ code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CityFileData {
+    pub residential_history: Vec<u16>,
+    pub commercial_history: Vec<u16>,
+    pub industrial_history: Vec<u16>,
+    pub crime_history: Vec<u16>,
+    pub pollution_history: Vec<u16>,
+    pub money_history: Vec<u16>,
+    pub misc_history: Vec<u16>,
+    pub map: TileMap,
+}
+
+impl CityFileData {
+    /// Read a city file, with or without the Mac resource header.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, MicropolisError> {
+        let mut bytes = Vec::with_capacity(CITY_FILE_SIZE);
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse the raw bytes of a city file, with or without the Mac resource header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MicropolisError> {
+        let data = match bytes.len() {
+            CITY_FILE_SIZE => bytes,
+            size if size == CITY_FILE_SIZE + CITY_FILE_MAC_HEADER_SIZE => {
+                &bytes[CITY_FILE_MAC_HEADER_SIZE..]
+            }
+            size => {
+                return Err(MicropolisError::parse(format!(
+                    "invalid city file size {} (expected {})",
+                    size, CITY_FILE_SIZE
+                )))
+            }
+        };
+
+        let mut words = data
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]));
+        let mut read_words =
+            |count: usize| -> Vec<u16> { words.by_ref().take(count).collect::<Vec<u16>>() };
+
+        let residential_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let commercial_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let industrial_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let crime_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let pollution_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let money_history = read_words(CITY_FILE_HISTORY_LENGTH);
+        let misc_history = read_words(CITY_FILE_MISC_HISTORY_LENGTH);
+
+        let mut map_data = Vec::with_capacity(CITY_FILE_MAP_WIDTH);
+        for _ in 0..CITY_FILE_MAP_WIDTH {
+            map_data.push(
+                read_words(CITY_FILE_MAP_HEIGHT)
+                    .into_iter()
+                    .map(Tile::from_raw)
+                    .collect::<Result<Vec<Tile>, MicropolisError>>()?,
+            );
+        }
+
+        Ok(CityFileData {
+            residential_history,
+            commercial_history,
+            industrial_history,
+            crime_history,
+            pollution_history,
+            money_history,
+            misc_history,
+            map: Map::with_data(map_data, MapClusteringStrategy::BlockSize1),
+        })
+    }
+
+    /// Write the city file, without any Mac resource header.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), MicropolisError> {
+        Ok(writer.write_all(&self.to_bytes()?)?)
+    }
+
+    /// Serialize the city file to its raw bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MicropolisError> {
+        let bounds = self.map.bounds();
+        if bounds.get_width() != CITY_FILE_MAP_WIDTH || bounds.get_height() != CITY_FILE_MAP_HEIGHT
+        {
+            return Err(MicropolisError::InvalidArgument(format!(
+                "unsupported city file map dimensions {}x{}",
+                bounds.get_width(),
+                bounds.get_height()
+            )));
+        }
+
+        let mut bytes = Vec::with_capacity(CITY_FILE_SIZE);
+        let histories = [
+            (&self.residential_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.commercial_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.industrial_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.crime_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.pollution_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.money_history, CITY_FILE_HISTORY_LENGTH),
+            (&self.misc_history, CITY_FILE_MISC_HISTORY_LENGTH),
+        ];
+        for (history, length) in histories.iter() {
+            if history.len() != *length {
+                return Err(MicropolisError::InvalidArgument(format!(
+                    "invalid city file history length {} (expected {})",
+                    history.len(),
+                    length
+                )));
+            }
+            for word in history.iter() {
+                bytes.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+        for column in self.map.tiles().iter() {
+            for tile in column.iter() {
+                bytes.extend_from_slice(&tile.get_raw().to_be_bytes());
+            }
+        }
+
+        debug_assert_eq!(bytes.len(), CITY_FILE_SIZE);
+        Ok(bytes)
+    }
+
+    pub fn get_city_time(&self) -> i32 {
+        self.get_misc_long(MISC_CITY_TIME)
+    }
+    pub fn set_city_time(&mut self, city_time: i32) {
+        self.set_misc_long(MISC_CITY_TIME, city_time);
+    }
+
+    pub fn get_total_funds(&self) -> i32 {
+        self.get_misc_long(MISC_TOTAL_FUNDS)
+    }
+    pub fn set_total_funds(&mut self, total_funds: i32) {
+        self.set_misc_long(MISC_TOTAL_FUNDS, total_funds);
+    }
+
+    pub fn is_auto_bulldoze_enabled(&self) -> bool {
+        self.misc_history[MISC_AUTO_BULLDOZE] != 0
+    }
+    pub fn set_auto_bulldoze_enabled(&mut self, enabled: bool) {
+        self.misc_history[MISC_AUTO_BULLDOZE] = enabled as u16;
+    }
+    pub fn is_auto_budget_enabled(&self) -> bool {
+        self.misc_history[MISC_AUTO_BUDGET] != 0
+    }
+    pub fn set_auto_budget_enabled(&mut self, enabled: bool) {
+        self.misc_history[MISC_AUTO_BUDGET] = enabled as u16;
+    }
+    pub fn is_auto_goto_enabled(&self) -> bool {
+        self.misc_history[MISC_AUTO_GOTO] != 0
+    }
+    pub fn set_auto_goto_enabled(&mut self, enabled: bool) {
+        self.misc_history[MISC_AUTO_GOTO] = enabled as u16;
+    }
+    pub fn is_sound_enabled(&self) -> bool {
+        self.misc_history[MISC_SOUND_ENABLED] != 0
+    }
+    pub fn set_sound_enabled(&mut self, enabled: bool) {
+        self.misc_history[MISC_SOUND_ENABLED] = enabled as u16;
+    }
+
+    pub fn get_city_tax(&self) -> i16 {
+        self.misc_history[MISC_CITY_TAX] as i16
+    }
+    pub fn set_city_tax(&mut self, city_tax: i16) {
+        self.misc_history[MISC_CITY_TAX] = city_tax as u16;
+    }
+
+    pub fn get_simulation_speed(&self) -> i16 {
+        self.misc_history[MISC_SIMULATION_SPEED] as i16
+    }
+    pub fn set_simulation_speed(&mut self, speed: i16) {
+        self.misc_history[MISC_SIMULATION_SPEED] = speed as u16;
+    }
+
+    /// Police funding percentage, between 0 and 1.
+    pub fn get_police_percentage(&self) -> f64 {
+        Self::fixed_to_percentage(self.get_misc_long(MISC_POLICE_PERCENTAGE))
+    }
+    pub fn set_police_percentage(&mut self, percentage: f64) {
+        self.set_misc_long(
+            MISC_POLICE_PERCENTAGE,
+            Self::percentage_to_fixed(percentage),
+        );
+    }
+
+    /// Fire funding percentage, between 0 and 1.
+    pub fn get_fire_percentage(&self) -> f64 {
+        Self::fixed_to_percentage(self.get_misc_long(MISC_FIRE_PERCENTAGE))
+    }
+    pub fn set_fire_percentage(&mut self, percentage: f64) {
+        self.set_misc_long(MISC_FIRE_PERCENTAGE, Self::percentage_to_fixed(percentage));
+    }
+
+    /// Road funding percentage, between 0 and 1.
+    pub fn get_road_percentage(&self) -> f64 {
+        Self::fixed_to_percentage(self.get_misc_long(MISC_ROAD_PERCENTAGE))
+    }
+    pub fn set_road_percentage(&mut self, percentage: f64) {
+        self.set_misc_long(MISC_ROAD_PERCENTAGE, Self::percentage_to_fixed(percentage));
+    }
+
+    /// Read a 32-bit value stored as two words, most significant first.
+    fn get_misc_long(&self, index: usize) -> i32 {
+        (((self.misc_history[index] as u32) << 16) | self.misc_history[index + 1] as u32) as i32
+    }
+
+    /// Write a 32-bit value as two words, most significant first.
+    fn set_misc_long(&mut self, index: usize, value: i32) {
+        let value = value as u32;
+        self.misc_history[index] = (value >> 16) as u16;
+        self.misc_history[index + 1] = (value & 0xFFFF) as u16;
+    }
+
+    fn fixed_to_percentage(value: i32) -> f64 {
+        (value as f64 / 65536.0).clamp(0.0, 1.0)
+    }
+
+    fn percentage_to_fixed(percentage: f64) -> i32 {
+        (percentage * 65536.0) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CityFileData, CITY_FILE_HISTORY_LENGTH, CITY_FILE_MAC_HEADER_SIZE, CITY_FILE_MAP_HEIGHT,
+        CITY_FILE_MAP_WIDTH, CITY_FILE_MISC_HISTORY_LENGTH, CITY_FILE_SIZE,
+    };
+
+    /// Build a deterministic, synthetic city file.
+    fn synthetic_city_file_bytes() -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CITY_FILE_SIZE);
+        let words_count = 6 * CITY_FILE_HISTORY_LENGTH + CITY_FILE_MISC_HISTORY_LENGTH;
+        for i in 0..words_count {
+            bytes.extend_from_slice(&((i * 7 % 256) as u16).to_be_bytes());
+        }
+        for x in 0..CITY_FILE_MAP_WIDTH {
+            for y in 0..CITY_FILE_MAP_HEIGHT {
+                let raw =
+                    ((x * 31 + y * 17) % 960) as u16 | if (x + y) % 5 == 0 { 0x4400 } else { 0 };
+                bytes.extend_from_slice(&raw.to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_city_file_round_trip() {
+        let bytes = synthetic_city_file_bytes();
+        let data = CityFileData::from_bytes(&bytes).unwrap();
+        assert_eq!(data.residential_history.len(), CITY_FILE_HISTORY_LENGTH);
+        assert_eq!(data.misc_history.len(), CITY_FILE_MISC_HISTORY_LENGTH);
+        assert_eq!(
+            data.money_history[0],
+            ((5 * CITY_FILE_HISTORY_LENGTH) * 7 % 256) as u16
+        );
+        assert_eq!(
+            data.map.get_tile_at(&(3, 2).into()).unwrap().get_raw(),
+            (3 * 31 + 2 * 17) as u16 | 0x4400
+        );
+        assert_eq!(data.to_bytes().unwrap(), bytes);
+
+        let mut mac_bytes = vec![0xAB; CITY_FILE_MAC_HEADER_SIZE];
+        mac_bytes.extend_from_slice(&bytes);
+        let mac_data = CityFileData::from_bytes(&mac_bytes).unwrap();
+        assert_eq!(mac_data, data);
+
+        assert!(CityFileData::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn test_city_file_settings() {
+        let mut data = CityFileData::from_bytes(&synthetic_city_file_bytes()).unwrap();
+        data.set_total_funds(-1234567);
+        data.set_city_time(48 * 50 + 3);
+        data.set_city_tax(9);
+        data.set_simulation_speed(2);
+        data.set_police_percentage(0.5);
+        data.set_fire_percentage(1.0);
+        data.set_road_percentage(0.25);
+
+        let reloaded = CityFileData::from_bytes(&data.to_bytes().unwrap()).unwrap();
+        assert_eq!(reloaded.get_total_funds(), -1234567);
+        assert_eq!(reloaded.get_city_time(), 48 * 50 + 3);
+        assert_eq!(reloaded.get_city_tax(), 9);
+        assert_eq!(reloaded.get_simulation_speed(), 2);
+        assert_eq!(reloaded.get_police_percentage(), 0.5);
+        assert_eq!(reloaded.get_fire_percentage(), 1.0);
+        assert_eq!(reloaded.get_road_percentage(), 0.25);
+        // big-endian, most significant word first
+        assert_eq!(reloaded.misc_history[50], 0xFFED);
+        assert_eq!(reloaded.misc_history[51], 0x2979);
+    }
+}
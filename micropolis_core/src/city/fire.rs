@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::{
+        tiles::{TILE_BURN_BIT, TILE_LOW_MASK},
+        Map, MapClusteringStrategy, MapPosition, MapPositionOffset, Tile, TileMap, TileType,
+    },
+    utils::{
+        clamp,
+        hash::{StableHash, StableHasher},
+    },
+};
+
+/// Maximum strength of the wind.
+pub const MAX_WIND_STRENGTH: u8 = 3;
+
+/// Wind blowing over the city, pushing fires downwind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wind {
+    /// Direction the wind blows towards.
+    pub direction: MapPositionOffset,
+    /// Strength of the wind, from 0 (calm) to `MAX_WIND_STRENGTH`.
+    pub strength: u8,
+}
+
+impl Wind {
+    pub fn calm() -> Self {
+        Self {
+            direction: MapPositionOffset::None,
+            strength: 0,
+        }
+    }
+}
+
+/// Tuning of the fire spread and firefighting model.
+///
+/// Chances are expressed out of 16.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FireTuning {
+    /// Chance for a fire to spread to each burnable neighboring tile.
+    pub spread_chance: u16,
+    /// Extra spread chance for trees and woods.
+    pub woods_spread_bonus: u16,
+    /// Extra spread chance downwind, and lesser spread chance upwind,
+    /// for each point of wind strength.
+    pub wind_spread_factor: u16,
+    /// Fire station coverage above which fires are put out faster.
+    pub coverage_thresholds: [i16; 3],
+    /// Fires burn out with a chance of 1 in (rate + 1): the first rate applies
+    /// without coverage, the other ones above each coverage threshold.
+    pub burn_out_rates: [i16; 4],
+}
+
+impl Default for FireTuning {
+    /// Same as the original game when there is no wind.
+    fn default() -> Self {
+        Self {
+            spread_chance: 2,
+            woods_spread_bonus: 0,
+            wind_spread_factor: 1,
+            coverage_thresholds: [0, 20, 100],
+            burn_out_rates: [10, 3, 2, 1],
+        }
+    }
+}
+
+/// Statistics about the fires of the city.
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FireStatistics {
+    /// Number of fires that broke out.
+    pub fires_started: u32,
+    /// Number of fires that are completely out.
+    pub fires_out: u32,
+    /// Number of tiles that caught fire.
+    pub tiles_lost: u32,
+    /// Number of tiles lost to the fires that are out.
+    pub tiles_lost_to_fires_out: u32,
+    /// Largest number of tiles lost to a single fire.
+    pub largest_fire: u32,
+}
+
+impl FireStatistics {
+    /// Average number of tiles lost to each fire that is out.
+    pub fn average_tiles_lost_per_fire(&self) -> f64 {
+        if self.fires_out == 0 {
+            return 0.0;
+        }
+        self.tiles_lost_to_fires_out as f64 / self.fires_out as f64
+    }
+}
+
+/// Fire being tracked, from its outbreak until its last tile burns out.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct FireRecord {
+    /// Number of tiles still burning.
+    burning_tiles: u32,
+    /// Number of tiles that caught fire.
+    tiles_lost: u32,
+}
+
+/// Fire spread and firefighting simulation.
+///
+/// The map scan drives the fires; this keeps the wind, the tuning of the
+/// model and tracks each fire to compute the statistics.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CityFires {
+    wind: Wind,
+    tuning: FireTuning,
+    statistics: FireStatistics,
+    /// Identifier of the fire burning each tile, or 0.
+    fire_ids: Map<u32>,
+    /// Fires currently burning, by identifier.
+    fires: BTreeMap<u32, FireRecord>,
+    next_fire_id: u32,
+}
+
+impl CityFires {
+    pub fn from_map(map: &TileMap) -> Self {
+        let bounds = map.bounds();
+        Self {
+            wind: Wind::calm(),
+            tuning: FireTuning::default(),
+            statistics: FireStatistics::default(),
+            fire_ids: Map::with_data(
+                vec![vec![0; bounds.get_height()]; bounds.get_width()],
+                MapClusteringStrategy::BlockSize1,
+            ),
+            fires: BTreeMap::new(),
+            next_fire_id: 1,
+        }
+    }
+
+    pub fn get_wind(&self) -> &Wind {
+        &self.wind
+    }
+    pub fn set_wind(&mut self, wind: Wind) {
+        self.wind = Wind {
+            strength: wind.strength.min(MAX_WIND_STRENGTH),
+            ..wind
+        };
+    }
+
+    pub fn get_tuning(&self) -> &FireTuning {
+        &self.tuning
+    }
+    pub fn set_tuning(&mut self, tuning: FireTuning) {
+        self.tuning = tuning;
+    }
+
+    pub fn get_statistics(&self) -> &FireStatistics {
+        &self.statistics
+    }
+
+    /// Number of fires currently burning.
+    pub fn get_burning_fires_count(&self) -> usize {
+        self.fires.len()
+    }
+
+    /// Map a 16-bit random number to a roll out of 16, to compare with a spread chance.
+    ///
+    /// The lowest rolls are the ones the original game spreads fires on, so
+    /// that the default spread chance of 2 amounts to `(getRandom16() & 7) == 0`.
+    pub fn spread_roll(random: i16) -> u16 {
+        let random = random as u16;
+        ((random & 0x07) << 1) | ((random >> 3) & 0x01)
+    }
+
+    /// Chance, out of 16, for a fire to spread to the given tile, lying at
+    /// the given offset from the burning one.
+    pub fn get_spread_chance(&self, tile_raw: u16, offset: (i32, i32)) -> u16 {
+        if tile_raw & TILE_BURN_BIT == 0x00 {
+            return 0; // not burnable
+        }
+
+        let mut chance = self.tuning.spread_chance as i32;
+        let tile_value = tile_raw & TILE_LOW_MASK;
+        if tile_value >= TileType::TreeBase.to_u16().unwrap()
+            && tile_value <= TileType::Woods5.to_u16().unwrap()
+        {
+            chance += self.tuning.woods_spread_bonus as i32;
+        }
+
+        // positive downwind, negative upwind
+        let (wind_x, wind_y) = self.wind.direction.offset();
+        let alignment = wind_x as i32 * offset.0 + wind_y as i32 * offset.1;
+        chance +=
+            alignment.signum() * self.wind.strength as i32 * self.tuning.wind_spread_factor as i32;
+
+        clamp(chance, 0, 16) as u16
+    }
+
+    /// Rate at which fires burn out with the given fire station coverage:
+    /// the chance is 1 in (rate + 1).
+    ///
+    /// The coverage already accounts for the funding, the power and the road
+    /// access of the fire stations around.
+    pub fn get_burn_out_rate(&self, coverage: i16) -> i16 {
+        let level = self
+            .tuning
+            .coverage_thresholds
+            .iter()
+            .filter(|threshold| coverage > **threshold)
+            .count();
+        self.tuning.burn_out_rates[level]
+    }
+
+    /// Identifier of the fire burning at the given position, tracking a new
+    /// fire if it broke out since the last time.
+    pub(crate) fn track_fire_at(&mut self, position: &MapPosition) -> u32 {
+        match self.fire_ids.get_tile_at(position) {
+            Some(id) if *id != 0 => *id,
+            Some(_) => {
+                let id = self.next_fire_id;
+                self.next_fire_id += 1;
+                self.statistics.fires_started += 1;
+                self.fires.insert(
+                    id,
+                    FireRecord {
+                        burning_tiles: 0,
+                        tiles_lost: 0,
+                    },
+                );
+                self.set_on_fire(position, id);
+                id
+            }
+            None => 0,
+        }
+    }
+
+    /// A fire spread from a burning tile to another one.
+    pub(crate) fn spread(&mut self, from: &MapPosition, to: &MapPosition) {
+        let id = self.track_fire_at(from);
+        if id != 0 && self.fire_ids.get_tile_at(to) == Some(&0) {
+            self.set_on_fire(to, id);
+        }
+    }
+
+    /// The tile at the given position does not burn anymore.
+    pub(crate) fn burn_out(&mut self, position: &MapPosition) {
+        let id = match self.fire_ids.get_tile_at(position) {
+            Some(id) if *id != 0 => *id,
+            _ => return,
+        };
+        self.fire_ids.set_tile_at(position, 0);
+
+        let fire = self.fires.get_mut(&id).unwrap();
+        fire.burning_tiles -= 1;
+        if fire.burning_tiles == 0 {
+            let tiles_lost = fire.tiles_lost;
+            self.fires.remove(&id);
+            self.statistics.fires_out += 1;
+            self.statistics.tiles_lost_to_fires_out += tiles_lost;
+            self.statistics.largest_fire = self.statistics.largest_fire.max(tiles_lost);
+        }
+    }
+
+    /// Stop tracking the tiles that do not burn anymore, for instance after
+    /// being bulldozed or flooded.
+    pub(crate) fn prune(&mut self, map: &TileMap) {
+        let bounds = map.bounds();
+        for x in 0..bounds.get_width() {
+            for y in 0..bounds.get_height() {
+                let position: MapPosition = (x, y).into();
+                if self.fire_ids.get_tile_at(&position) == Some(&0) {
+                    continue;
+                }
+                if !map.get_tile_at(&position).is_some_and(is_fire) {
+                    self.burn_out(&position);
+                }
+            }
+        }
+    }
+
+    fn set_on_fire(&mut self, position: &MapPosition, id: u32) {
+        self.fire_ids.set_tile_at(position, id);
+        let fire = self.fires.get_mut(&id).unwrap();
+        fire.burning_tiles += 1;
+        fire.tiles_lost += 1;
+        self.statistics.tiles_lost += 1;
+    }
+}
+
+fn is_fire(tile: &Tile) -> bool {
+    let tile_value = tile.get_raw() & TILE_LOW_MASK;
+    tile_value >= TileType::Fire.to_u16().unwrap()
+        && tile_value <= TileType::LastFire.to_u16().unwrap()
+}
+
+impl StableHash for FireRecord {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(self.burning_tiles);
+        hasher.write_u32(self.tiles_lost);
+    }
+}
+
+impl StableHash for CityFires {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.wind.direction.stable_hash(hasher);
+        hasher.write_u8(self.wind.strength);
+        hasher.write_u16(self.tuning.spread_chance);
+        hasher.write_u16(self.tuning.woods_spread_bonus);
+        hasher.write_u16(self.tuning.wind_spread_factor);
+        self.tuning.coverage_thresholds.stable_hash(hasher);
+        self.tuning.burn_out_rates.stable_hash(hasher);
+        hasher.write_u32(self.statistics.fires_started);
+        hasher.write_u32(self.statistics.fires_out);
+        hasher.write_u32(self.statistics.tiles_lost);
+        hasher.write_u32(self.statistics.tiles_lost_to_fires_out);
+        hasher.write_u32(self.statistics.largest_fire);
+        self.fire_ids.stable_hash(hasher);
+        self.fires.stable_hash(hasher);
+        hasher.write_u32(self.next_fire_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityFires, FireTuning, Wind, MAX_WIND_STRENGTH};
+    use crate::map::{
+        tiles::{TILE_BULL_BIT, TILE_BURN_BIT},
+        Map, MapPositionOffset, MapRectangle, Tile, TileType,
+    };
+
+    #[test]
+    fn test_spread_and_burn_out_chances() {
+        let map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let mut fires = CityFires::from_map(&map);
+        let house = (TileType::ResidentialBase.to_u16().unwrap() + 1) | TILE_BURN_BIT;
+        let woods = TileType::Woods.to_u16().unwrap() | TILE_BURN_BIT | TILE_BULL_BIT;
+
+        assert_eq!(fires.get_spread_chance(0, (1, 0)), 0);
+        assert_eq!(fires.get_spread_chance(house, (1, 0)), 2);
+        assert_eq!(fires.get_spread_chance(woods, (1, 0)), 2);
+
+        fires.set_wind(Wind {
+            direction: MapPositionOffset::NorthEast,
+            strength: 10,
+        });
+        assert_eq!(fires.get_wind().strength, MAX_WIND_STRENGTH);
+        assert_eq!(fires.get_spread_chance(house, (1, 0)), 5);
+        assert_eq!(fires.get_spread_chance(house, (0, -1)), 5);
+        assert_eq!(fires.get_spread_chance(house, (-1, 0)), 0);
+        assert_eq!(fires.get_spread_chance(woods, (0, 1)), 0);
+
+        assert_eq!(fires.get_burn_out_rate(0), 10);
+        assert_eq!(fires.get_burn_out_rate(20), 3);
+        assert_eq!(fires.get_burn_out_rate(21), 2);
+        assert_eq!(fires.get_burn_out_rate(150), 1);
+    }
+
+    #[test]
+    fn test_default_spread_matches_the_original_game() {
+        let map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let fires = CityFires::from_map(&map);
+        assert_eq!(fires.get_tuning(), &FireTuning::default());
+        let house = (TileType::ResidentialBase.to_u16().unwrap() + 1) | TILE_BURN_BIT;
+        let woods = TileType::Woods.to_u16().unwrap() | TILE_BURN_BIT | TILE_BULL_BIT;
+        for tile_raw in [house, woods] {
+            let chance = fires.get_spread_chance(tile_raw, (0, 1));
+            for random in i16::MIN..=i16::MAX {
+                assert_eq!(
+                    CityFires::spread_roll(random) < chance,
+                    (random & 7) == 0,
+                    "random number {:#06x}",
+                    random
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fire_statistics() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let mut fires = CityFires::from_map(&map);
+        let fire = Tile::from_type(TileType::Fire).unwrap();
+
+        // a first fire spreading to 2 more tiles
+        for x in 10..13 {
+            map.set_tile_at(&(x, 10).into(), fire.clone());
+        }
+        fires.spread(&(10, 10).into(), &(11, 10).into());
+        fires.spread(&(11, 10).into(), &(12, 10).into());
+        // a second one, on its own
+        map.set_tile_at(&(50, 50).into(), fire.clone());
+        fires.track_fire_at(&(50, 50).into());
+        assert_eq!(fires.get_burning_fires_count(), 2);
+        assert_eq!(fires.get_statistics().fires_started, 2);
+        assert_eq!(fires.get_statistics().tiles_lost, 4);
+
+        fires.burn_out(&(10, 10).into());
+        fires.burn_out(&(11, 10).into());
+        fires.burn_out(&(50, 50).into());
+        assert_eq!(fires.get_statistics().fires_out, 1);
+        assert_eq!(fires.get_statistics().largest_fire, 1);
+
+        // the last burning tile got bulldozed
+        map.set_tile_at(&(12, 10).into(), Tile::from_type(TileType::Dirt).unwrap());
+        fires.prune(&map);
+        let statistics = fires.get_statistics();
+        assert_eq!(fires.get_burning_fires_count(), 0);
+        assert_eq!(statistics.fires_out, 2);
+        assert_eq!(statistics.largest_fire, 3);
+        assert_eq!(statistics.average_tiles_lost_per_fire(), 2.0);
+    }
+}
@@ -1,6 +1,8 @@
 use quick_xml::{events::Event, Reader};
 use std::str;
 
+use crate::error::MicropolisError;
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CityMetadata {
     title: String,
@@ -34,7 +36,7 @@ impl CityMetadata {
     /// for every property since they are value-encoded in micropolis.
     ///
     /// TODO: now quick-xml integrates with serde's Serialize/Deserialize, can something be done here?
-    pub fn decode_from_xml(raw: &str) -> Result<Self, String> {
+    pub fn decode_from_xml(raw: &str) -> Result<Self, MicropolisError> {
         let mut reader = Reader::from_str(raw);
         let mut in_container_tag = false;
         let mut current_property = Option::<String>::None;
@@ -45,13 +47,18 @@ impl CityMetadata {
         loop {
             match reader.read_event() {
                 Ok(Event::Start(e)) => {
-                    let name = str::from_utf8(e.name().into_inner())
-                        .map_err(|err| format!("from_utf8 error: {}", err))?;
+                    let name = str::from_utf8(e.name().into_inner()).map_err(|err| {
+                        MicropolisError::parse(format!("from_utf8 error: {}", err))
+                    })?;
                     println!("name {}", name);
                     match name {
                         "metaCity" => {
                             in_container_tag = match in_container_tag {
-                                true => return Err("duplicate 'metaCity' container tag".into()),
+                                true => {
+                                    return Err(MicropolisError::parse(
+                                        "duplicate 'metaCity' container tag",
+                                    ))
+                                }
                                 false => true,
                             }
                         }
@@ -60,15 +67,23 @@ impl CityMetadata {
                             missing_properties.retain(|p| p != &property);
                         }
                         duplicate if XML_REQUIRED_PROPERTIES_NAMES.contains(&name) => {
-                            return Err(format!("duplicate property tag '{}'", duplicate))
+                            return Err(MicropolisError::parse(format!(
+                                "duplicate property tag '{}'",
+                                duplicate
+                            )))
+                        }
+                        _ => {
+                            return Err(MicropolisError::parse(format!(
+                                "unknown property tag '{}'",
+                                name
+                            )))
                         }
-                        _ => return Err(format!("unknown property tag '{}'", name)),
                     }
                 }
                 Ok(Event::Text(e)) => {
-                    let value_cow = e
-                        .decode()
-                        .map_err(|err| format!("text event decoding error: {}", err))?;
+                    let value_cow = e.decode().map_err(|err| {
+                        MicropolisError::parse(format!("text event decoding error: {}", err))
+                    })?;
                     let value = value_cow.trim();
                     if value.is_empty() {
                         continue;
@@ -81,18 +96,23 @@ impl CityMetadata {
                             "saveFileName" => parsed.save_file_name = value.to_string(),
                             "readOnly" if value == "true" => parsed.is_read_only = true,
                             "readOnly" if value == "false" => parsed.is_read_only = false,
-                            "readOnly" => return Err(format!("invalid boolean value '{}'", value)),
+                            "readOnly" => {
+                                return Err(MicropolisError::parse(format!(
+                                    "invalid boolean value '{}'",
+                                    value
+                                )))
+                            }
                             _ => unreachable!(),
                         }
                     }
                 }
                 Ok(Event::Eof) => break,
                 Err(why) => {
-                    return Err(format!(
-                        "parsing error at position {}: {:?}",
+                    return Err(MicropolisError::parse(format!(
+                        "error at position {}: {:?}",
                         reader.buffer_position(),
                         why
-                    ))
+                    )))
                 }
                 _ => {}
             };
@@ -100,11 +120,11 @@ impl CityMetadata {
 
         match missing_properties.len() {
             0 => Ok(parsed),
-            n => Err(format!(
-                "missing {} properties): {}",
+            n => Err(MicropolisError::parse(format!(
+                "missing {} properties: {}",
                 n,
                 missing_properties.join(", ")
-            )),
+            ))),
         }
     }
 
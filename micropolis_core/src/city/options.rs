@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Game options saved along with the city.
+///
+/// See the `autoBulldoze`, `autoBudget`, `autoGoto` and `enableSound` fields
+/// of `Micropolis` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CityOptions {
+    /// Clear the rubble, trees and other bulldozable tiles when building over them.
+    pub auto_bulldoze: bool,
+    /// Balance the budget automatically at the end of each year.
+    pub auto_budget: bool,
+    /// Move the view to the location of important events.
+    pub auto_goto: bool,
+    /// Play the sound effects.
+    pub sound_enabled: bool,
+}
+
+impl Default for CityOptions {
+    /// See `Micropolis::init` in `micropolis.cpp`.
+    fn default() -> Self {
+        CityOptions {
+            auto_bulldoze: true,
+            auto_budget: true,
+            auto_goto: true,
+            sound_enabled: true,
+        }
+    }
+}
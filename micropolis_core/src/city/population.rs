@@ -1,6 +1,14 @@
-use crate::map::{Map, MapClusteringStrategy, MapPosition, MapRectangle, TileMap};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+use crate::{
+    map::{Map, MapClusteringStrategy, MapPosition, MapRectangle, TileMap},
+    utils::{
+        clamp,
+        hash::{StableHash, StableHasher},
+    },
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum CityClass {
     /// Population <= 2k citizens.
     Village,
@@ -29,6 +37,23 @@ impl CityClass {
     }
 }
 
+/// Maximum absolute value of the residential valve.
+pub const RESIDENTIAL_VALVE_RANGE: i16 = 2000;
+/// Maximum absolute value of the commercial valve.
+pub const COMMERCIAL_VALVE_RANGE: i16 = 1500;
+/// Maximum absolute value of the industrial valve.
+pub const INDUSTRIAL_VALVE_RANGE: i16 = 1500;
+
+/// Demand for each zone type, as drawn by the RCI indicator.
+///
+/// Every value is between -1 (no demand at all) and 1 (maximum demand).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CityDemand {
+    pub residential: f64,
+    pub commercial: f64,
+    pub industrial: f64,
+}
+
 pub type PopulationDensityMap = Map<u8>;
 
 impl PopulationDensityMap {
@@ -41,6 +66,7 @@ impl PopulationDensityMap {
 }
 
 // TODO: dedicated struct for valves?
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityPopulation {
     /// Population density map.
     density_map: Map<u8>,
@@ -48,6 +74,8 @@ pub struct CityPopulation {
     ///
     /// Depends on the level of zone development.
     residential: u16,
+    /// Number of residential zones.
+    residential_zones: u16,
     /// Block residential growth?
     residential_cap: bool,
     residential_valve: i16,
@@ -55,6 +83,8 @@ pub struct CityPopulation {
     ///
     /// Depends on the level of zone development.
     commercial: u16,
+    /// Number of commercial zones.
+    commercial_zones: u16,
     /// Block commercial growth?
     commercial_cap: bool,
     commercial_valve: i16,
@@ -62,12 +92,14 @@ pub struct CityPopulation {
     ///
     /// Depends on the level of zone development.
     industrial: u16,
+    /// Number of industrial zones.
+    industrial_zones: u16,
     /// Block industrial growth?
     industrial_cap: bool,
     industrial_valve: i16,
     /// Total city population.
     ///
-    /// Formula = (residential population) / 8 + (commercial population) + (industrial population).
+    /// Formula = ((residential population) + ((commercial population) + (industrial population)) * 8) * 20.
     total: i64,
     /// Change in the total city population.
     total_delta: i64,
@@ -78,12 +110,15 @@ impl CityPopulation {
         Self {
             density_map: PopulationDensityMap::with_dimensions(&map.bounds(), 0),
             residential: 0,
+            residential_zones: 0,
             residential_cap: false,
             residential_valve: 0,
             commercial: 0,
+            commercial_zones: 0,
             commercial_cap: false,
             commercial_valve: 0,
             industrial: 0,
+            industrial_zones: 0,
             industrial_cap: false,
             industrial_valve: 0,
             total: 0,
@@ -113,8 +148,11 @@ impl CityPopulation {
     pub fn get_residential(&self) -> u16 {
         self.residential
     }
+    pub fn get_residential_zones(&self) -> u16 {
+        self.residential_zones
+    }
     pub fn get_residential_valve(&self) -> i16 {
-        self.commercial_valve
+        self.residential_valve
     }
     pub fn is_residential_capped(&self) -> bool {
         self.residential_cap
@@ -123,6 +161,9 @@ impl CityPopulation {
     pub fn get_commercial(&self) -> u16 {
         self.commercial
     }
+    pub fn get_commercial_zones(&self) -> u16 {
+        self.commercial_zones
+    }
     pub fn get_commercial_valve(&self) -> i16 {
         self.commercial_valve
     }
@@ -133,6 +174,9 @@ impl CityPopulation {
     pub fn get_industrial(&self) -> u16 {
         self.industrial
     }
+    pub fn get_industrial_zones(&self) -> u16 {
+        self.industrial_zones
+    }
     pub fn get_industrial_valve(&self) -> i16 {
         self.industrial_valve
     }
@@ -140,6 +184,86 @@ impl CityPopulation {
         self.industrial_cap
     }
 
+    /// Get the normalized RCI demand.
+    ///
+    /// See `Micropolis::getDemands` in the C++ code.
+    pub fn get_demand(&self) -> CityDemand {
+        CityDemand {
+            residential: self.residential_valve as f64 / RESIDENTIAL_VALVE_RANGE as f64,
+            commercial: self.commercial_valve as f64 / COMMERCIAL_VALVE_RANGE as f64,
+            industrial: self.industrial_valve as f64 / INDUSTRIAL_VALVE_RANGE as f64,
+        }
+    }
+
+    /// Set which zone types are blocked from growing.
+    pub fn set_caps(&mut self, residential: bool, commercial: bool, industrial: bool) {
+        self.residential_cap = residential;
+        self.commercial_cap = commercial;
+        self.industrial_cap = industrial;
+    }
+
+    /// Change the RCI valves by the given amounts.
+    ///
+    /// The valves are clamped to their range, and capped zone types cannot have
+    /// a positive demand.
+    pub fn change_valves(&mut self, residential: i16, commercial: i16, industrial: i16) {
+        self.residential_valve = Self::change_valve(
+            self.residential_valve,
+            residential,
+            RESIDENTIAL_VALVE_RANGE,
+            self.residential_cap,
+        );
+        self.commercial_valve = Self::change_valve(
+            self.commercial_valve,
+            commercial,
+            COMMERCIAL_VALVE_RANGE,
+            self.commercial_cap,
+        );
+        self.industrial_valve = Self::change_valve(
+            self.industrial_valve,
+            industrial,
+            INDUSTRIAL_VALVE_RANGE,
+            self.industrial_cap,
+        );
+    }
+
+    fn change_valve(valve: i16, change: i16, range: i16, capped: bool) -> i16 {
+        let valve = clamp(valve.saturating_add(change), -range, range);
+        if capped && valve > 0 {
+            0
+        } else {
+            valve
+        }
+    }
+
+    /// Count a residential zone and its population found during the map scan.
+    pub fn add_residential(&mut self, population: u16) {
+        self.residential = self.residential.saturating_add(population);
+        self.residential_zones += 1;
+    }
+
+    /// Count a commercial zone and its population found during the map scan.
+    pub fn add_commercial(&mut self, population: u16) {
+        self.commercial = self.commercial.saturating_add(population);
+        self.commercial_zones += 1;
+    }
+
+    /// Count an industrial zone and its population found during the map scan.
+    pub fn add_industrial(&mut self, population: u16) {
+        self.industrial = self.industrial.saturating_add(population);
+        self.industrial_zones += 1;
+    }
+
+    /// Reset the zone counters and populations before a new map scan.
+    pub fn clear_census(&mut self) {
+        self.residential = 0;
+        self.residential_zones = 0;
+        self.commercial = 0;
+        self.commercial_zones = 0;
+        self.industrial = 0;
+        self.industrial_zones = 0;
+    }
+
     pub fn total_population(&self) -> i64 {
         self.total
     }
@@ -161,6 +285,26 @@ impl CityPopulation {
     }
 
     fn compute_total_population(&self) -> i64 {
-        (self.residential as i64 + (self.commercial as i64 + self.industrial as i64 * 8)) * 20
+        (self.residential as i64 + (self.commercial as i64 + self.industrial as i64) * 8) * 20
+    }
+}
+
+/// The density map is left out, being hashed with the other overlay maps.
+impl StableHash for CityPopulation {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u16(self.residential);
+        hasher.write_u16(self.residential_zones);
+        hasher.write_bool(self.residential_cap);
+        hasher.write_i16(self.residential_valve);
+        hasher.write_u16(self.commercial);
+        hasher.write_u16(self.commercial_zones);
+        hasher.write_bool(self.commercial_cap);
+        hasher.write_i16(self.commercial_valve);
+        hasher.write_u16(self.industrial);
+        hasher.write_u16(self.industrial_zones);
+        hasher.write_bool(self.industrial_cap);
+        hasher.write_i16(self.industrial_valve);
+        hasher.write_i64(self.total);
+        hasher.write_i64(self.total_delta);
     }
 }
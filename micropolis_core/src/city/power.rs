@@ -1,8 +1,18 @@
-use crate::map::{
-    Map, MapClusteringStrategy, MapPosition, MapPositionOffset, MapRectangle, TileMap,
-    MAP_POSITION_DIRECTIONS, WORLD_HEIGHT, WORLD_WIDTH,
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::{
+        Map, MapClusteringStrategy, MapPosition, MapPositionOffset, MapRectangle, TileMap,
+        WORLD_HEIGHT, WORLD_WIDTH,
+    },
+    messages::GameMessage,
 };
 
+use super::events::CityEvents;
+use network::PowerNetworks;
+
+pub mod network;
+
 /// Size of the power stack.
 const POWER_STACK_SIZE: usize = (WORLD_WIDTH * WORLD_HEIGHT) / 4;
 
@@ -12,6 +22,20 @@ const COAL_POWER_STRENGTH: usize = 700;
 /// Number of tiles that a nuclear power plant can supply power to.
 const NUCLEAR_POWER_STRENGTH: usize = 2000;
 
+/// Directions in which power flows between conductive tiles.
+const POWER_DIRECTIONS: [MapPositionOffset; 4] = [
+    MapPositionOffset::North,
+    MapPositionOffset::East,
+    MapPositionOffset::South,
+    MapPositionOffset::West,
+];
+
+/// Value of the grids map for tiles not part of any grid.
+const NO_GRID: u16 = 0;
+
+/// Value of the grids map for tiles of the grid being flooded.
+const FLOODED_GRID: u16 = u16::MAX;
+
 type PowerMap = Map<u8>;
 
 impl PowerMap {
@@ -23,22 +47,67 @@ impl PowerMap {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerPlantType {
+    Coal,
+    Nuclear,
+}
+
+impl PowerPlantType {
+    /// Number of tiles that a power plant of this type can supply power to.
+    pub fn capacity(&self) -> usize {
+        match self {
+            PowerPlantType::Coal => COAL_POWER_STRENGTH,
+            PowerPlantType::Nuclear => NUCLEAR_POWER_STRENGTH,
+        }
+    }
+}
+
+/// A power plant found during the last map scan.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerPlant {
+    /// Position of the center of the power plant.
+    pub position: MapPosition,
+    pub plant_type: PowerPlantType,
+}
+
+/// A connected power grid found during the last power scan.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerGrid {
+    /// Position of the first power plant of the grid, or of its first tile
+    /// when the grid has no power plant.
+    pub origin: MapPosition,
+    /// Number of power plants in the grid.
+    pub plants_count: usize,
+    /// Combined capacity of the power plants of the grid, in number of tiles.
+    pub capacity: usize,
+    /// Number of tiles powered in the grid.
+    pub load: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityPower {
     pub(crate) power_grid_map: PowerMap,
     /// Number of coal power plants on the map.
     pub(crate) coal_generators_count: usize,
     /// Number of nuclear power plants on the map.
     pub(crate) nuclear_generators_count: usize,
-    /// Stack used to find powered tiles by tracking conductive tiles.
-    ///
-    /// Points to top-most item in the power stack.
-    power_stack_pointer: usize,
+    /// Power plants found during the current map scan, in scan order.
+    power_plants: Vec<PowerPlant>,
     /// Stack of map positions for traversing setting the power grid.
-    power_stack: [MapPosition; POWER_STACK_SIZE],
+    power_stack: Vec<MapPosition>,
     /// Number of powered tiles in all zones.
     powered_zone_count: i16,
     /// Number of unpowered tiles in all zones.
     unpowered_zone_count: i16,
+    /// Combined capacity of the power plants during the last power scan.
+    capacity: usize,
+    /// Number of tiles powered during the last power scan.
+    load: usize,
+    /// Disconnected power grids found during the last power scan.
+    grids: Vec<PowerGrid>,
+    /// Did the last power scan run out of power?
+    overloaded: bool,
 }
 
 impl CityPower {
@@ -47,11 +116,14 @@ impl CityPower {
             power_grid_map: PowerMap::powermap_with_dimensions(&map.bounds(), 0x00),
             coal_generators_count: 0,
             nuclear_generators_count: 0,
-            power_stack_pointer: 0,
-            power_stack: [MapPosition::new(0, 0); POWER_STACK_SIZE],
-            // TODO: tracking of these values
+            power_plants: Vec::new(),
+            power_stack: Vec::with_capacity(POWER_STACK_SIZE),
             powered_zone_count: 0,
             unpowered_zone_count: 0,
+            capacity: 0,
+            load: 0,
+            grids: Vec::new(),
+            overloaded: false,
         }
     }
 
@@ -63,19 +135,103 @@ impl CityPower {
         return self.unpowered_zone_count;
     }
 
+    /// Count a zone as powered or not for the current map scan.
+    pub fn count_zone(&mut self, powered: bool) {
+        if powered {
+            self.powered_zone_count += 1;
+        } else {
+            self.unpowered_zone_count += 1;
+        }
+    }
+
+    pub fn get_power_plants(&self) -> &Vec<PowerPlant> {
+        &self.power_plants
+    }
+
+    /// Register a power plant found during the current map scan.
+    ///
+    /// The power plant will feed the power grid on the next power scan.
+    pub fn add_power_plant(&mut self, position: MapPosition, plant_type: PowerPlantType) {
+        match plant_type {
+            PowerPlantType::Coal => self.coal_generators_count += 1,
+            PowerPlantType::Nuclear => self.nuclear_generators_count += 1,
+        }
+        self.power_plants.push(PowerPlant {
+            position,
+            plant_type,
+        });
+    }
+
+    /// Combined capacity of the power plants, in number of tiles, as of the last power scan.
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of tiles powered during the last power scan.
+    pub fn get_load(&self) -> usize {
+        self.load
+    }
+
+    /// Ratio of the power plants capacity used during the last power scan.
+    pub fn get_capacity_usage(&self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.load as f64 / self.capacity as f64
+        }
+    }
+
+    /// Disconnected power grids found during the last power scan, with or
+    /// without power plants.
+    pub fn get_grids(&self) -> &Vec<PowerGrid> {
+        &self.grids
+    }
+
+    /// Number of disconnected power grids found during the last power scan.
+    pub fn get_grids_count(&self) -> usize {
+        self.grids.len()
+    }
+
+    /// Partition the power grid of the given map into connected networks.
+    ///
+    /// Unlike the power scan, this looks at the current state of the map, and
+    /// checks each network against the capacity of its own power plants.
+    pub fn analyze_networks(map: &TileMap) -> PowerNetworks {
+        PowerNetworks::analyze(map)
+    }
+
+    /// Did the last power scan run out of power before reaching all the grids?
+    pub fn is_overloaded(&self) -> bool {
+        self.overloaded
+    }
+
+    /// Is the given position connected to the power grid?
+    pub fn is_powered_at(&self, at: &MapPosition) -> bool {
+        self.power_grid_map
+            .get_tile_at(at)
+            .is_some_and(|power| *power != 0x00)
+    }
+
+    /// Reset the power plants and zones counters before a new map scan.
+    pub fn clear_census(&mut self) {
+        self.coal_generators_count = 0;
+        self.nuclear_generators_count = 0;
+        self.power_plants.clear();
+        self.powered_zone_count = 0;
+        self.unpowered_zone_count = 0;
+        self.power_stack.clear();
+    }
+
     /// Push the given position onto the power stack if there is room.
     pub fn push_power_stack(&mut self, position: MapPosition) {
-        if self.power_stack_pointer < (POWER_STACK_SIZE - 2) {
-            self.power_stack_pointer += 1;
-            self.power_stack[self.power_stack_pointer] = position;
+        if self.power_stack.len() < (POWER_STACK_SIZE - 2) {
+            self.power_stack.push(position);
         }
     }
 
     /// Pull a position from the power stack.
     pub fn pull_power_stack(&mut self) -> Option<MapPosition> {
-        assert!(self.power_stack_pointer > 0);
-        self.power_stack_pointer -= 1;
-        self.power_stack.get(self.power_stack_pointer + 1).cloned()
+        self.power_stack.pop()
     }
 
     /// Checks at the given position for a power-less conducting tile in the given direction.
@@ -99,46 +255,330 @@ impl CityPower {
 
     /// Scans the map for powered tiles, and copy them to the `power_grid_map`.
     ///
+    /// The power grid is flooded from each power plant found during the last map scan,
+    /// until the combined capacity of all the plants is exhausted.
     /// Also warns the user about using too much power.
-    pub fn do_power_scan(&mut self, map: &TileMap) {
+    ///
+    /// See `Micropolis::doPowerScan` in the C++ code.
+    pub fn do_power_scan(&mut self, map: &TileMap, events: &mut CityEvents) {
         let map_bounds = map.bounds();
         // clear power map
         self.power_grid_map = PowerMap::powermap_with_dimensions(&map_bounds, 0x00);
+        let mut grids_map = Map::with_data(
+            vec![vec![NO_GRID; map_bounds.get_height()]; map_bounds.get_width()],
+            MapClusteringStrategy::BlockSize1,
+        );
 
         // combined coal+nuclear power plants deliverable power
         let max_power = self.coal_generators_count * COAL_POWER_STRENGTH
             + self.nuclear_generators_count * NUCLEAR_POWER_STRENGTH;
         let mut power_count: usize = 0;
+        self.capacity = max_power;
+        self.grids.clear();
+        self.overloaded = false;
 
-        while self.power_stack_pointer > 0 {
-            let mut position = self.pull_power_stack().unwrap();
-            let mut direction = MapPositionOffset::None;
-            'inner: loop {
-                power_count += 1;
-                if power_count > max_power {
-                    // TODO: send MESSAGE_NOT_ENOUGH_POWER
-                    return;
-                }
-                if direction != MapPositionOffset::None {
-                    position = direction.apply_with_bounds(&position, &map_bounds).unwrap();
+        // plants are stacked in scan order: flood from the last one first
+        let plants: Vec<PowerPlant> = self.power_plants.iter().rev().cloned().collect();
+        let mut seeded_count = 0;
+        'plants: for plant in &plants {
+            let label = match grids_map.get_tile_at(&plant.position) {
+                Some(&label) if label != NO_GRID => label,
+                _ => {
+                    self.grids.push(PowerGrid {
+                        origin: plant.position,
+                        plants_count: 0,
+                        capacity: 0,
+                        load: 0,
+                    });
+                    self.grids.len() as u16
                 }
-                self.power_grid_map.set_tile_at(&position, 0x01);
-                let mut connections_count: usize = 0;
-                for current_direction in &MAP_POSITION_DIRECTIONS {
-                    if connections_count >= 2 {
-                        break;
+            };
+            let grid_index = label as usize - 1;
+            self.grids[grid_index].plants_count += 1;
+            self.grids[grid_index].capacity += plant.plant_type.capacity();
+            seeded_count += 1;
+            let grid_power_count = power_count;
+            self.push_power_stack(plant.position);
+
+            while let Some(mut position) = self.pull_power_stack() {
+                let mut direction = MapPositionOffset::None;
+                'inner: loop {
+                    power_count += 1;
+                    if power_count > max_power {
+                        self.grids[grid_index].load += max_power - grid_power_count;
+                        self.overloaded = true;
+                        self.power_stack.clear();
+                        events.send_message(GameMessage::MessageNotEnoughPower, None, false, false);
+                        break 'plants;
+                    }
+                    if direction != MapPositionOffset::None {
+                        position = direction.apply_with_bounds(&position, &map_bounds).unwrap();
+                    }
+                    self.power_grid_map.set_tile_at(&position, 0x01);
+                    grids_map.set_tile_at(&position, label);
+                    let mut connections_count: usize = 0;
+                    for current_direction in &POWER_DIRECTIONS {
+                        if connections_count >= 2 {
+                            break;
+                        }
+                        if self.test_for_conductive(map, &position, current_direction) {
+                            connections_count += 1;
+                            direction = *current_direction;
+                        }
                     }
-                    if self.test_for_conductive(map, &position, current_direction) {
-                        connections_count += 1;
-                        direction = current_direction.clone();
+                    if connections_count > 1 {
+                        self.push_power_stack(position);
+                    } else if connections_count == 0 {
+                        break 'inner;
+                    }
+                }
+            }
+            self.grids[grid_index].load += power_count - grid_power_count;
+        }
+        self.load = if self.overloaded {
+            max_power
+        } else {
+            power_count
+        };
+
+        self.find_unpowered_grids(map, &mut grids_map, &plants[seeded_count..]);
+    }
+
+    /// Add the grids left unpowered by the last power scan to the grids report.
+    ///
+    /// These are the grids without any power plant, the grids whose plants were
+    /// not reached because of a power shortage, and the rest of the grid where
+    /// power ran out, which is merged into that grid.
+    fn find_unpowered_grids(
+        &mut self,
+        map: &TileMap,
+        grids_map: &mut Map<u16>,
+        unseeded_plants: &[PowerPlant],
+    ) {
+        let map_bounds = map.bounds();
+        for x in 0..map_bounds.get_width() {
+            for y in 0..map_bounds.get_height() {
+                let start = MapPosition::new(x as i32, y as i32);
+                let is_new_grid = grids_map.get_tile_at(&start) == Some(&NO_GRID)
+                    && map
+                        .get_tile_at(&start)
+                        .is_some_and(|tile| tile.is_conductive());
+                if !is_new_grid {
+                    continue;
+                }
+
+                let mut tiles = vec![start];
+                let mut connected_label = None;
+                grids_map.set_tile_at(&start, FLOODED_GRID);
+                let mut index = 0;
+                while index < tiles.len() {
+                    let position = tiles[index];
+                    index += 1;
+                    for direction in &POWER_DIRECTIONS {
+                        let neighbor = match direction.apply_with_bounds(&position, &map_bounds) {
+                            Some(neighbor) => neighbor,
+                            None => continue,
+                        };
+                        match grids_map.get_tile_at(&neighbor) {
+                            Some(&NO_GRID) => {
+                                if map
+                                    .get_tile_at(&neighbor)
+                                    .is_some_and(|tile| tile.is_conductive())
+                                {
+                                    grids_map.set_tile_at(&neighbor, FLOODED_GRID);
+                                    tiles.push(neighbor);
+                                }
+                            }
+                            Some(&FLOODED_GRID) | None => {}
+                            Some(&label) => connected_label = Some(label),
+                        }
                     }
                 }
-                if connections_count > 1 {
-                    self.push_power_stack(position);
-                } else if connections_count == 0 {
-                    break 'inner;
+
+                let label = connected_label.unwrap_or_else(|| {
+                    self.grids.push(PowerGrid {
+                        origin: start,
+                        plants_count: 0,
+                        capacity: 0,
+                        load: 0,
+                    });
+                    self.grids.len() as u16
+                });
+                for position in &tiles {
+                    grids_map.set_tile_at(position, label);
                 }
             }
         }
+
+        for plant in unseeded_plants {
+            let label = match grids_map.get_tile_at(&plant.position) {
+                Some(&label) if label != NO_GRID => label,
+                _ => continue,
+            };
+            let grid = &mut self.grids[label as usize - 1];
+            if grid.plants_count == 0 {
+                grid.origin = plant.position;
+            }
+            grid.plants_count += 1;
+            grid.capacity += plant.plant_type.capacity();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityPower, PowerGrid, PowerPlantType};
+    use crate::{
+        city::events::CityEvents,
+        map::{tiles::TILE_CONDUCT_BIT, Map, MapPosition, MapRectangle, Tile, TileType},
+    };
+
+    fn set_conductive(map: &mut Map<Tile>, x: i32, y: i32) {
+        let raw = TileType::HorizontalPower.to_u16().unwrap() | TILE_CONDUCT_BIT;
+        map.set_tile_at(&MapPosition::new(x, y), Tile::from_raw(raw).unwrap());
+    }
+
+    /// Count the given zones for the current map scan, as `Simulation::do_zone` does.
+    fn count_zones(power: &mut CityPower, zones: &[(i32, i32)]) {
+        for &(x, y) in zones {
+            let powered = power.is_powered_at(&MapPosition::new(x, y));
+            power.count_zone(powered);
+        }
+    }
+
+    #[test]
+    fn test_power_scan() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        // coal plant feeding a wire, and an isolated nuclear plant
+        for x in 5..=15 {
+            set_conductive(&mut map, x, 5);
+        }
+        set_conductive(&mut map, 16, 6);
+        set_conductive(&mut map, 50, 50);
+
+        let mut power = CityPower::from_map(&map);
+        let mut events = CityEvents::new();
+        power.add_power_plant((5, 5).into(), PowerPlantType::Coal);
+        power.add_power_plant((50, 50).into(), PowerPlantType::Nuclear);
+        power.do_power_scan(&map, &mut events);
+
+        assert!(power.is_powered_at(&(15, 5).into()));
+        assert!(!power.is_powered_at(&(16, 6).into()));
+        assert!(power.is_powered_at(&(50, 50).into()));
+        assert_eq!(power.get_capacity(), 2700);
+        assert_eq!(power.get_load(), 12);
+        // the lone wire tile is an island without power plant
+        assert_eq!(power.get_grids_count(), 3);
+        assert_eq!(
+            power.get_grids(),
+            &vec![
+                PowerGrid {
+                    origin: (50, 50).into(),
+                    plants_count: 1,
+                    capacity: 2000,
+                    load: 1,
+                },
+                PowerGrid {
+                    origin: (5, 5).into(),
+                    plants_count: 1,
+                    capacity: 700,
+                    load: 11,
+                },
+                PowerGrid {
+                    origin: (16, 6).into(),
+                    plants_count: 0,
+                    capacity: 0,
+                    load: 0,
+                },
+            ]
+        );
+        assert!(!power.is_overloaded());
+        assert!(events.is_empty());
+        // zones on the wire, off the wire, and on the nuclear plant island
+        count_zones(&mut power, &[(10, 5), (16, 6), (30, 30), (50, 50)]);
+        assert_eq!(power.get_powered_zone_count(), 2);
+        assert_eq!(power.get_unpowered_zone_count(), 2);
+
+        // not enough power for a large grid
+        for y in 20..30 {
+            for x in 0..120 {
+                set_conductive(&mut map, x, y);
+            }
+        }
+        power.clear_census();
+        assert_eq!(power.get_powered_zone_count(), 0);
+        assert_eq!(power.get_unpowered_zone_count(), 0);
+        power.add_power_plant((0, 20).into(), PowerPlantType::Coal);
+        power.do_power_scan(&map, &mut events);
+        assert!(power.is_overloaded());
+        assert_eq!(power.get_load(), 700);
+        assert_eq!(power.get_capacity_usage(), 1.0);
+        assert_eq!(events.len(), 1);
+        // the zones beyond the first 700 tiles of the grid go dark
+        let powered_tiles = (20..30)
+            .flat_map(|y| (0..120).map(move |x| (x, y)))
+            .filter(|&(x, y)| power.is_powered_at(&MapPosition::new(x, y)))
+            .count();
+        assert_eq!(powered_tiles, 700);
+        count_zones(
+            &mut power,
+            &[(0, 20), (60, 25), (119, 29), (60, 21), (119, 20)],
+        );
+        assert!(!power.is_powered_at(&(60, 21).into()));
+        assert!(!power.is_powered_at(&(119, 20).into()));
+        assert_eq!(power.get_powered_zone_count(), 3);
+        assert_eq!(power.get_unpowered_zone_count(), 2);
+        // the unpowered rest of the large grid is not an island
+        assert_eq!(power.get_grids_count(), 4);
+        assert_eq!(
+            power.get_grids()[0],
+            PowerGrid {
+                origin: (0, 20).into(),
+                plants_count: 1,
+                capacity: 700,
+                load: 700,
+            }
+        );
+        let islands: Vec<_> = power.get_grids()[1..]
+            .iter()
+            .map(|grid| (grid.origin, grid.plants_count, grid.load))
+            .collect();
+        assert_eq!(
+            islands,
+            vec![
+                ((5, 5).into(), 0, 0),
+                ((16, 6).into(), 0, 0),
+                ((50, 50).into(), 0, 0)
+            ]
+        );
+
+        // plants left out by the shortage still count in their grids
+        power.clear_census();
+        power.add_power_plant((50, 50).into(), PowerPlantType::Coal);
+        power.add_power_plant((5, 5).into(), PowerPlantType::Coal);
+        power.add_power_plant((0, 20).into(), PowerPlantType::Coal);
+        power.do_power_scan(&map, &mut events);
+        assert!(power.is_overloaded());
+        assert!(!power.is_powered_at(&(5, 5).into()));
+        assert_eq!(power.get_grids_count(), 4);
+        let grids: Vec<_> = power
+            .get_grids()
+            .iter()
+            .map(|grid| (grid.origin, grid.plants_count, grid.capacity))
+            .collect();
+        assert_eq!(
+            grids,
+            vec![
+                ((0, 20).into(), 1, 700),
+                ((5, 5).into(), 1, 700),
+                ((16, 6).into(), 0, 0),
+                ((50, 50).into(), 1, 700)
+            ]
+        );
+        let total_capacity: usize = power.get_grids().iter().map(|grid| grid.capacity).sum();
+        assert_eq!(total_capacity, power.get_capacity());
+        let total_load: usize = power.get_grids().iter().map(|grid| grid.load).sum();
+        assert_eq!(total_load, power.get_load());
     }
 }
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{
+    tiles::{TILE_LOW_MASK, TILE_ZONE_BIT},
+    Map, MapClusteringStrategy, MapPosition, TileMap, TileType,
+};
+
+use super::{PowerPlant, PowerPlantType, POWER_DIRECTIONS};
+
+/// Value of the networks map for tiles not part of any network.
+const NO_NETWORK: u16 = 0;
+/// Value of the networks map for conductive tiles with neither power plant nor zone.
+const IGNORED_NETWORK: u16 = u16::MAX;
+
+/// A set of conductive tiles connected together.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerNetwork {
+    /// Power plants generating power for this network.
+    pub plants: Vec<PowerPlant>,
+    /// Combined capacity of the power plants, in number of tiles.
+    pub capacity: usize,
+    /// Centers of the zones (other than power plants) consuming power from this network.
+    pub zones: Vec<MapPosition>,
+    /// Number of tiles to power, i.e. the number of conductive tiles of the network.
+    pub load: usize,
+}
+
+impl PowerNetwork {
+    /// Number of tiles the power plants cannot supply power to.
+    pub fn get_shortfall(&self) -> usize {
+        self.load.saturating_sub(self.capacity)
+    }
+
+    /// Can the power plants supply power to the whole network?
+    pub fn is_fully_powered(&self) -> bool {
+        self.capacity > 0 && self.get_shortfall() == 0
+    }
+}
+
+/// Partition of the power grid into connected networks.
+///
+/// Only the networks with at least one power plant or one zone are kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerNetworks {
+    networks: Vec<PowerNetwork>,
+    /// For each tile, index of its network plus one, or `NO_NETWORK`/`IGNORED_NETWORK`.
+    networks_map: Map<u16>,
+}
+
+impl PowerNetworks {
+    /// Find all the power networks of the given map.
+    pub fn analyze(map: &TileMap) -> Self {
+        let bounds = map.bounds();
+        let mut networks = Vec::new();
+        let mut networks_map = Map::with_data(
+            vec![vec![NO_NETWORK; bounds.get_height()]; bounds.get_width()],
+            MapClusteringStrategy::BlockSize1,
+        );
+
+        for x in 0..bounds.get_width() {
+            for y in 0..bounds.get_height() {
+                let start = MapPosition::new(x as i32, y as i32);
+                let is_new_network = map.get_tile_at(&start).is_some_and(|t| t.is_conductive())
+                    && networks_map.get_tile_at(&start) == Some(&NO_NETWORK);
+                if !is_new_network {
+                    continue;
+                }
+
+                let tiles = Self::flood(map, &mut networks_map, start);
+                let network = Self::build_network(map, &tiles);
+                let label = if network.plants.is_empty() && network.zones.is_empty() {
+                    IGNORED_NETWORK
+                } else {
+                    networks.push(network);
+                    networks.len() as u16
+                };
+                for position in &tiles {
+                    networks_map.set_tile_at(position, label);
+                }
+            }
+        }
+
+        PowerNetworks {
+            networks,
+            networks_map,
+        }
+    }
+
+    pub fn get_networks(&self) -> &Vec<PowerNetwork> {
+        &self.networks
+    }
+
+    /// Index of the network the given position is on, if any.
+    pub fn get_network_index_at(&self, at: &MapPosition) -> Option<usize> {
+        match *self.networks_map.get_tile_at(at)? {
+            NO_NETWORK | IGNORED_NETWORK => None,
+            label => Some(label as usize - 1),
+        }
+    }
+
+    /// Network the given position is on, if any.
+    pub fn get_network_at(&self, at: &MapPosition) -> Option<&PowerNetwork> {
+        self.networks.get(self.get_network_index_at(at)?)
+    }
+
+    /// Power plants feeding the given position.
+    pub fn get_feeding_plants(&self, at: &MapPosition) -> &[PowerPlant] {
+        self.get_network_at(at)
+            .map(|network| &network.plants[..])
+            .unwrap_or(&[])
+    }
+
+    /// Combined shortfall of all the networks.
+    pub fn get_total_shortfall(&self) -> usize {
+        self.networks.iter().map(PowerNetwork::get_shortfall).sum()
+    }
+
+    /// Collect all the conductive tiles connected to the given start position.
+    fn flood(map: &TileMap, networks_map: &mut Map<u16>, start: MapPosition) -> Vec<MapPosition> {
+        let bounds = map.bounds();
+        let mut tiles = vec![start];
+        let mut queue = VecDeque::from(vec![start]);
+        networks_map.set_tile_at(&start, IGNORED_NETWORK);
+
+        while let Some(position) = queue.pop_front() {
+            for direction in &POWER_DIRECTIONS {
+                let neighbor = match direction.apply_with_bounds(&position, &bounds) {
+                    Some(neighbor) => neighbor,
+                    None => continue,
+                };
+                let is_conductive = map
+                    .get_tile_at(&neighbor)
+                    .is_some_and(|t| t.is_conductive());
+                if is_conductive && networks_map.get_tile_at(&neighbor) == Some(&NO_NETWORK) {
+                    networks_map.set_tile_at(&neighbor, IGNORED_NETWORK);
+                    tiles.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        tiles
+    }
+
+    fn build_network(map: &TileMap, tiles: &[MapPosition]) -> PowerNetwork {
+        let coal = TileType::PowerPlant.to_u16().unwrap();
+        let nuclear = TileType::Nuclear.to_u16().unwrap();
+
+        let mut network = PowerNetwork {
+            plants: Vec::new(),
+            capacity: 0,
+            zones: Vec::new(),
+            load: tiles.len(),
+        };
+        for position in tiles {
+            let raw = match map.get_tile_at(position) {
+                Some(tile) if tile.get_raw() & TILE_ZONE_BIT == TILE_ZONE_BIT => tile.get_raw(),
+                _ => continue,
+            };
+            let plant_type = match raw & TILE_LOW_MASK {
+                value if value == coal => PowerPlantType::Coal,
+                value if value == nuclear => PowerPlantType::Nuclear,
+                _ => {
+                    network.zones.push(*position);
+                    continue;
+                }
+            };
+            network.capacity += plant_type.capacity();
+            network.plants.push(PowerPlant {
+                position: *position,
+                plant_type,
+            });
+        }
+        network
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        city::power::{CityPower, PowerPlantType},
+        map::{
+            tiles::{TILE_CONDUCT_BIT, TILE_ZONE_BIT},
+            Map, MapPosition, MapRectangle, Tile, TileType,
+        },
+    };
+
+    fn set_tile(map: &mut Map<Tile>, x: i32, y: i32, tile_type: TileType, zone: bool) {
+        let mut raw = tile_type.to_u16().unwrap() | TILE_CONDUCT_BIT;
+        if zone {
+            raw |= TILE_ZONE_BIT;
+        }
+        map.set_tile_at(&MapPosition::new(x, y), Tile::from_raw(raw).unwrap());
+    }
+
+    #[test]
+    fn test_power_networks() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        // coal plant wired to a residential zone
+        set_tile(&mut map, 10, 10, TileType::PowerPlant, true);
+        for x in 11..20 {
+            set_tile(&mut map, x, 10, TileType::HorizontalPower, false);
+        }
+        set_tile(&mut map, 20, 10, TileType::FreeZoneCenter, true);
+        // isolated nuclear plant
+        set_tile(&mut map, 60, 60, TileType::Nuclear, true);
+        // zone with no power plant
+        set_tile(&mut map, 90, 90, TileType::FreeZoneCenter, true);
+        // wire with nothing connected
+        set_tile(&mut map, 5, 50, TileType::HorizontalPower, false);
+
+        let networks = CityPower::analyze_networks(&map);
+        assert_eq!(networks.get_networks().len(), 3);
+
+        let coal_network = networks.get_network_at(&(15, 10).into()).unwrap();
+        assert_eq!(coal_network.plants.len(), 1);
+        assert_eq!(coal_network.plants[0].plant_type, PowerPlantType::Coal);
+        assert_eq!(coal_network.capacity, 700);
+        assert_eq!(coal_network.zones, vec![MapPosition::new(20, 10)]);
+        assert_eq!(coal_network.load, 11);
+        assert!(coal_network.is_fully_powered());
+        assert_eq!(
+            networks.get_feeding_plants(&(20, 10).into())[0].position,
+            MapPosition::new(10, 10)
+        );
+
+        let unpowered_network = networks.get_network_at(&(90, 90).into()).unwrap();
+        assert_eq!(unpowered_network.capacity, 0);
+        assert_eq!(unpowered_network.get_shortfall(), 1);
+        assert!(!unpowered_network.is_fully_powered());
+        assert_eq!(networks.get_total_shortfall(), 1);
+
+        assert_ne!(
+            networks.get_network_index_at(&(60, 60).into()),
+            networks.get_network_index_at(&(10, 10).into())
+        );
+        assert!(networks.get_network_at(&(5, 50).into()).is_none());
+        assert!(networks.get_feeding_plants(&(0, 0).into()).is_empty());
+    }
+}
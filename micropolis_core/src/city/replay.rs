@@ -0,0 +1,560 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::Engine,
+    error::MicropolisError,
+    map::{
+        tools::{apply_tool, tool_down, EditingTool},
+        MapPosition,
+    },
+    utils::Percentage,
+};
+
+use super::{
+    budget::BudgetLineKind, disasters::DisasterKind, fire::Wind, snapshot::CitySnapshot,
+    state_hash::CityStateHash, traffic::routing::TrafficModel, City,
+};
+
+/// Current version of the game recording format.
+///
+/// Must be incremented whenever the recorded commands or the state hashes change.
+pub const GAME_RECORDING_VERSION: u32 = 5;
+
+/// A player input that changes the course of a game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameCommand {
+    /// Apply a tool on a single tile.
+    ToolDown {
+        tool: EditingTool,
+        position: MapPosition,
+        auto_bulldoze: bool,
+        animations_enabled: bool,
+    },
+    /// Drag a tool from one tile to another.
+    ToolDrag {
+        tool: EditingTool,
+        from: MapPosition,
+        to: MapPosition,
+        auto_bulldoze: bool,
+        animations_enabled: bool,
+    },
+    /// Change the city tax rate, from 0 to 20%.
+    SetTaxRate(u16),
+    /// Change the funding level of a budget line, from 0 to 100%.
+    SetBudgetPercentage {
+        line: BudgetLineKind,
+        percentage: u8,
+    },
+    /// Change the simulation speed, from 0 to 3.
+    SetSimulationSpeed(u8),
+    /// Trigger a disaster at the given map position, or at a random one.
+    TriggerDisaster {
+        kind: DisasterKind,
+        at: Option<MapPosition>,
+    },
+    /// Plan a disaster for the given city time, at the given map position or at a random one.
+    ScheduleDisaster {
+        kind: DisasterKind,
+        city_time: u32,
+        at: Option<MapPosition>,
+    },
+    /// Enable or disable the random disasters.
+    SetDisastersEnabled(bool),
+    /// Change the wind blowing over the city.
+    SetWind(Wind),
+    /// Change how the trips of the traffic simulation are made.
+    SetTrafficModel(TrafficModel),
+}
+
+impl GameCommand {
+    /// Apply the command to the city.
+    ///
+    /// Tools draw their random numbers from the generator of the city.
+    pub fn apply(&self, city: &mut City) -> Result<(), MicropolisError> {
+        use GameCommand::*;
+        match self {
+            ToolDown {
+                tool,
+                position,
+                auto_bulldoze,
+                animations_enabled,
+            } => {
+                let total_funds = city.total_funds();
+                let result = tool_down(
+                    &mut city.rng,
+                    &mut city.map,
+                    position,
+                    tool,
+                    *auto_bulldoze,
+                    *animations_enabled,
+                    total_funds,
+                    &mut city.events,
+                )?;
+                city.charge_tool_result(&result)?;
+                Self::on_map_edited(city);
+            }
+            ToolDrag {
+                tool,
+                from,
+                to,
+                auto_bulldoze,
+                animations_enabled,
+            } => {
+                // the tiles edited before a failure stay edited
+                let dragged = Self::drag_path(tool, from, to)
+                    .iter()
+                    .try_for_each(|position| {
+                        let total_funds = city.total_funds();
+                        let result = apply_tool(
+                            &mut city.rng,
+                            &mut city.map,
+                            position,
+                            tool,
+                            *auto_bulldoze,
+                            *animations_enabled,
+                            total_funds,
+                            &mut city.events,
+                        )?;
+                        city.charge_tool_result(&result)
+                    });
+                Self::on_map_edited(city);
+                dragged?;
+            }
+            SetTaxRate(tax) => {
+                if *tax > 20 {
+                    return Err(MicropolisError::InvalidArgument(format!(
+                        "invalid tax rate {}",
+                        tax
+                    )));
+                }
+                city.sim.get_taxes_mut().city_tax = *tax;
+            }
+            SetBudgetPercentage { line, percentage } => {
+                let percentage = Percentage::from_integer(*percentage).ok_or_else(|| {
+                    MicropolisError::InvalidArgument(format!(
+                        "invalid budget percentage {}",
+                        percentage
+                    ))
+                })?;
+                city.budget.get_line_mut(*line).set_percentage(percentage);
+            }
+            SetSimulationSpeed(speed) => city.set_simulation_speed(*speed),
+            TriggerDisaster { kind, at } => city.trigger_disaster(*kind, *at)?,
+            ScheduleDisaster {
+                kind,
+                city_time,
+                at,
+            } => city.disasters.schedule_disaster(*kind, *city_time, *at),
+            SetDisastersEnabled(enabled) => city.disasters.set_disasters_enabled(*enabled),
+            SetWind(wind) => city.fires.set_wind(*wind),
+            SetTrafficModel(model) => city.traffic.set_model(*model),
+        }
+        Ok(())
+    }
+
+    /// Tiles a dragged tool is applied on, in order.
+    ///
+    /// Big tools are only applied at the end of the drag.
+    fn drag_path(tool: &EditingTool, from: &MapPosition, to: &MapPosition) -> Vec<MapPosition> {
+        if tool.clone().size() > 1 {
+            return vec![*to];
+        }
+        let mut path = vec![*from];
+        let mut current = *from;
+        while current != *to {
+            let (dx, dy) = (to.get_x() - current.get_x(), to.get_y() - current.get_y());
+            current = if dx.abs() >= dy.abs() {
+                MapPosition::new(current.get_x() + dx.signum(), current.get_y())
+            } else {
+                MapPosition::new(current.get_x(), current.get_y() + dy.signum())
+            };
+            path.push(current);
+        }
+        path
+    }
+
+    fn on_map_edited(city: &mut City) {
+        city.sim.reset_pass_counter();
+        city.invalidate_map();
+    }
+}
+
+/// A command, stamped with the tick before which it was applied.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    pub tick: u64,
+    pub command: GameCommand,
+    /// Did the command fail after changing the city (e.g. a drag running out of funds)?
+    pub failed: bool,
+}
+
+/// Everything needed to replay a game from its start.
+///
+/// The simulation is fully deterministic, so the initial state of the city,
+/// the seed of the frame random number generator and the player commands are
+/// enough to reproduce the final state of the city.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecording {
+    /// Version of the recording format.
+    version: u32,
+    /// Seed of the random number generator used by the simulation steps and the tools.
+    seed: i32,
+    /// State of the city when the recording started.
+    initial_state: CitySnapshot,
+    /// Recorded commands, in order.
+    commands: Vec<RecordedCommand>,
+    /// Number of frame ticks run during the recording.
+    ticks: u64,
+    /// Hash of the city state after each tick, to detect a diverging replay.
+    state_hashes: Vec<CityStateHash>,
+}
+
+impl GameRecording {
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get_seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn get_initial_state(&self) -> &CitySnapshot {
+        &self.initial_state
+    }
+
+    pub fn get_commands(&self) -> &Vec<RecordedCommand> {
+        &self.commands
+    }
+
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn get_state_hashes(&self) -> &Vec<CityStateHash> {
+        &self.state_hashes
+    }
+}
+
+/// Run a game while recording its inputs.
+pub struct GameRecorder {
+    engine: Engine,
+    recording: GameRecording,
+}
+
+impl GameRecorder {
+    /// Start recording a game from the current state of the given city.
+    pub fn start(city: City, seed: i32) -> Self {
+        let recording = GameRecording {
+            version: GAME_RECORDING_VERSION,
+            seed,
+            initial_state: city.snapshot(),
+            commands: vec![],
+            ticks: 0,
+            state_hashes: vec![],
+        };
+        GameRecorder {
+            engine: Engine::with_seed(city, seed),
+            recording,
+        }
+    }
+
+    pub fn get_city(&self) -> &City {
+        self.engine.get_city()
+    }
+
+    /// Number of frame ticks run since the recording started.
+    pub fn get_tick(&self) -> u64 {
+        self.engine.get_ticks()
+    }
+
+    /// Apply a command to the city, and record it if it succeeded.
+    ///
+    /// Failed commands are recorded too when they changed the city or drew
+    /// random numbers before failing, so that the replay goes through the same
+    /// states.
+    pub fn execute(&mut self, command: GameCommand) -> Result<(), MicropolisError> {
+        let state_before = self.engine.state_hash();
+        let result = self.engine.execute(&command);
+        if result.is_ok() || state_before != self.engine.state_hash() {
+            self.recording.commands.push(RecordedCommand {
+                tick: self.engine.get_ticks(),
+                command,
+                failed: result.is_err(),
+            });
+        }
+        result
+    }
+
+    /// Advance the city by one frame tick.
+    pub fn tick(&mut self) -> Result<(), MicropolisError> {
+        self.engine.tick()?;
+        self.recording.ticks = self.engine.get_ticks();
+        self.recording.state_hashes.push(self.engine.state_hash());
+        Ok(())
+    }
+
+    /// Stop recording, returning the city and its recording.
+    pub fn finish(self) -> (City, GameRecording) {
+        (self.engine.into_city(), self.recording)
+    }
+}
+
+/// Re-run a recorded game.
+pub struct GameReplayer {
+    engine: Engine,
+    recording: GameRecording,
+    /// Index of the next command to apply.
+    next_command: usize,
+}
+
+impl GameReplayer {
+    /// Prepare the replay of the given recording.
+    ///
+    /// Fails if the recording was made with an incompatible format version.
+    pub fn new(recording: GameRecording) -> Result<Self, MicropolisError> {
+        if recording.version != GAME_RECORDING_VERSION {
+            return Err(MicropolisError::UnsupportedVersion {
+                found: recording.version,
+                expected: GAME_RECORDING_VERSION,
+            });
+        }
+        let city = City::from_snapshot(recording.initial_state.clone())?;
+        Ok(GameReplayer {
+            engine: Engine::with_seed(city, recording.seed),
+            recording,
+            next_command: 0,
+        })
+    }
+
+    pub fn get_city(&self) -> &City {
+        self.engine.get_city()
+    }
+
+    /// Number of frame ticks replayed so far.
+    pub fn get_tick(&self) -> u64 {
+        self.engine.get_ticks()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.get_tick() >= self.recording.ticks
+            && self.next_command >= self.recording.commands.len()
+    }
+
+    /// Apply the commands recorded for the current tick, then run it.
+    ///
+    /// Returns false once the whole recording has been replayed, and fails as
+    /// soon as the replayed city diverges from the recorded one.
+    pub fn step(&mut self) -> Result<bool, MicropolisError> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+        let tick = self.get_tick();
+        while let Some(recorded) = self.recording.commands.get(self.next_command) {
+            if recorded.tick != tick {
+                break;
+            }
+            match self.engine.execute(&recorded.command) {
+                Err(_) if recorded.failed => {}
+                result => result?,
+            }
+            self.next_command += 1;
+        }
+        if tick < self.recording.ticks {
+            self.engine.tick()?;
+            if let Some(expected) = self.recording.state_hashes.get(tick as usize) {
+                if let Some(subsystem) = expected.first_mismatch(&self.engine.state_hash()) {
+                    return Err(MicropolisError::Desync { tick, subsystem });
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Replay the whole recording, returning the final city.
+    pub fn run(mut self) -> Result<City, MicropolisError> {
+        while self.step()? {}
+        Ok(self.engine.into_city())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameCommand, GameRecorder, GameRecording, GameReplayer};
+    use crate::{
+        city::{
+            budget::BudgetLineKind, disasters::DisasterKind, fire::Wind,
+            traffic::routing::TrafficModel, City,
+        },
+        error::MicropolisError,
+        game::GameScenario,
+        map::{tiles::TILE_LOW_MASK, tools::EditingTool, MapPositionOffset, Tile, TileType},
+    };
+
+    fn record_game() -> (City, GameRecording) {
+        let city = City::new("Replayville".into(), GameScenario::None).unwrap();
+        let mut recorder = GameRecorder::start(city, 4321);
+        let commands = vec![
+            (
+                0,
+                GameCommand::ToolDown {
+                    tool: EditingTool::CoalPower,
+                    position: (20, 20).into(),
+                    auto_bulldoze: true,
+                    animations_enabled: true,
+                },
+            ),
+            (
+                3,
+                GameCommand::ToolDrag {
+                    tool: EditingTool::Park,
+                    from: (22, 20).into(),
+                    to: (30, 24).into(),
+                    auto_bulldoze: true,
+                    animations_enabled: true,
+                },
+            ),
+            (
+                3,
+                GameCommand::ToolDrag {
+                    tool: EditingTool::Bulldozer,
+                    from: (21, 20).into(),
+                    to: (27, 21).into(),
+                    auto_bulldoze: true,
+                    animations_enabled: true,
+                },
+            ),
+            (
+                10,
+                GameCommand::ToolDown {
+                    tool: EditingTool::Residential,
+                    position: (31, 25).into(),
+                    auto_bulldoze: true,
+                    animations_enabled: true,
+                },
+            ),
+            (
+                12,
+                GameCommand::SetWind(Wind {
+                    direction: MapPositionOffset::NorthEast,
+                    strength: 2,
+                }),
+            ),
+            (15, GameCommand::SetTrafficModel(TrafficModel::ShortestPath)),
+            (20, GameCommand::SetTaxRate(9)),
+            (
+                25,
+                GameCommand::SetBudgetPercentage {
+                    line: BudgetLineKind::Roads,
+                    percentage: 80,
+                },
+            ),
+            (30, GameCommand::SetDisastersEnabled(false)),
+            (
+                30,
+                GameCommand::ScheduleDisaster {
+                    kind: DisasterKind::Fire,
+                    city_time: 1,
+                    at: None,
+                },
+            ),
+            (40, GameCommand::SetSimulationSpeed(3)),
+            (
+                60,
+                GameCommand::TriggerDisaster {
+                    kind: DisasterKind::Tornado,
+                    at: Some((35, 30).into()),
+                },
+            ),
+        ];
+        let mut commands = commands.into_iter().peekable();
+        for tick in 0..200 {
+            while let Some((_, command)) = commands.next_if(|(at, _)| *at == tick) {
+                recorder.execute(command).unwrap();
+            }
+            recorder.tick().unwrap();
+        }
+        recorder
+            .execute(GameCommand::SetSimulationSpeed(1))
+            .unwrap();
+        recorder.finish()
+    }
+
+    #[test]
+    fn test_replay_reproduces_final_state() {
+        let (city, recording) = record_game();
+        assert_eq!(recording.get_commands().len(), 13);
+        assert_eq!(recording.get_ticks(), 200);
+        assert_ne!(&city, recording.get_initial_state().get_city());
+
+        let json = serde_json::to_string(&recording).unwrap();
+        let replayed = GameReplayer::new(serde_json::from_str(&json).unwrap())
+            .unwrap()
+            .run()
+            .unwrap();
+        assert_eq!(replayed, city);
+    }
+
+    #[test]
+    fn test_replay_desync() {
+        let (_, mut recording) = record_game();
+        recording.seed += 1;
+        let error = GameReplayer::new(recording).unwrap().run().unwrap_err();
+        assert!(matches!(error, MicropolisError::Desync { .. }));
+    }
+
+    #[test]
+    fn test_replay_invalid_command() {
+        let city = City::new("Replayville".into(), GameScenario::None).unwrap();
+        let mut recorder = GameRecorder::start(city, 4321);
+        assert!(matches!(
+            recorder.execute(GameCommand::SetTaxRate(42)),
+            Err(MicropolisError::InvalidArgument(_))
+        ));
+        assert!(recorder
+            .execute(GameCommand::SetBudgetPercentage {
+                line: BudgetLineKind::FireDepartment,
+                percentage: 101,
+            })
+            .is_err());
+        let (_, recording) = recorder.finish();
+        assert!(recording.get_commands().is_empty());
+    }
+
+    #[test]
+    fn test_replay_partially_failed_command() {
+        let mut city = City::new("Replayville".into(), GameScenario::None).unwrap();
+        // the drag clears some woods, then fails on a corrupted tile
+        let invalid_raw = (0..TILE_LOW_MASK)
+            .find(|raw| TileType::from_u16(*raw).is_none())
+            .unwrap();
+        for x in 10..13 {
+            city.map
+                .set_tile_at(&(x, 50).into(), Tile::from_type(TileType::Woods).unwrap());
+        }
+        city.map
+            .set_tile_at(&(13, 50).into(), Tile::from_raw(invalid_raw).unwrap());
+        let mut recorder = GameRecorder::start(city, 4321);
+        recorder.tick().unwrap();
+        let hash_before = recorder.get_city().state_hash();
+        assert!(matches!(
+            recorder.execute(GameCommand::ToolDrag {
+                tool: EditingTool::Bulldozer,
+                from: (10, 50).into(),
+                to: (15, 50).into(),
+                auto_bulldoze: true,
+                animations_enabled: true,
+            }),
+            Err(MicropolisError::InvalidTileValue(_))
+        ));
+        assert_ne!(recorder.get_city().state_hash(), hash_before);
+        for _ in 0..20 {
+            recorder.tick().unwrap();
+        }
+        let (city, recording) = recorder.finish();
+        assert_eq!(recording.get_commands().len(), 1);
+        assert!(recording.get_commands()[0].failed);
+
+        let replayed = GameReplayer::new(recording).unwrap().run().unwrap();
+        assert_eq!(replayed, city);
+    }
+}
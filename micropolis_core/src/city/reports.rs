@@ -1,5 +1,52 @@
-use crate::map::MapPosition;
+use serde::{Deserialize, Serialize};
 
+use super::City;
+use crate::{
+    map::{
+        tiles::{TILE_LOW_MASK, TILE_POWER_BIT, TILE_ZONE_BIT},
+        MapPosition, TileType,
+    },
+    messages::{ScoreCardString, TileKindString},
+};
+
+/// First tile value of each tile kind, in increasing order.
+///
+/// See `idArray` in the C++ code.
+const TILE_KINDS: [(u16, TileKindString); 28] = [
+    (0, TileKindString::Clear),                  // DIRT
+    (2, TileKindString::Water),                  // RIVER
+    (21, TileKindString::Trees),                 // TREEBASE
+    (44, TileKindString::Rubble),                // RUBBLE
+    (48, TileKindString::Flood),                 // FLOOD
+    (52, TileKindString::RadioactiveWaste),      // RADTILE
+    (56, TileKindString::Fire),                  // FIRE
+    (64, TileKindString::Road),                  // ROADBASE
+    (208, TileKindString::Power),                // POWERBASE
+    (224, TileKindString::Rail),                 // RAILBASE
+    (240, TileKindString::Residential),          // RESBASE
+    (423, TileKindString::Commercial),           // COMBASE
+    (612, TileKindString::Industrial),           // INDBASE
+    (693, TileKindString::Seaport),              // PORTBASE
+    (709, TileKindString::Airport),              // AIRPORTBASE
+    (745, TileKindString::CoalPower),            // COALBASE
+    (761, TileKindString::FireDepartment),       // FIRESTBASE
+    (770, TileKindString::PoliceDepartment),     // POLICESTBASE
+    (779, TileKindString::Stadium),              // STADIUMBASE
+    (811, TileKindString::NuclearPower),         // NUCLEARBASE
+    (828, TileKindString::HorizontalDrawBridge), // HBRDG0
+    (832, TileKindString::RadarDish),            // RADAR0
+    (840, TileKindString::Fountain),             // FOUNTAIN
+    (844, TileKindString::IndustrialAnimation),  // INDBASE2
+    (932, TileKindString::FootballGame),         // FOOTBALLGAME1
+    (948, TileKindString::VerticalDrawBridge),   // VBRDG0
+    (952, TileKindString::NuclearSwirl),
+    (956, TileKindString::Unknown),
+];
+
+/// Maximum distance between a tile and the center of the zone it belongs to.
+const MAX_ZONE_CENTER_DISTANCE: i32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CityQuery {
     PopulationDensity,
     LandValue,
@@ -8,15 +55,237 @@ pub enum CityQuery {
     GrowthRate,
 }
 
-pub struct CityReportBuilder;
+/// Everything the "Query" tool tells about a tile.
+///
+/// See `Micropolis::doZoneStatus` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileStatusReport {
+    /// Queried map position.
+    pub position: MapPosition,
+    /// Kind of the tile.
+    pub kind: TileKindString,
+    pub population_density: ScoreCardString,
+    pub land_value: ScoreCardString,
+    pub crime: ScoreCardString,
+    pub pollution: ScoreCardString,
+    pub growth_rate: ScoreCardString,
+    /// Is the tile (or the zone it belongs to) powered?
+    pub powered: bool,
+    /// Center of the zone the tile belongs to, if any.
+    pub zone_center: Option<MapPosition>,
+    /// Traffic density around the tile.
+    pub traffic_density: u8,
+}
+
+/// Answers the queries of the player about the tiles of a city.
+pub struct CityReportBuilder<'a> {
+    city: &'a City,
+}
+
+impl<'a> CityReportBuilder<'a> {
+    pub fn new(city: &'a City) -> Self {
+        CityReportBuilder { city }
+    }
+
+    /// Bucket the value of the given overlay at the given position into its
+    /// score card category.
+    ///
+    /// Returns None if the position is outside of the map.
+    ///
+    /// See `Micropolis::getDensity` in the C++ code.
+    pub fn query_tile(&self, category: &CityQuery, at: &MapPosition) -> Option<ScoreCardString> {
+        let sim = &self.city.sim;
+        Some(match category {
+            CityQuery::PopulationDensity => {
+                match (self.city.population.get_density_map().get_tile_at(at)? >> 6) & 3 {
+                    0 => ScoreCardString::PopulationdensityLow,
+                    1 => ScoreCardString::PopulationdensityMedium,
+                    2 => ScoreCardString::PopulationdensityHigh,
+                    _ => ScoreCardString::PopulationdensityVeryhigh,
+                }
+            }
+            CityQuery::LandValue => match *sim.get_land_value_map().get_tile_at(at)? {
+                z if z < 30 => ScoreCardString::LandvalueSlum,
+                z if z < 80 => ScoreCardString::LandvalueLowerClass,
+                z if z < 150 => ScoreCardString::LandvalueMiddleClass,
+                _ => ScoreCardString::LandvalueHighClass,
+            },
+            CityQuery::CrimeRate => match (sim.get_crime_rate_map().get_tile_at(at)? >> 6) & 3 {
+                0 => ScoreCardString::CrimeNone,
+                1 => ScoreCardString::CrimeLight,
+                2 => ScoreCardString::CrimeModerate,
+                _ => ScoreCardString::CrimeDangerous,
+            },
+            CityQuery::Pollution => match *sim.get_pollution_density_map().get_tile_at(at)? {
+                z if z > 0 && z < 64 => ScoreCardString::PollutionModerate,
+                z => match (z >> 6) & 3 {
+                    0 => ScoreCardString::PollutionNone,
+                    1 => ScoreCardString::PollutionModerate,
+                    2 => ScoreCardString::PollutionHeavy,
+                    _ => ScoreCardString::PollutionVeryHeavy,
+                },
+            },
+            CityQuery::GrowthRate => match *sim.get_rate_of_growth_map().get_tile_at(at)? {
+                z if z < 0 => ScoreCardString::GrowrateDeclining,
+                0 => ScoreCardString::GrowrateStable,
+                z if z > 100 => ScoreCardString::GrowrateFastGrowth,
+                _ => ScoreCardString::GrowrateSlowGrowth,
+            },
+        })
+    }
+
+    /// Build the full status report of the tile at the given position.
+    ///
+    /// Returns None if the position is outside of the map.
+    pub fn tile_status(&self, at: &MapPosition) -> Option<TileStatusReport> {
+        let tile_value = self.city.map.get_tile_char_at(at)?;
+        let zone_center = self.find_zone_center(at);
+        let powered = match &zone_center {
+            Some(center) => self
+                .city
+                .map
+                .get_tile_at(center)
+                .is_some_and(|tile| tile.get_raw() & TILE_POWER_BIT == TILE_POWER_BIT),
+            None => self.city.power.is_powered_at(at),
+        };
 
-impl CityReportBuilder {
-    // TODO: query cost
-    pub fn query_tile(&self, category: &CityQuery, at: &MapPosition) -> usize {
-        todo!()
+        Some(TileStatusReport {
+            position: *at,
+            kind: Self::tile_kind(tile_value),
+            population_density: self.query_tile(&CityQuery::PopulationDensity, at)?,
+            land_value: self.query_tile(&CityQuery::LandValue, at)?,
+            crime: self.query_tile(&CityQuery::CrimeRate, at)?,
+            pollution: self.query_tile(&CityQuery::Pollution, at)?,
+            growth_rate: self.query_tile(&CityQuery::GrowthRate, at)?,
+            powered,
+            zone_center,
+            traffic_density: self
+                .city
+                .traffic
+                .get_density_map()
+                .get_tile_at(at)
+                .copied()
+                .unwrap_or(0),
+        })
     }
 
-    pub fn tile_status(&self, at: &MapPosition) {
-        todo!()
+    /// Find the kind of a tile from its value.
+    fn tile_kind(tile_value: u16) -> TileKindString {
+        // coal smoke is part of the coal power plant
+        let tile_value = if tile_value >= TileType::CoalSmoke1.to_u16().unwrap()
+            && tile_value < TileType::FootballGame1.to_u16().unwrap()
+        {
+            TileType::CoalBase.to_u16().unwrap()
+        } else {
+            tile_value
+        };
+        TILE_KINDS
+            .iter()
+            .rev()
+            .find(|(base, _)| tile_value >= *base)
+            .map(|(_, kind)| *kind)
+            .unwrap_or(TileKindString::Unknown)
+    }
+
+    /// Find the center of the zone covering the given position, if any.
+    fn find_zone_center(&self, at: &MapPosition) -> Option<MapPosition> {
+        let map = &self.city.map;
+        for dy in -MAX_ZONE_CENTER_DISTANCE..=1 {
+            for dx in -MAX_ZONE_CENTER_DISTANCE..=1 {
+                let center = MapPosition::new(at.get_x() + dx, at.get_y() + dy);
+                let raw = match map.get_tile_at(&center) {
+                    Some(tile) if tile.get_raw() & TILE_ZONE_BIT == TILE_ZONE_BIT => tile.get_raw(),
+                    _ => continue,
+                };
+                // the zone center is at (1, 1) from its top-left corner
+                let size = Self::zone_size(raw & TILE_LOW_MASK);
+                if -dx < size - 1 && -dy < size - 1 {
+                    return Some(center);
+                }
+            }
+        }
+        None
+    }
+
+    /// Footprint size of the zone with the given center tile value.
+    fn zone_size(center_value: u16) -> i32 {
+        match center_value {
+            v if v >= TileType::Church1Base.to_u16().unwrap() => 3,
+            v if v < TileType::PortBase.to_u16().unwrap() => 3,
+            v if v < TileType::AirportBase.to_u16().unwrap() => 4,
+            v if v < TileType::CoalBase.to_u16().unwrap() => 6,
+            v if v < TileType::FireStationBase.to_u16().unwrap() => 4,
+            v if v < TileType::StadiumBase.to_u16().unwrap() => 3,
+            _ => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityQuery, CityReportBuilder};
+    use crate::{
+        city::City,
+        game::GameScenario,
+        map::{
+            tiles::{TILE_POWER_BIT, TILE_ZONE_BIT},
+            MapPosition, TileType,
+        },
+        messages::{ScoreCardString, TileKindString},
+    };
+
+    #[test]
+    fn test_tile_status() {
+        let mut city = City::new("Queryville".into(), GameScenario::None).unwrap();
+        let center = MapPosition::new(10, 10);
+        let queried = MapPosition::new(11, 9);
+        let base = TileType::ResidentialBase.to_u16().unwrap();
+        for (i, (x, y)) in (9..12)
+            .flat_map(|y| (9..12).map(move |x| (x, y)))
+            .enumerate()
+        {
+            city.map
+                .get_tile_mut_at(&MapPosition::new(x, y))
+                .unwrap()
+                .set_raw(base + i as u16);
+        }
+        city.map
+            .get_tile_mut_at(&center)
+            .unwrap()
+            .set_raw((base + 4) | TILE_ZONE_BIT | TILE_POWER_BIT);
+        city.map
+            .get_tile_mut_at(&MapPosition::new(20, 20))
+            .unwrap()
+            .set_raw(TileType::River.to_u16().unwrap());
+        city.population
+            .get_density_map_mut()
+            .set_tile_at(&queried, 200);
+        city.traffic.get_density_map_mut().set_tile_at(&queried, 42);
+
+        let reports = CityReportBuilder::new(&city);
+        let status = reports.tile_status(&queried).unwrap();
+        assert_eq!(status.kind, TileKindString::Residential);
+        assert_eq!(status.zone_center, Some(center));
+        assert!(status.powered);
+        assert_eq!(
+            status.population_density,
+            ScoreCardString::PopulationdensityVeryhigh
+        );
+        assert_eq!(status.land_value, ScoreCardString::LandvalueSlum);
+        assert_eq!(status.crime, ScoreCardString::CrimeNone);
+        assert_eq!(status.pollution, ScoreCardString::PollutionNone);
+        assert_eq!(status.growth_rate, ScoreCardString::GrowrateStable);
+        assert_eq!(status.traffic_density, 42);
+
+        let status = reports.tile_status(&MapPosition::new(20, 20)).unwrap();
+        assert_eq!(status.kind, TileKindString::Water);
+        assert_eq!(status.zone_center, None);
+        assert!(!status.powered);
+
+        assert_eq!(
+            reports.query_tile(&CityQuery::LandValue, &MapPosition::new(200, 20)),
+            None
+        );
+        assert!(reports.tile_status(&MapPosition::new(-1, 0)).is_none());
     }
 }
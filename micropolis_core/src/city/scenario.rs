@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{city::budget::MoneyValue, game::GameScenario};
+
+use super::{
+    evaluate::CityEvaluator, population::CityClass, simulation::statistics::SimulationStatistics,
+};
+
+/// Number of city time units per year.
+const CITY_TIME_UNITS_PER_YEAR: u32 = 48;
+
+/// Goal to reach before the time limit of a scenario runs out.
+///
+/// See `Micropolis::doScenarioScore` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioGoal {
+    /// The city must reach at least the given class.
+    MinimumCityClass(CityClass),
+    /// The average traffic must be strictly below the given value.
+    MaximumTrafficAverage(u16),
+    /// The average crime must be strictly below the given value.
+    MaximumCrimeAverage(u16),
+    /// The city score must be strictly above the given value.
+    MinimumScore(u16),
+}
+
+impl ScenarioGoal {
+    pub fn is_reached(&self, indicators: &ScenarioIndicators) -> bool {
+        use ScenarioGoal::*;
+        match self {
+            MinimumCityClass(class) => indicators.city_class >= *class,
+            MaximumTrafficAverage(traffic) => indicators.traffic_average < *traffic,
+            MaximumCrimeAverage(crime) => indicators.crime_average < *crime,
+            MinimumScore(score) => indicators.score > *score,
+        }
+    }
+}
+
+/// City indicators used to judge the outcome of a scenario.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScenarioIndicators {
+    /// Class of the city, from the last evaluation.
+    pub city_class: CityClass,
+    /// Score of the city, from the last evaluation.
+    pub score: u16,
+    /// Average traffic, from the last evaluation.
+    pub traffic_average: u16,
+    /// Average crime.
+    pub crime_average: u16,
+}
+
+impl ScenarioIndicators {
+    pub fn from_evaluation(evaluator: &CityEvaluator, statistics: &SimulationStatistics) -> Self {
+        ScenarioIndicators {
+            city_class: evaluator.get_class().clone(),
+            score: evaluator.get_score().get_current(),
+            traffic_average: evaluator.get_traffic_average(),
+            crime_average: statistics.average_crime,
+        }
+    }
+}
+
+/// Outcome of a scenario, once its time limit ran out.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioOutcome {
+    Won,
+    Lost,
+}
+
+/// Static description of a scenario.
+///
+/// See `Micropolis::loadScenario` and `scoreWaitTable` in the C++ code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScenarioDefinition {
+    /// Name of the scenario city.
+    pub name: &'static str,
+    /// Name of the city file containing the starting city.
+    pub file_name: &'static str,
+    /// Year the scenario starts in.
+    pub starting_year: u32,
+    /// Funds available at the start of the scenario.
+    pub starting_funds: MoneyValue,
+    /// Duration of the scenario, in city time units.
+    pub time_limit: u32,
+    /// Goal to reach before the end of the scenario.
+    pub goal: ScenarioGoal,
+}
+
+impl ScenarioDefinition {
+    /// Get the definition of the given scenario, if any.
+    pub fn from_scenario(scenario: &GameScenario) -> Option<Self> {
+        use GameScenario::*;
+        let (name, file_name, starting_year, starting_funds, time_limit_years, goal) =
+            match scenario {
+                None => return Option::None,
+                Dullsville => (
+                    "Dullsville",
+                    "snro.111",
+                    1900,
+                    5000,
+                    30,
+                    ScenarioGoal::MinimumCityClass(CityClass::Metropolis),
+                ),
+                SanFrancisco => (
+                    "San Francisco",
+                    "snro.222",
+                    1906,
+                    20000,
+                    5,
+                    ScenarioGoal::MinimumCityClass(CityClass::Metropolis),
+                ),
+                Hamburg => (
+                    "Hamburg",
+                    "snro.333",
+                    1944,
+                    20000,
+                    5,
+                    ScenarioGoal::MinimumCityClass(CityClass::Metropolis),
+                ),
+                Bern => (
+                    "Bern",
+                    "snro.444",
+                    1965,
+                    20000,
+                    10,
+                    ScenarioGoal::MaximumTrafficAverage(80),
+                ),
+                Tokyo => (
+                    "Tokyo",
+                    "snro.555",
+                    1957,
+                    20000,
+                    5,
+                    ScenarioGoal::MinimumScore(500),
+                ),
+                Detroit => (
+                    "Detroit",
+                    "snro.666",
+                    1972,
+                    20000,
+                    10,
+                    ScenarioGoal::MaximumCrimeAverage(60),
+                ),
+                Boston => (
+                    "Boston",
+                    "snro.777",
+                    2010,
+                    20000,
+                    5,
+                    ScenarioGoal::MinimumScore(500),
+                ),
+                Rio => (
+                    "Rio de Janeiro",
+                    "snro.888",
+                    2047,
+                    20000,
+                    10,
+                    ScenarioGoal::MinimumScore(500),
+                ),
+            };
+        Some(ScenarioDefinition {
+            name,
+            file_name,
+            starting_year,
+            starting_funds,
+            time_limit: time_limit_years * CITY_TIME_UNITS_PER_YEAR,
+            goal,
+        })
+    }
+
+    /// City time at the start of the scenario, relative to the year 1900.
+    pub fn get_starting_city_time(&self) -> u32 {
+        (self.starting_year - 1900) * CITY_TIME_UNITS_PER_YEAR + 2
+    }
+}
+
+/// Scenario being played by the city, if any.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CityScenario {
+    /// Scenario being played.
+    scenario: GameScenario,
+    /// Remaining city time units before judging the scenario (`scoreWait` in the C++ code).
+    remaining_time: u32,
+    /// Outcome of the scenario, once over.
+    outcome: Option<ScenarioOutcome>,
+}
+
+impl CityScenario {
+    pub fn new(scenario: &GameScenario) -> Self {
+        CityScenario {
+            scenario: scenario.clone(),
+            remaining_time: ScenarioDefinition::from_scenario(scenario)
+                .map(|definition| definition.time_limit)
+                .unwrap_or(0),
+            outcome: None,
+        }
+    }
+
+    pub fn get_scenario(&self) -> &GameScenario {
+        &self.scenario
+    }
+
+    pub fn get_definition(&self) -> Option<ScenarioDefinition> {
+        ScenarioDefinition::from_scenario(&self.scenario)
+    }
+
+    /// Remaining city time units before the end of the scenario.
+    pub fn get_remaining_time(&self) -> u32 {
+        self.remaining_time
+    }
+
+    pub fn get_outcome(&self) -> &Option<ScenarioOutcome> {
+        &self.outcome
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Advance the scenario by one city time unit.
+    ///
+    /// Returns the outcome of the scenario once, when its time limit runs out.
+    pub fn update(&mut self, indicators: &ScenarioIndicators) -> Option<ScenarioOutcome> {
+        if self.scenario == GameScenario::None || self.remaining_time == 0 {
+            return None;
+        }
+
+        self.remaining_time -= 1;
+        if self.remaining_time > 0 {
+            return None;
+        }
+
+        let outcome = self.judge(indicators);
+        self.outcome = Some(outcome.clone());
+        Some(outcome)
+    }
+
+    /// Judge the outcome of the scenario with the given city indicators.
+    pub fn judge(&self, indicators: &ScenarioIndicators) -> ScenarioOutcome {
+        match self.get_definition() {
+            Some(definition) if definition.goal.is_reached(indicators) => ScenarioOutcome::Won,
+            _ => ScenarioOutcome::Lost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CityScenario, ScenarioDefinition, ScenarioGoal, ScenarioIndicators, ScenarioOutcome,
+    };
+    use crate::{
+        city::{population::CityClass, City},
+        game::{GameLevelDifficulty, GameScenario},
+    };
+
+    fn indicators(
+        city_class: CityClass,
+        score: u16,
+        traffic_average: u16,
+        crime_average: u16,
+    ) -> ScenarioIndicators {
+        ScenarioIndicators {
+            city_class,
+            score,
+            traffic_average,
+            crime_average,
+        }
+    }
+
+    #[test]
+    fn test_scenario_goals() {
+        let metropolis = ScenarioGoal::MinimumCityClass(CityClass::Metropolis);
+        assert!(metropolis.is_reached(&indicators(CityClass::Metropolis, 0, 0, 0)));
+        assert!(metropolis.is_reached(&indicators(CityClass::Megalopolis, 0, 0, 0)));
+        assert!(!metropolis.is_reached(&indicators(CityClass::Capital, 0, 0, 0)));
+
+        let traffic = ScenarioGoal::MaximumTrafficAverage(80);
+        assert!(traffic.is_reached(&indicators(CityClass::Village, 0, 79, 0)));
+        assert!(!traffic.is_reached(&indicators(CityClass::Village, 0, 80, 0)));
+
+        let crime = ScenarioGoal::MaximumCrimeAverage(60);
+        assert!(crime.is_reached(&indicators(CityClass::Village, 0, 0, 59)));
+        assert!(!crime.is_reached(&indicators(CityClass::Village, 0, 0, 60)));
+
+        let score = ScenarioGoal::MinimumScore(500);
+        assert!(score.is_reached(&indicators(CityClass::Village, 501, 0, 0)));
+        assert!(!score.is_reached(&indicators(CityClass::Village, 500, 0, 0)));
+    }
+
+    #[test]
+    fn test_scenario_time_limit() {
+        let mut scenario = CityScenario::new(&GameScenario::Bern);
+        assert_eq!(scenario.get_remaining_time(), 10 * 48);
+
+        let congested = indicators(CityClass::City, 600, 120, 0);
+        for _ in 0..(10 * 48 - 1) {
+            assert_eq!(scenario.update(&congested), None);
+        }
+        assert!(!scenario.is_over());
+        assert_eq!(scenario.update(&congested), Some(ScenarioOutcome::Lost));
+        assert_eq!(scenario.get_outcome(), &Some(ScenarioOutcome::Lost));
+        // judged only once
+        assert_eq!(scenario.update(&congested), None);
+
+        let mut scenario = CityScenario::new(&GameScenario::Tokyo);
+        let fluid = indicators(CityClass::City, 650, 10, 0);
+        let outcomes: Vec<_> = (0..5 * 48)
+            .filter_map(|_| scenario.update(&fluid))
+            .collect();
+        assert_eq!(outcomes, vec![ScenarioOutcome::Won]);
+
+        let mut free_play = CityScenario::new(&GameScenario::None);
+        assert_eq!(free_play.update(&fluid), None);
+        assert_eq!(free_play.judge(&fluid), ScenarioOutcome::Lost);
+    }
+
+    #[test]
+    fn test_scenario_loading() {
+        let data = City::new("Scenario".into(), GameScenario::None)
+            .unwrap()
+            .to_file_data();
+        let city = City::load_scenario(&GameScenario::SanFrancisco, data).unwrap();
+        let definition = ScenarioDefinition::from_scenario(&GameScenario::SanFrancisco).unwrap();
+
+        assert_eq!(city.get_name(), "San Francisco");
+        assert_eq!(city.get_city_time(), 6 * 48 + 2);
+        assert_eq!(city.total_funds(), definition.starting_funds);
+        assert_eq!(city.get_simulation_speed(), 3);
+        assert_eq!(city.get_sim().get_taxes().city_tax, 7);
+        assert_eq!(city.get_difficulty(), &GameLevelDifficulty::Easy);
+        assert_eq!(city.get_scenario().get_remaining_time(), 5 * 48);
+        assert_eq!(
+            city.get_disasters().get_disaster_event(),
+            &GameScenario::SanFrancisco
+        );
+        assert_eq!(city.get_disasters().get_disaster_timer(), 10);
+
+        let data = city.to_file_data();
+        assert!(City::load_scenario(&GameScenario::None, data).is_err());
+    }
+}
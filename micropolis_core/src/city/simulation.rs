@@ -1,24 +1,32 @@
-use std::todo;
+use std::cmp;
+
+use serde::{Deserialize, Serialize};
 
 use parameters::SimulationParameters;
 use statistics::SimulationStatistics;
-use taxes::SimulationTaxes;
+use taxes::{SimulationTaxes, TaxAssessment, EXTERNAL_MARKET_PARAMETERS_TABLE, TAX_TABLE};
 
 use self::{
     census::CitySimulationCensus,
-    parameters::MAX_ROAD_EFFECT,
+    parameters::{MAX_FIRE_EFFECT, MAX_POLICE_EFFECT, MAX_ROAD_EFFECT},
     scan::CitySimulationScanner,
     sprites::{generate_copter, generate_plane, generate_ship, generate_train},
 };
 
 use super::{
-    disasters::CityDisasters,
-    power::CityPower,
-    sprite::{ActiveSpritesList, SpriteType},
+    disasters::{CityDisasters, DisasterEnvironment},
+    evaluate::CityEvaluator,
+    events::CityEvents,
+    fire::CityFires,
+    population::CityPopulation,
+    power::{CityPower, PowerPlantType},
+    scenario::{ScenarioIndicators, ScenarioOutcome},
+    sprite::{ActiveSpritesList, SpriteEnvironment, SpriteType},
     traffic::CityTraffic,
     City,
 };
 use crate::{
+    error::MicropolisError,
     game::GameLevelDifficulty,
     map::{
         tiles::TILE_ANIM_BIT,
@@ -28,7 +36,11 @@ use crate::{
         tiles::{TILE_CONDUCT_BIT, TILE_POWER_BIT, TILE_ZONE_BIT},
         Map, MapClusteringStrategy, MapPosition, TileMap, TileType,
     },
-    utils::random::MicropolisRandom,
+    messages::GameMessage,
+    utils::{
+        hash::{StableHash, StableHasher},
+        random::MicropolisRandom,
+    },
 };
 use crate::{
     game::{GameSpeed, GameSpeedPreset},
@@ -36,7 +48,7 @@ use crate::{
     utils::clamp,
 };
 
-mod census;
+pub mod census;
 pub mod parameters;
 mod scan;
 pub mod sprites;
@@ -66,6 +78,7 @@ const SPEED_CRIME_SCAN: [u16; 3] = [1, 8, 18];
 const SPEED_POPULATION_DENSITY_SCAN: [u16; 3] = [1, 9, 19];
 const SPEED_FIRE_ANALYSIS: [u16; 3] = [1, 10, 20];
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Simulation {
     scanner: CitySimulationScanner,
     parameters: SimulationParameters,
@@ -84,12 +97,6 @@ pub struct Simulation {
     pass_index: usize,
     /// Incremented every time the map changes.
     map_serial: u32,
-    /// City time unit counter, increnented once every 16 runs through
-    /// the simulator (at fast speed). A time unit is 7.6 days. 4 units
-    /// per month, 48 units per year, relative to #startingYear
-    ///
-    /// Four units per month, so one unit is about a week (7.6 days).
-    city_time: u64,
     /// Coordinates of the city center.
     city_center: MapPosition,
     /// Pollution density map.
@@ -150,7 +157,6 @@ impl Simulation {
             passes: 0,
             pass_index: 0,
             map_serial: 1,
-            city_time: 0,
             city_center: (0, 0).into(),
             pollution_density: Map::with_data(
                 vec![vec![0x00; dimensions.get_height() / 2]; dimensions.get_width() / 2],
@@ -194,6 +200,55 @@ impl Simulation {
             ),
         }
     }
+    /// Empty simulation, holding the place of a simulation detached from its city.
+    pub(super) fn detached() -> Self {
+        Self::new(&TileMap::with_data(
+            vec![],
+            MapClusteringStrategy::BlockSize1,
+        ))
+    }
+
+    pub fn get_census(&self) -> &CitySimulationCensus {
+        &self.census
+    }
+    pub fn get_census_mut(&mut self) -> &mut CitySimulationCensus {
+        &mut self.census
+    }
+
+    pub fn get_statistics(&self) -> &SimulationStatistics {
+        &self.statistics
+    }
+
+    /// Center of mass of the city population.
+    pub fn get_city_center(&self) -> &MapPosition {
+        &self.city_center
+    }
+
+    pub fn get_taxes(&self) -> &SimulationTaxes {
+        &self.taxes
+    }
+    pub fn get_taxes_mut(&mut self) -> &mut SimulationTaxes {
+        &mut self.taxes
+    }
+
+    pub fn get_pollution_density_map(&self) -> &Map<u8> {
+        &self.pollution_density
+    }
+    pub fn get_land_value_map(&self) -> &Map<u8> {
+        &self.land_value_map
+    }
+    pub fn get_crime_rate_map(&self) -> &Map<u8> {
+        &self.crime_rate_map
+    }
+    pub fn get_rate_of_growth_map(&self) -> &Map<i16> {
+        &self.rate_of_growth
+    }
+
+    /// Phase of the simulator run by the next simulation step, from 0 to 15.
+    pub fn get_phase_cycle(&self) -> u8 {
+        self.phase_cycle
+    }
+
     pub fn reset_pass_counter(&mut self) {
         self.pass_index = 0;
     }
@@ -202,8 +257,37 @@ impl Simulation {
         self.map_serial += 1;
     }
 
+    /// Feed the overlay maps computed by the simulation to the given hasher.
+    pub(crate) fn hash_overlay_maps(&self, hasher: &mut StableHasher) {
+        self.pollution_density.stable_hash(hasher);
+        self.land_value_map.stable_hash(hasher);
+        self.crime_rate_map.stable_hash(hasher);
+        self.terrain_density.stable_hash(hasher);
+        self.rate_of_growth.stable_hash(hasher);
+        self.fire_station_map.stable_hash(hasher);
+        self.fire_station_effect_map.stable_hash(hasher);
+        self.police_station_map.stable_hash(hasher);
+        self.police_station_effect_map.stable_hash(hasher);
+        self.commercial_rate_map.stable_hash(hasher);
+    }
+
+    /// Feed the simulation cycle counters to the given hasher.
+    pub(crate) fn hash_counters(&self, hasher: &mut StableHasher) {
+        hasher.write_u16(self.speed_cycle);
+        hasher.write_u8(self.phase_cycle);
+        hasher.write_u16(self.simulation_cycle);
+        hasher.write_bool(self.do_initial_evaluation);
+        hasher.write_bool(self.new_power);
+        hasher.write_u32(self.passes);
+        hasher.write_usize(self.pass_index);
+        hasher.write_u32(self.map_serial);
+        self.city_center.stable_hash(hasher);
+    }
+
     /// Advance the city simulation and its visualization by one frame tick.
-    pub fn step(&mut self, rng: &mut MicropolisRandom, city: &mut City) -> Result<(), String> {
+    ///
+    /// The simulation must be detached from the city, see `City::step`.
+    pub(super) fn step(&mut self, city: &mut City) -> Result<(), MicropolisError> {
         let sim_steps_per_update = self.speed.get_sim_steps_per_update();
         if sim_steps_per_update == 0 {
             return Ok(());
@@ -212,22 +296,39 @@ impl Simulation {
         if self.speed_cycle == 1024 {
             self.speed_cycle = 0;
         }
-        match sim_steps_per_update {
-            1 if self.speed_cycle % 5 != 0 => return Ok(()),
-            2 if self.speed_cycle % 3 != 0 => return Ok(()),
-            _ => {}
+        let do_simulate = match sim_steps_per_update {
+            1 => self.speed_cycle.is_multiple_of(5),
+            2 => self.speed_cycle.is_multiple_of(3),
+            _ => true,
+        };
+        if do_simulate {
+            self.simulate(city)?;
         }
 
-        self.simulate(rng, city)
+        self.move_sprites(city)
+    }
+
+    /// Move the sprites, letting them interact with the city.
+    fn move_sprites(&mut self, city: &mut City) -> Result<(), MicropolisError> {
+        let mut environment = SpriteEnvironment {
+            map: &mut city.map,
+            events: &mut city.events,
+            traffic_density_map: city.traffic.get_density_map(),
+            rate_of_growth_map: &mut self.rate_of_growth,
+            treasury: &mut city.treasury,
+            city_time: city.city_time,
+            disasters_enabled: city.disasters.are_disasters_enabled(),
+        };
+        city.sprites.move_sprites(&mut city.rng, &mut environment)
     }
 
-    fn simulate(&mut self, rng: &mut MicropolisRandom, city: &mut City) -> Result<(), String> {
+    fn simulate(&mut self, city: &mut City) -> Result<(), MicropolisError> {
         // The simulator has 16 different phases, which we cycle through
         // according to `phase_cycle`, which is incremented and wrapped at the
         // end of this switch.
 
         let map_size = city.map.bounds();
-        let speed_index = clamp(city.simulation_speed - 1, 0, 2) as usize;
+        let speed_index = clamp(city.simulation_speed.saturating_sub(1), 0, 2) as usize;
 
         // TODO: initSimLoad behavior
         self.phase_cycle &= 15;
@@ -241,17 +342,24 @@ impl Simulation {
 
                 if self.do_initial_evaluation {
                     self.do_initial_evaluation = false;
-                    city.evaluate()?;
+                    self.evaluate_city(
+                        &mut city.rng,
+                        &mut city.evaluator,
+                        &mut city.population,
+                        &city.power,
+                        &city.traffic,
+                    );
                 }
 
-                self.city_time += 1;
+                city.city_time += 1;
                 self.taxes.city_tax_average += self.taxes.city_tax;
 
                 if self.simulation_cycle & 0x01 == 0x00 {
-                    self.compute_valves();
+                    self.compute_valves(&mut city.population, &city.difficulty);
                 }
 
-                self.clear_census();
+                city.fires_count = self.statistics.fires_count as u32;
+                self.clear_census(&mut city.population, &mut city.power);
             }
             // Scan 1/8th of the map for each of these 8 phases
             1..=8 => {
@@ -259,7 +367,7 @@ impl Simulation {
                 self.scan_map_section(
                     city,
                     (phase_cycle - 1) * map_size.get_width() / 8,
-                    phase_cycle * map_size.get_height() / 8,
+                    phase_cycle * map_size.get_width() / 8,
                 )?
             }
             9 => {
@@ -274,8 +382,27 @@ impl Simulation {
                     self.census.take_yearly_census(&city.population);
                 }
                 if city.city_time % TAX_FREQUENCY == 0 {
-                    self.taxes
-                        .collect_taxes(&mut city.cash_flow, &self.statistics);
+                    let assessment = TaxAssessment {
+                        difficulty: &city.difficulty,
+                        population: &city.population,
+                        statistics: &self.statistics,
+                        city_time: city.city_time,
+                    };
+                    city.cash_flow = self.taxes.collect_taxes(
+                        &assessment,
+                        &mut self.parameters,
+                        &mut city.budget,
+                        &mut city.treasury,
+                    );
+                    self.evaluate_city(
+                        &mut city.rng,
+                        &mut city.evaluator,
+                        &mut city.population,
+                        &city.power,
+                        &city.traffic,
+                    );
+                    city.evaluator
+                        .record_report(city.city_time, &city.population);
                 }
             }
             10 => {
@@ -284,11 +411,21 @@ impl Simulation {
                 }
                 self.decrease_traffic_map(&city.map, &mut city.traffic);
 
-                // TODO: sendMessages()
+                self.send_messages(city);
+                if let Some(outcome) = city.scenario.update(&ScenarioIndicators::from_evaluation(
+                    &city.evaluator,
+                    &self.statistics,
+                )) {
+                    let message = match outcome {
+                        ScenarioOutcome::Won => GameMessage::MessageScenarioWon,
+                        ScenarioOutcome::Lost => GameMessage::MessageScenarioLost,
+                    };
+                    city.events.send_message(message, None, true, true);
+                }
             }
             11 => {
                 if (self.simulation_cycle % SPEED_POWER_SCAN[speed_index]) == 0 {
-                    city.power.do_power_scan(&city.map);
+                    city.power.do_power_scan(&city.map, &mut city.events);
                     self.new_power = true;
                 }
             }
@@ -298,7 +435,7 @@ impl Simulation {
                 {
                     let (pollution_average, pollution_max_at, land_value_average) =
                         self.scanner.pollution_terrain_land_value_scan(
-                            rng,
+                            &mut city.rng,
                             &city.map,
                             &self.city_center,
                             &self.statistics.maximum_pollution_at,
@@ -316,7 +453,7 @@ impl Simulation {
                 if (self.simulation_cycle % SPEED_CRIME_SCAN[speed_index]) == 0 {
                     let (crime_average, crime_maximum_at, police_station_effect_map) =
                         self.scanner.crime_scan(
-                            rng,
+                            &mut city.rng,
                             &city.map,
                             &self.land_value_map,
                             &mut self.police_station_map,
@@ -343,16 +480,23 @@ impl Simulation {
                 if (self.simulation_cycle % SPEED_FIRE_ANALYSIS[speed_index]) == 0 {
                     self.fire_station_effect_map =
                         self.scanner.fire_analysis(&mut self.fire_station_map);
+                    city.fires.prune(&city.map);
                 }
 
+                let mut environment = DisasterEnvironment {
+                    map: &mut city.map,
+                    sprites: &mut city.sprites,
+                    events: &mut city.events,
+                    maximum_pollution_at: self.statistics.maximum_pollution_at,
+                    city_center: self.city_center,
+                };
                 city.disasters.do_disasters(
-                    rng,
-                    &mut city.map,
-                    &mut city.sprites,
+                    &mut city.rng,
+                    &mut environment,
                     &city.difficulty,
-                    &city.scenario,
+                    city.scenario.get_scenario(),
                     self.statistics.average_pollution,
-                    &self.statistics.maximum_pollution_at,
+                    city.city_time,
                 )?;
             }
             _ => unreachable!(),
@@ -363,12 +507,205 @@ impl Simulation {
     }
 
     /// Compute the RCI valves, standing for Residential, Commercial and Industrial zone demands.
-    fn compute_valves(&mut self) {
-        todo!()
+    ///
+    /// See `Micropolis::setValves` in the C++ code.
+    fn compute_valves(&self, population: &mut CityPopulation, difficulty: &GameLevelDifficulty) {
+        let parameters = &self.parameters;
+        let level = difficulty.to_usize().unwrap();
+
+        let residential = population.get_residential() as f64;
+        let commercial = population.get_commercial() as f64;
+        let industrial = population.get_industrial() as f64;
+        let normalized_residential =
+            residential / parameters.get_residential_population_denominator() as f64;
+
+        // last month figures
+        let residential_history = self.census.get_residential_history()[1] as f64;
+        let workers_history = (self.census.get_commercial_history()[1] as f64)
+            + (self.census.get_industrial_history()[1] as f64);
+
+        let employment = if residential > 0.0 {
+            workers_history / normalized_residential
+        } else {
+            1.0
+        };
+        let migration = normalized_residential * (employment - 1.0);
+        let births = normalized_residential * parameters.get_birth_rate();
+        let projected_residential = normalized_residential + migration + births;
+
+        let labor_base = if workers_history > 0.0 {
+            residential_history / workers_history
+        } else {
+            1.0
+        }
+        .clamp(0.0, parameters.get_max_labor_base());
+
+        let internal_market = (normalized_residential + commercial + industrial)
+            / parameters.get_internal_market_denominator();
+        let projected_commercial = internal_market * labor_base;
+        let projected_industrial =
+            (industrial * labor_base * EXTERNAL_MARKET_PARAMETERS_TABLE[level])
+                .max(parameters.get_min_projected_industrial_population());
+
+        let residential_ratio = if normalized_residential > 0.0 {
+            projected_residential / normalized_residential
+        } else {
+            parameters.get_default_residential_ratio()
+        }
+        .min(parameters.get_max_residential_ratio());
+        let commercial_ratio = if commercial > 0.0 {
+            projected_commercial / commercial
+        } else {
+            projected_commercial
+        }
+        .min(parameters.get_max_commercial_ratio());
+        let industrial_ratio = if industrial > 0.0 {
+            projected_industrial / industrial
+        } else {
+            projected_industrial
+        }
+        .min(parameters.get_max_industrial_ratio());
+
+        let tax_index = cmp::min(
+            self.taxes.city_tax as usize + level,
+            parameters.get_max_tax_rate() as usize,
+        );
+        let tax_effect = TAX_TABLE[tax_index] as f64;
+        let tax_table_scale = parameters.get_tax_table_scale();
+
+        // the ratios are velocity changes to the valves
+        let velocity = |ratio: f64| ((ratio - 1.0) * tax_table_scale + tax_effect) as i16;
+
+        // see `Micropolis::sendMessages` in the C++ code
+        population.set_caps(
+            population.get_residential() > 500 && self.statistics.stadium_count == 0,
+            population.get_commercial() > 100 && self.statistics.airport_count == 0,
+            population.get_industrial() > 70 && self.statistics.seaport_count == 0,
+        );
+        population.change_valves(
+            velocity(residential_ratio),
+            velocity(commercial_ratio),
+            velocity(industrial_ratio),
+        );
     }
 
-    fn clear_census(&mut self) {
-        todo!()
+    /// Evaluate the city from the current statistics.
+    ///
+    /// See `Micropolis::cityEvaluation` in the C++ code.
+    pub(super) fn evaluate_city(
+        &self,
+        rng: &mut MicropolisRandom,
+        evaluator: &mut CityEvaluator,
+        population: &mut CityPopulation,
+        power: &CityPower,
+        traffic: &CityTraffic,
+    ) {
+        evaluator.perform_evaluation(
+            rng,
+            &self.land_value_map,
+            &self.parameters,
+            population,
+            &self.statistics,
+            &self.taxes,
+            power,
+            traffic,
+        );
+    }
+
+    /// Reset the census counters before scanning the map.
+    fn clear_census(&mut self, population: &mut CityPopulation, power: &mut CityPower) {
+        population.clear_census();
+        power.clear_census();
+        self.statistics.clear_census();
+        self.fire_station_map.clear(0);
+        self.police_station_map.clear(0);
+    }
+
+    /// Warn the player about the problems of the city, one check per city time unit.
+    ///
+    /// See `Micropolis::sendMessages` in the C++ code.
+    fn send_messages(&self, city: &mut City) {
+        // TODO: checkGrowth()
+        let population = &city.population;
+        let total_zones = population.get_residential_zones() as u32
+            + population.get_commercial_zones() as u32
+            + population.get_industrial_zones() as u32;
+        let total_population = population.total_population();
+        let power_plants = city.power.coal_generators_count + city.power.nuclear_generators_count;
+        let statistics = &self.statistics;
+
+        let message = match city.city_time & 63 {
+            1 if total_zones / 4 >= population.get_residential_zones() as u32 => {
+                Some((GameMessage::MessageNeedMoreResidential, false))
+            }
+            5 if total_zones / 8 >= population.get_commercial_zones() as u32 => {
+                Some((GameMessage::MessageNeedMoreCommercial, false))
+            }
+            10 if total_zones / 8 >= population.get_industrial_zones() as u32 => {
+                Some((GameMessage::MessageNeedMoreIndustrial, false))
+            }
+            14 if total_zones > 10 && total_zones * 2 > statistics.road_total as u32 => {
+                Some((GameMessage::MessageNeedMoreRoads, false))
+            }
+            18 if total_zones > 50 && total_zones > statistics.rail_total as u32 => {
+                Some((GameMessage::MessageNeedMoreRails, false))
+            }
+            22 if total_zones > 10 && power_plants == 0 => {
+                Some((GameMessage::MessageNeedElectricity, false))
+            }
+            26 if population.is_residential_capped() => {
+                Some((GameMessage::MessageNeedStadium, false))
+            }
+            28 if population.is_industrial_capped() => {
+                Some((GameMessage::MessageNeedSeaport, false))
+            }
+            30 if population.is_commercial_capped() => {
+                Some((GameMessage::MessageNeedAirport, false))
+            }
+            32 => {
+                let powered = city.power.get_powered_zone_count() as f64;
+                let zones = powered + city.power.get_unpowered_zone_count() as f64;
+                if zones > 0.0 && powered / zones < 0.7 {
+                    Some((GameMessage::MessageBlackoutsReported, false))
+                } else {
+                    None
+                }
+            }
+            35 if statistics.average_pollution > 60 => {
+                Some((GameMessage::MessageHighPollution, true))
+            }
+            42 if statistics.average_crime > 100 => Some((GameMessage::MessageHighCrime, true)),
+            45 if total_population > 60 && statistics.fire_station_count == 0 => {
+                Some((GameMessage::MessageNeedFireStation, false))
+            }
+            48 if total_population > 60 && statistics.police_station_count == 0 => {
+                Some((GameMessage::MessageNeedPoliceStation, false))
+            }
+            51 if self.taxes.city_tax > 12 => Some((GameMessage::MessageTaxTooHigh, false)),
+            54 if self.parameters.get_road_effect() < 5 * MAX_ROAD_EFFECT / 8
+                && statistics.road_total > 30 =>
+            {
+                Some((GameMessage::MessageRoadNeedsFunding, false))
+            }
+            57 if self.parameters.get_fire_effect() < 7 * MAX_FIRE_EFFECT / 10
+                && total_population > 20 =>
+            {
+                Some((GameMessage::MessageFireStationNeedsFunding, false))
+            }
+            60 if self.parameters.get_police_effect() < 7 * MAX_POLICE_EFFECT / 10
+                && total_population > 20 =>
+            {
+                Some((GameMessage::MessagePoliceNeedsFunding, false))
+            }
+            63 if city.evaluator.get_traffic_average() > 60 => {
+                Some((GameMessage::MessageTrafficJams, true))
+            }
+            _ => None,
+        };
+
+        if let Some((message, important)) = message {
+            city.events.send_message(message, None, false, important);
+        }
     }
 
     /// Decrease rate of growth.
@@ -422,58 +759,70 @@ impl Simulation {
         }
     }
 
-    fn scan_map_section(&mut self, city: &mut City, x1: usize, x2: usize) -> Result<(), String> {
-        let rng = &mut city.rng;
-        let sprites = &mut city.sprites;
-
-        let map = &mut city.map;
-        let map_height = map.bounds().get_height();
-
-        let flood_type_raw = TileType::Flood
-            .to_u16()
-            .ok_or("Flood tile type raw conversion error")?;
+    fn scan_map_section(
+        &mut self,
+        city: &mut City,
+        x1: usize,
+        x2: usize,
+    ) -> Result<(), MicropolisError> {
+        let map_height = city.map.bounds().get_height();
 
         for x in x1..x2 {
             for y in 0..map_height {
                 let position: MapPosition = (x, y).into();
-                let tile = map.get_tile_at(&position).expect(
-                    &format!(
+                let tile = city.map.get_tile_at(&position).unwrap_or_else(|| {
+                    panic!(
                         "Simulation.scan_map_section cannot get tile at {}",
                         position
-                    )[..],
-                );
+                    )
+                });
 
-                let tile_type = tile.get_type().as_ref().ok_or(format!(
-                    "Simulation.scan_map_section invalid tile {:?}",
-                    tile
-                ))?;
-                if *tile_type == TileType::Dirt {
+                let tile_raw = tile.get_raw();
+                // animated tiles like fires or rubble have no tile type of their own
+                let tile_value = tile_raw & TILE_LOW_MASK;
+                if tile_value == TileType::Dirt.to_u16().unwrap() {
                     continue;
                 }
 
-                if *tile_type < TileType::Flood {
+                if tile_value < TileType::Flood.to_u16().unwrap() {
                     continue;
                 }
 
-                if *tile_type < TileType::HorizontalBridge {
-                    if *tile_type >= TileType::Fire {
-                        self.statistics.fire_station_count += 1;
-                        if rng.get_random_16() & 0x03 == 0x00 {
+                if tile_value < TileType::HorizontalBridge.to_u16().unwrap() {
+                    if tile_value >= TileType::Fire.to_u16().unwrap() {
+                        self.statistics.fires_count += 1;
+                        if city.rng.get_random_16() & 0x03 == 0x00 {
                             // 1 in 4 times
-                            self.do_fire(rng, map, sprites, &position)?;
+                            self.do_fire(
+                                &mut city.rng,
+                                &mut city.map,
+                                &mut city.sprites,
+                                &mut city.fires,
+                                &position,
+                            )?;
                         }
                         continue;
                     }
 
-                    if *tile_type < TileType::Radioactive {
-                        city.disasters.do_flood(rng, map, &position)?;
+                    if tile_value < TileType::Radioactive.to_u16().unwrap() {
+                        city.disasters.do_flood(
+                            &mut city.rng,
+                            &mut city.map,
+                            &mut self.rate_of_growth,
+                            &position,
+                        )?;
                     } else {
-                        self.do_radioactive_tile(rng, map, &position)?;
+                        self.do_radioactive_tile(&mut city.rng, &mut city.map, &position)?;
                     }
                     continue;
                 }
 
-                // TODO:
+                if tile_raw & TILE_ZONE_BIT != 0x00 {
+                    self.do_zone(city, &position)?;
+                    continue;
+                }
+
+                // TODO: roads and rails
             }
         }
 
@@ -490,7 +839,7 @@ impl Simulation {
         at: &MapPosition,
         zone_center: TileType,
         zone_size: u16,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         let mut tile_raw = zone_center.to_u16().unwrap() - 2 - zone_size;
 
         // y and x loops one position shifted to compensate for the center-tile position.
@@ -529,27 +878,25 @@ impl Simulation {
         map: &mut TileMap,
         power: &mut CityPower,
         sprites: &mut ActiveSpritesList,
+        events: &mut CityEvents,
         at: &MapPosition,
+        city_time: u32,
         is_zone_powered: bool,
         disasters_enabled: bool,
         difficulty: &GameLevelDifficulty,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         let tile_type = map
             .get_tile_at(at)
             .map(|t| t.get_raw() & TILE_LOW_MASK)
-            .map(|r| {
-                TileType::from_u16(r)
-                    .ok_or("Simulation::do_special_zone invalid tile type".to_string())
-            })
-            .ok_or("Simulation::do_special_zone cannot read tile".to_string())??;
+            .map(|r| TileType::from_u16(r).ok_or(MicropolisError::InvalidTileValue(r)))
+            .ok_or(MicropolisError::OutOfBounds(*at))??;
         Ok(match tile_type {
             TileType::PowerPlant => {
                 // coal power generation
-                power.coal_generators_count += 1;
-                if self.city_time & 0x07 == 0x00 {
+                power.add_power_plant(*at, PowerPlantType::Coal);
+                if city_time & 0x07 == 0x00 {
                     self.repair_zone(map, at, TileType::PowerPlant, 4)?;
                 }
-                power.push_power_stack(*at);
                 Self::coal_smoke(map, at)?;
             }
             TileType::Nuclear => {
@@ -557,20 +904,19 @@ impl Simulation {
                 if disasters_enabled
                     && (rng.get_random(ZONE_MELTDOWN_TABLE[difficulty.to_usize().unwrap()]) == 0x00)
                 {
-                    CityDisasters::do_meltdown(rng, map, at)?;
+                    CityDisasters::do_meltdown(rng, map, sprites, events, at)?;
                     return Ok(());
                 }
 
                 // otherwise, nuclear power generation
-                power.nuclear_generators_count += 1;
-                if self.city_time & 0x07 == 0x00 {
+                power.add_power_plant(*at, PowerPlantType::Nuclear);
+                if city_time & 0x07 == 0x00 {
                     self.repair_zone(map, at, TileType::Nuclear, 4)?;
                 }
-                power.push_power_stack(*at);
             }
             TileType::FireStation => {
                 self.statistics.fire_station_count += 1;
-                if self.city_time & 0x07 == 0x00 {
+                if city_time & 0x07 == 0x00 {
                     self.repair_zone(map, at, TileType::FireStation, 3)?;
                 }
 
@@ -586,19 +932,17 @@ impl Simulation {
                     z /= 2;
                 }
 
-                let fire_control =
-                    self.fire_station_map
-                        .get_tile_at(&road_position)
-                        .ok_or(format!(
-                        "Simulation.do_special_zone cannot get fire_station_map tile value at {}",
-                        road_position
-                    ))? + z;
+                let fire_control = self
+                    .fire_station_map
+                    .get_tile_at(&road_position)
+                    .ok_or(MicropolisError::OutOfBounds(road_position))?
+                    + z;
                 self.fire_station_map
                     .set_tile_at(&road_position, fire_control);
             }
             TileType::PoliceStation => {
                 self.statistics.police_station_count += 1;
-                if self.city_time & 0x07 == 0x00 {
+                if city_time & 0x07 == 0x00 {
                     self.repair_zone(map, at, TileType::PoliceStation, 3)?;
                 }
 
@@ -614,13 +958,11 @@ impl Simulation {
                     z /= 2;
                 }
 
-                let police_efficiency =
-                    self.police_station_map
-                        .get_tile_at(&road_position)
-                        .ok_or(format!(
-                        "Simulation.do_special_zone cannot get police_station_map tile value at {}",
-                        road_position
-                    ))? + z;
+                let police_efficiency = self
+                    .police_station_map
+                    .get_tile_at(&road_position)
+                    .ok_or(MicropolisError::OutOfBounds(road_position))?
+                    + z;
                 self.police_station_map
                     .set_tile_at(&road_position, police_efficiency);
             }
@@ -628,13 +970,13 @@ impl Simulation {
             TileType::Stadium => {
                 self.statistics.stadium_count += 1;
 
-                if self.city_time & 0x0F == 0x00 {
+                if city_time & 0x0F == 0x00 {
                     self.repair_zone(map, at, TileType::Stadium, 4)?;
                 }
 
                 if is_zone_powered {
                     // start a match every now and then
-                    if (self.city_time as i32 + at.get_x() + at.get_y()) & 0x1F == 0x00 {
+                    if (city_time as i32 + at.get_x() + at.get_y()) & 0x1F == 0x00 {
                         Self::draw_stadium(map, at, TileType::FullStadium)?;
                         map.set_tile_at(
                             &(*at + (1, 0).into()),
@@ -655,7 +997,7 @@ impl Simulation {
             TileType::FullStadium => {
                 self.statistics.stadium_count += 1;
 
-                if (self.city_time as i32 + at.get_x() + at.get_y()) & 0x07 == 0x00 {
+                if (city_time as i32 + at.get_x() + at.get_y()) & 0x07 == 0x00 {
                     // stop the match
                     Self::draw_stadium(map, at, TileType::Stadium)?;
                 }
@@ -663,12 +1005,14 @@ impl Simulation {
             TileType::Airport => {
                 self.statistics.airport_count += 1;
 
-                if self.city_time & 0x07 == 0x00 {
+                if city_time & 0x07 == 0x00 {
                     self.repair_zone(map, at, TileType::Airport, 6)?;
 
                     // display a rotating radar if powered
                     let radar_position = *at + (1, -1).into();
-                    let radar_tile = map.get_tile_mut_at(&radar_position).ok_or(format!(""))?;
+                    let radar_tile = map
+                        .get_tile_mut_at(&radar_position)
+                        .ok_or(MicropolisError::OutOfBounds(radar_position))?;
                     if is_zone_powered {
                         if radar_tile.get_raw() & TILE_LOW_MASK == TileType::Radar.to_u16().unwrap()
                         {
@@ -694,7 +1038,7 @@ impl Simulation {
             TileType::Port => {
                 self.statistics.seaport_count += 1;
 
-                if self.city_time & 0x15 == 0x00 {
+                if city_time & 0x15 == 0x00 {
                     self.repair_zone(map, at, TileType::Port, 4)?;
                 }
 
@@ -708,7 +1052,7 @@ impl Simulation {
     }
 
     /// Draw coal smoke tiles around the given coal power plant position.
-    fn coal_smoke(map: &mut TileMap, at: &MapPosition) -> Result<(), String> {
+    fn coal_smoke(map: &mut TileMap, at: &MapPosition) -> Result<(), MicropolisError> {
         for i in 0..4 {
             map.set_tile_at(
                 &(*at + (SMOKE_DX[i], SMOKE_DY[i]).into()),
@@ -729,14 +1073,13 @@ impl Simulation {
         map: &mut TileMap,
         center: &MapPosition,
         base_value: TileType,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         debug_assert!(base_value.to_u16().unwrap() >= 5);
 
         // Center
-        let tile = map.get_tile_mut_at(center).ok_or(format!(
-            "Simulation::draw_stadium cannot access center tile at position {}",
-            center,
-        ))?;
+        let tile = map
+            .get_tile_mut_at(center)
+            .ok_or(MicropolisError::OutOfBounds(*center))?;
         tile.set_raw(tile.get_raw() | TILE_ZONE_BIT | TILE_POWER_BIT);
 
         // Other tiles
@@ -745,10 +1088,9 @@ impl Simulation {
         for y in center_y - 1..center_y + 3 {
             for x in center_x - 1..center_x + 3 {
                 let at = (x, y).into();
-                let tile = map.get_tile_mut_at(&at).ok_or(format!(
-                    "Simulation::draw_stadium cannot access tile at position {}",
-                    at,
-                ))?;
+                let tile = map
+                    .get_tile_mut_at(&at)
+                    .ok_or(MicropolisError::OutOfBounds(at))?;
                 tile.set_raw(value | TILE_BURN_BIT | TILE_CONDUCT_BIT);
                 value += 1;
             }
@@ -767,7 +1109,7 @@ impl Simulation {
         sprites: &mut ActiveSpritesList,
         position: &MapPosition,
         total_population: u32,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         self.statistics.rail_total += 1;
 
         generate_train(rng, sprites, position, total_population)?;
@@ -778,10 +1120,9 @@ impl Simulation {
 
         // rail deteriorates if not enough budget
         if rng.get_random_16() & 0x01FF != 0x00 {
-            let tile = map.get_tile_mut_at(position).ok_or(format!(
-                "Simulation.do_rail: cannot get tile at {}",
-                position
-            ))?;
+            let tile = map
+                .get_tile_mut_at(position)
+                .ok_or(MicropolisError::OutOfBounds(*position))?;
 
             if tile.get_raw() & TILE_CONDUCT_BIT != 0x00 {
                 debug_assert!(MAX_ROAD_EFFECT == 32); // otherwise the random16() & 31 makes no sense
@@ -807,7 +1148,7 @@ impl Simulation {
         rng: &mut MicropolisRandom,
         map: &mut TileMap,
         position: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         if rng.get_random_16() & 0x0FFF == 0x00 {
             map.set_tile_at(position, Tile::from_type(TileType::Dirt)?);
         }
@@ -816,68 +1157,60 @@ impl Simulation {
 
     /// Handle a tile on fire at the given map position.
     ///
-    /// TODO: needs a notion of iterative neighbour tiles computing
-    /// TODO: use a getFromMap()-like function here
-    /// TODO: extract constants of fire station effectiveness from here
+    /// The fire may spread to the neighboring tiles, depending on their
+    /// burnability and on the wind, and may burn out, depending on the fire
+    /// station coverage.
     fn do_fire(
         &mut self,
         rng: &mut MicropolisRandom,
         map: &mut TileMap,
         sprites: &mut ActiveSpritesList,
+        fires: &mut CityFires,
         position: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
+        fires.track_fire_at(position);
+
         // try to set neighbouring tiles on fire as well
         for z in 0..4 {
-            if rng.get_random_16() & 0x07 == 0x00 {
-                let position_temp = *position + (FIRE_DX[z], FIRE_DY[z]).into();
-
-                if let Some(tile) = map.get_tile_at(&position_temp).cloned() {
-                    let tile_raw = tile.get_raw();
-                    if tile_raw & TILE_BURN_BIT == 0x00 {
-                        continue; // not burnable
-                    }
-
-                    if tile_raw & TILE_ZONE_BIT != 0x00 {
-                        // neighbour tile is a burnable zone
-                        self.fire_zone(map, position, &tile)?;
+            let roll = CityFires::spread_roll(rng.get_random_16());
+            let position_temp = *position + (FIRE_DX[z], FIRE_DY[z]).into();
+            let tile = match map.get_tile_at(&position_temp) {
+                Some(tile) => tile.clone(),
+                None => continue,
+            };
+            let tile_raw = tile.get_raw();
+            if roll >= fires.get_spread_chance(tile_raw, (FIRE_DX[z], FIRE_DY[z])) {
+                continue; // not burnable, or lucky
+            }
 
-                        // explode?
-                        if tile_raw & TILE_LOW_MASK > TileType::IndustrialZoneBase.to_u16().unwrap()
-                        {
-                            let explosion_position: MapPosition =
-                                position_temp * 16 + (8, 8).into();
-                            CityDisasters::make_explosion_at(rng, sprites, &explosion_position)?;
-                        }
-                    }
+            if tile_raw & TILE_ZONE_BIT != 0x00 {
+                // neighbour tile is a burnable zone
+                Self::fire_zone(&mut self.rate_of_growth, map, &position_temp, &tile)?;
 
-                    map.set_tile_at(
-                        &position_temp,
-                        Tile::from_raw(Self::random_fire_tile_value(rng))?,
-                    );
+                // explode?
+                if tile_raw & TILE_LOW_MASK > TileType::IndustrialZoneBase.to_u16().unwrap() {
+                    let explosion_position: MapPosition = position_temp * 16 + (8, 8).into();
+                    CityDisasters::make_explosion_at(rng, sprites, &explosion_position)?;
                 }
             }
-        }
 
-        // compute likelihood of fire running out of fuel
-        let z = self.fire_station_map.get_tile_at(position).ok_or(format!(
-            "Simulation.do_fire: cannot get fire control efficience at {}",
-            position
-        ))?;
-        let rate = match z {
-            0..=19 => 3,
-            20..=99 => 2,
-            _ if *z > 100 => 1,
-            _ => 10,
-        };
+            map.set_tile_at(
+                &position_temp,
+                Tile::from_raw(Self::random_fire_tile_value(rng))?,
+            );
+            fires.spread(position, &position_temp);
+        }
 
         // should we put out the fire?
-        if rng.get_random(rate) == 0x00 {
+        let coverage = self
+            .fire_station_effect_map
+            .get_tile_at(position)
+            .ok_or(MicropolisError::OutOfBounds(*position))?;
+        if rng.get_random(fires.get_burn_out_rate(*coverage)) == 0x00 {
             map.get_tile_mut_at(position)
-                .ok_or(format!(
-                    "Simulation.do_fire: cannot get tile at {}",
-                    position
-                ))?
+                .ok_or(MicropolisError::OutOfBounds(*position))?
                 .set_raw(Self::random_rubble_tile_value(rng));
+            fires.burn_out(position);
         }
 
         Ok(())
@@ -886,19 +1219,15 @@ impl Simulation {
     /// Handle a zone on fire.
     ///
     /// Decreases rate of growth of the zone, and makes remaining tiles bulldozable.
-    fn fire_zone(
-        &mut self,
+    pub(crate) fn fire_zone(
+        rate_of_growth: &mut Map<i16>,
         map: &mut TileMap,
         position: &MapPosition,
         zone_tile: &Tile,
-    ) -> Result<Tile, String> {
-        let value = self
-            .rate_of_growth
-            .get_tile_mut_at(&position)
-            .ok_or(format!(
-                "Simulation.fire_zone: cannot get rate of growth value at {}",
-                position
-            ))?;
+    ) -> Result<Tile, MicropolisError> {
+        let value = rate_of_growth
+            .get_tile_mut_at(position)
+            .ok_or(MicropolisError::OutOfBounds(*position))?;
         *value = clamp(*value - 20, -200, 200);
 
         let tile_raw = (zone_tile.get_raw() & TILE_LOW_MASK) as i16;
@@ -937,7 +1266,7 @@ impl Simulation {
         rng: &mut MicropolisRandom,
         sprites: &mut ActiveSpritesList,
         position: &MapPosition,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         if rng.get_random(5) == 0 {
             return generate_plane(rng, sprites, position);
         }
@@ -958,3 +1287,157 @@ impl Simulation {
         (TileType::Rubble.to_u16().unwrap() + (rng.get_random_16() as u16 & 0x03)) | TILE_BULL_BIT
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Simulation;
+    use crate::{
+        city::{
+            events::{GameEvent, MessageEvent},
+            fire::{Wind, MAX_WIND_STRENGTH},
+            population::{CityDemand, CityPopulation},
+            City,
+        },
+        game::{GameLevelDifficulty, GameScenario},
+        map::{
+            tiles::{TILE_BULL_BIT, TILE_BURN_BIT, TILE_LOW_MASK},
+            Map, MapPosition, MapPositionOffset, MapRectangle, Tile, TileType,
+        },
+        messages::GameMessage,
+    };
+
+    #[test]
+    fn test_fire_spread_with_wind() {
+        let mut city = City::new("Fireville".into(), GameScenario::None).unwrap();
+        city.rng.seed(1234);
+        let woods = TileType::Woods.to_u16().unwrap() | TILE_BURN_BIT | TILE_BULL_BIT;
+        for x in 40..80 {
+            for y in 30..70 {
+                city.map
+                    .set_tile_at(&(x, y).into(), Tile::from_raw(woods).unwrap());
+            }
+        }
+        let start: MapPosition = (60, 50).into();
+        city.map
+            .set_tile_at(&start, Tile::from_type(TileType::Fire).unwrap());
+        city.fires.set_wind(Wind {
+            direction: MapPositionOffset::East,
+            strength: MAX_WIND_STRENGTH,
+        });
+
+        let mut sim = city.sim.clone();
+        for _ in 0..50 {
+            let burning: Vec<MapPosition> = (40..80)
+                .flat_map(|x| (30..70).map(move |y| MapPosition::new(x, y)))
+                .filter(|position| {
+                    city.map.get_tile_at(position).unwrap().get_raw() & TILE_LOW_MASK
+                        >= TileType::Fire.to_u16().unwrap()
+                })
+                .collect();
+            for position in burning.iter() {
+                sim.do_fire(
+                    &mut city.rng,
+                    &mut city.map,
+                    &mut city.sprites,
+                    &mut city.fires,
+                    position,
+                )
+                .unwrap();
+            }
+        }
+
+        // nothing burns upwind
+        let lost = |x_range: std::ops::Range<i32>| {
+            x_range
+                .flat_map(|x| (30..70).map(move |y| MapPosition::new(x, y)))
+                .filter(|position| city.map.get_tile_at(position).unwrap().get_raw() != woods)
+                .count()
+        };
+        assert_eq!(lost(40..60), 0);
+        assert!(lost(61..80) > 0);
+        let statistics = city.fires.get_statistics();
+        assert_eq!(statistics.fires_started, 1);
+        assert_eq!(statistics.tiles_lost as usize, lost(60..80));
+
+        // the map scan counts the burning tiles
+        sim.scan_map_section(&mut city, 0, 120).unwrap();
+        let burning = (0..120)
+            .flat_map(|x| (0..100).map(move |y| MapPosition::new(x, y)))
+            .filter(|position| {
+                let tile_value = city.map.get_tile_at(position).unwrap().get_raw() & TILE_LOW_MASK;
+                tile_value >= TileType::Fire.to_u16().unwrap()
+                    && tile_value <= TileType::LastFire.to_u16().unwrap()
+            })
+            .count();
+        assert!(sim.statistics.fires_count as usize >= burning);
+        assert_eq!(sim.statistics.fire_station_count, 0);
+    }
+
+    #[test]
+    fn test_compute_valves() {
+        let map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let mut sim = Simulation::new(&map);
+        sim.get_taxes_mut().city_tax = 7;
+
+        let mut population = CityPopulation::from_map(&map);
+        sim.compute_valves(&mut population, &GameLevelDifficulty::Easy);
+        assert_eq!(population.get_residential_valve(), 180);
+        assert_eq!(population.get_commercial_valve(), -600);
+        assert_eq!(population.get_industrial_valve(), 600);
+
+        // higher difficulty levels increase the effective tax rate
+        let mut population = CityPopulation::from_map(&map);
+        sim.compute_valves(&mut population, &GameLevelDifficulty::Normal);
+        assert_eq!(population.get_residential_valve(), 170);
+        assert_eq!(population.get_commercial_valve(), -610);
+        assert_eq!(population.get_industrial_valve(), 590);
+
+        // valves are clamped to their range
+        for _ in 0..20 {
+            sim.compute_valves(&mut population, &GameLevelDifficulty::Easy);
+        }
+        assert_eq!(
+            population.get_demand(),
+            CityDemand {
+                residential: 1.0,
+                commercial: -1.0,
+                industrial: 1.0,
+            }
+        );
+
+        // capped zones cannot have a positive demand
+        population.set_caps(true, false, true);
+        population.change_valves(10, 10, -10);
+        assert_eq!(population.get_residential_valve(), 0);
+        assert_eq!(population.get_commercial_valve(), -1490);
+        assert_eq!(population.get_industrial_valve(), 0);
+    }
+
+    #[test]
+    fn test_send_messages() {
+        let mut city = City::new("Messageville".into(), GameScenario::None).unwrap();
+        let sim = city.sim.clone();
+
+        city.sim.get_taxes_mut().city_tax = 15;
+        let sim_with_high_taxes = city.sim.clone();
+        city.city_time = 50;
+        sim_with_high_taxes.send_messages(&mut city);
+        assert!(city.get_events().is_empty());
+
+        city.city_time = 51;
+        sim.send_messages(&mut city);
+        assert!(city.get_events().is_empty());
+        sim_with_high_taxes.send_messages(&mut city);
+        assert_eq!(
+            city.drain_events(),
+            vec![GameEvent::Message(MessageEvent {
+                message: GameMessage::MessageTaxTooHigh,
+                position: None,
+                picture: false,
+                important: false,
+            })]
+        );
+        assert!(city.get_events().is_empty());
+    }
+}
@@ -1,19 +1,29 @@
 use std::cmp::{max, min};
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    city::{budget::MoneyValue, population::CityPopulation},
+    city::{
+        budget::MoneyValue,
+        file::{CityFileData, CITY_FILE_HISTORY_LENGTH, CITY_FILE_MISC_HISTORY_LENGTH},
+        population::CityPopulation,
+    },
     utils::clamp,
 };
 
 use super::statistics::SimulationStatistics;
 
 /// Number of history entries.
-const HISTORY_LENGTH: usize = 480;
+///
+/// The first 120 entries are the 10 years history (one entry per month),
+/// the last 120 ones the 120 years history (one entry per year).
+pub const HISTORY_LENGTH: usize = CITY_FILE_HISTORY_LENGTH;
 /// Number of miscellaneous history entries.
-const MISC_HISTORY_LENGTH: usize = 240;
+pub const MISC_HISTORY_LENGTH: usize = CITY_FILE_MISC_HISTORY_LENGTH;
 
 const RESIDENTIAL_POPULATION_DENOMINATOR: u16 = 8;
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CitySimulationCensus {
     /// Residential population history.
     residential_history: Vec<u16>,
@@ -55,6 +65,12 @@ pub struct CitySimulationCensus {
     need_church: i8,
 }
 
+impl Default for CitySimulationCensus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CitySimulationCensus {
     pub fn new() -> Self {
         Self {
@@ -77,6 +93,70 @@ impl CitySimulationCensus {
         }
     }
 
+    pub fn get_residential_history(&self) -> &Vec<u16> {
+        &self.residential_history
+    }
+    pub fn get_commercial_history(&self) -> &Vec<u16> {
+        &self.commercial_history
+    }
+    pub fn get_industrial_history(&self) -> &Vec<u16> {
+        &self.industrial_history
+    }
+    pub fn get_money_history(&self) -> &Vec<u16> {
+        &self.money_history
+    }
+    pub fn get_pollution_history(&self) -> &Vec<u16> {
+        &self.pollution_history
+    }
+    pub fn get_crime_history(&self) -> &Vec<u16> {
+        &self.crime_history
+    }
+    pub fn get_misc_history(&self) -> &Vec<u16> {
+        &self.misc_history
+    }
+    pub fn has_census_changed(&self) -> bool {
+        self.census_changed
+    }
+
+    /// 1 if the city needs a hospital, -1 if it has too many, 0 otherwise.
+    pub fn get_need_hospital(&self) -> i8 {
+        self.need_hospital
+    }
+    pub fn set_need_hospital(&mut self, need_hospital: i8) {
+        self.need_hospital = need_hospital;
+    }
+
+    /// 1 if the city needs a church, -1 if it has too many, 0 otherwise.
+    pub fn get_need_church(&self) -> i8 {
+        self.need_church
+    }
+    pub fn set_need_church(&mut self, need_church: i8) {
+        self.need_church = need_church;
+    }
+
+    /// Restore the histories from a city file.
+    pub(crate) fn load_from_file_data(&mut self, data: &CityFileData) {
+        self.residential_history = data.residential_history.clone();
+        self.commercial_history = data.commercial_history.clone();
+        self.industrial_history = data.industrial_history.clone();
+        self.crime_history = data.crime_history.clone();
+        self.pollution_history = data.pollution_history.clone();
+        self.money_history = data.money_history.clone();
+        self.misc_history = data.misc_history.clone();
+        self.on_census_changed();
+    }
+
+    /// Store the histories into a city file.
+    pub(crate) fn save_to_file_data(&self, data: &mut CityFileData) {
+        data.residential_history = self.residential_history.clone();
+        data.commercial_history = self.commercial_history.clone();
+        data.industrial_history = self.industrial_history.clone();
+        data.crime_history = self.crime_history.clone();
+        data.pollution_history = self.pollution_history.clone();
+        data.money_history = self.money_history.clone();
+        data.misc_history = self.misc_history.clone();
+    }
+
     /// Take monthly snapshots of all relevant data for the historic graphs.
     ///
     /// Also update variables that control building new churches and hospitals.
@@ -116,14 +196,16 @@ impl CitySimulationCensus {
         self.commercial_history[0] = population.get_commercial();
         self.industrial_history[0] = population.get_industrial();
 
-        statistics.crime_ramp += (statistics.average_crime - statistics.crime_ramp) / 4;
+        let ramp =
+            |ramp: u16, average: u16| (ramp as i32 + (average as i32 - ramp as i32) / 4) as u16;
+
+        statistics.crime_ramp = ramp(statistics.crime_ramp, statistics.average_crime);
         self.crime_history[0] = min(statistics.crime_ramp, 255);
 
-        statistics.pollution_ramp += (statistics.average_pollution - statistics.pollution_ramp) / 4;
+        statistics.pollution_ramp = ramp(statistics.pollution_ramp, statistics.average_pollution);
         self.pollution_history[0] = min(statistics.pollution_ramp, 255);
 
-        let x = ((cash_flow / 20) + 128) as u16;
-        self.money_history[0] = clamp(x, 0, 255);
+        self.money_history[0] = clamp((cash_flow / 20) + 128, 0, 255) as u16;
 
         self.on_census_changed();
 
@@ -1,10 +1,15 @@
-use crate::utils::Percentage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    city::budget::{CityBudget, MoneyValue},
+    utils::Percentage,
+};
 
 pub const MAX_ROAD_EFFECT: u64 = 32;
 pub const MAX_FIRE_EFFECT: u64 = 1000;
 pub const MAX_POLICE_EFFECT: u64 = 1000;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SimulationParameters {
     /// Used to normalize residential population.
     residential_population_denominator: u32,
@@ -14,6 +19,8 @@ pub struct SimulationParameters {
     birth_rate: Percentage,
     /// Maximum `labor_base` value.
     max_labor_base: Percentage,
+    /// Used to normalize the internal market.
+    internal_market_denominator: Percentage,
     /// Minimum `projected_industrial_population` value.
     min_projected_industrial_population: Percentage,
     /// Default residential population ratio.
@@ -42,6 +49,7 @@ impl Default for SimulationParameters {
             residential_population_denominator: 8,
             birth_rate: 0.02.into(),
             max_labor_base: 1.3.into(),
+            internal_market_denominator: 3.7.into(),
             min_projected_industrial_population: 5.0.into(),
             default_residential_ratio: 1.3.into(),
             max_residential_ratio: 2.0.into(),
@@ -57,6 +65,50 @@ impl Default for SimulationParameters {
 }
 
 impl SimulationParameters {
+    pub fn get_residential_population_denominator(&self) -> u32 {
+        self.residential_population_denominator
+    }
+
+    pub fn get_birth_rate(&self) -> f64 {
+        self.birth_rate.value()
+    }
+
+    pub fn get_max_labor_base(&self) -> f64 {
+        self.max_labor_base.value()
+    }
+
+    pub fn get_internal_market_denominator(&self) -> f64 {
+        self.internal_market_denominator.value()
+    }
+
+    pub fn get_min_projected_industrial_population(&self) -> f64 {
+        self.min_projected_industrial_population.value()
+    }
+
+    pub fn get_default_residential_ratio(&self) -> f64 {
+        self.default_residential_ratio.value()
+    }
+
+    pub fn get_max_residential_ratio(&self) -> f64 {
+        self.max_residential_ratio.value()
+    }
+
+    pub fn get_max_commercial_ratio(&self) -> f64 {
+        self.max_commercial_ratio.value()
+    }
+
+    pub fn get_max_industrial_ratio(&self) -> f64 {
+        self.max_industrial_ratio.value()
+    }
+
+    pub fn get_max_tax_rate(&self) -> f64 {
+        self.max_tax_rate.value()
+    }
+
+    pub fn get_tax_table_scale(&self) -> f64 {
+        self.tax_table_scale.value()
+    }
+
     pub fn get_road_effect(&self) -> u64 {
         self.road_effect
     }
@@ -68,6 +120,33 @@ impl SimulationParameters {
     pub fn get_police_effect(&self) -> u64 {
         self.police_effect
     }
+
+    /// Compute the effects of the road, fire and police funding.
+    ///
+    /// See `Micropolis::updateFundEffects` in the C++ code.
+    pub fn update_fund_effects(&mut self, budget: &CityBudget) {
+        let effect = |max_effect: u64, funds: MoneyValue, spent: MoneyValue| {
+            if funds > 0 {
+                (max_effect as f64 * spent as f64 / funds as f64) as u64
+            } else {
+                max_effect
+            }
+        };
+
+        let roads = budget.get_roads();
+        self.road_effect = effect(MAX_ROAD_EFFECT, roads.get_funds(), roads.get_value());
+        let fire = budget.get_fire_department();
+        self.fire_effect = effect(MAX_FIRE_EFFECT, fire.get_funds(), fire.get_value());
+        let police = budget.get_police_department();
+        self.police_effect = effect(MAX_POLICE_EFFECT, police.get_funds(), police.get_value());
+    }
+
+    /// Reset the funding effects to their maximum.
+    pub fn reset_fund_effects(&mut self) {
+        self.road_effect = MAX_ROAD_EFFECT;
+        self.fire_effect = MAX_FIRE_EFFECT;
+        self.police_effect = MAX_POLICE_EFFECT;
+    }
 }
 
 #[cfg(test)]
@@ -80,6 +159,7 @@ mod tests {
         assert_eq!(parameters.residential_population_denominator, 8);
         assert_eq!(parameters.birth_rate.value(), 0.02);
         assert_eq!(parameters.max_labor_base.value(), 1.3);
+        assert_eq!(parameters.internal_market_denominator.value(), 3.7);
         assert_eq!(parameters.min_projected_industrial_population.value(), 5.0);
         assert_eq!(parameters.default_residential_ratio.value(), 1.3);
         assert_eq!(parameters.max_residential_ratio.value(), 2.0);
@@ -1,8 +1,10 @@
 use std::cmp::min;
 
 use num_traits::abs;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    error::MicropolisError,
     map::{
         tiles::{TILE_LOW_MASK, TILE_ZONE_BIT},
         Map, MapClusteringStrategy, MapPosition, TileMap, TileType,
@@ -15,6 +17,7 @@ use super::zones::{
     get_residential_zone_population,
 };
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CitySimulationScanner {
     /// Temporary map 1.
     ///
@@ -73,7 +76,7 @@ impl CitySimulationScanner {
         population_density_map: &mut Map<u8>,
         commercial_rate_map: &mut Map<i16>,
         current_city_center: &MapPosition,
-    ) -> Result<MapPosition, String> {
+    ) -> Result<MapPosition, MicropolisError> {
         self.temp_map_1.clear(0x00);
 
         let (mut x_total, mut y_total, mut z_total) = (0, 0, 0);
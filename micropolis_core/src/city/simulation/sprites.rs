@@ -1,6 +1,11 @@
 use crate::{
-    city::sprite::{ActiveSpritesList, Sprite, SpriteType},
+    city::{
+        events::CityEvents,
+        sprite::{ActiveSpritesList, Sprite, SpriteType},
+    },
+    error::MicropolisError,
     map::{tiles::TILE_BULL_BIT, MapPosition, TileMap, TileType},
+    messages::GameMessage,
     utils::random::MicropolisRandom,
 };
 
@@ -15,7 +20,7 @@ pub fn generate_train(
     sprites: &mut ActiveSpritesList,
     position: &MapPosition,
     total_population: u32,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if total_population <= 20
         || sprites.get_sprite(&SpriteType::Train).is_some()
         || rng.get_random(25) != 0
@@ -36,7 +41,7 @@ pub fn generate_bus(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
     position: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if sprites.get_sprite(&SpriteType::Bus).is_some() || rng.get_random(25) != 0 {
         return Ok(());
     }
@@ -54,7 +59,7 @@ pub fn generate_ship(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
     map: &TileMap,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     let map_size = map.bounds();
 
     if rng.get_random_16() & 0x03 != 0x00 {
@@ -112,7 +117,7 @@ fn make_ship_at(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
     position: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     let sprite_position: MapPosition =
         ((position.get_x() << 4) - (48 - 1), (position.get_y() << 4)).into();
     make_sprite(rng, sprites, &SpriteType::Ship, &sprite_position)
@@ -125,7 +130,7 @@ pub fn generate_plane(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
     position: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if sprites.get_sprite(&SpriteType::Airplane).is_some() {
         return Ok(());
     }
@@ -142,7 +147,7 @@ pub fn generate_copter(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
     position: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if sprites.get_sprite(&SpriteType::Helicopter).is_some() {
         return Ok(());
     }
@@ -152,24 +157,44 @@ pub fn generate_copter(
     make_sprite(rng, sprites, &SpriteType::Helicopter, &sprite_position)
 }
 
-// Ensure a tornado sprite exists.
+/// Ensure a tornado sprite exists.
+///
+/// If it does not exist, create one at the given map tile, or at a random one.
 pub fn make_tornado(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
+    events: &mut CityEvents,
     map: &TileMap,
-) -> Result<(), String> {
+    at: Option<&MapPosition>,
+) -> Result<(), MicropolisError> {
     if let Some(sprite) = sprites.get_sprite_mut(&SpriteType::Tornado) {
         sprite.count = 200;
         return Ok(());
     }
 
     let bounds = map.bounds();
-    let sprite_position: MapPosition = (
-        rng.get_random((bounds.get_width() << 4) as i16 - 800) + 400,
-        rng.get_random((bounds.get_height() << 4) as i16 - 200) + 100,
-    )
-        .into();
-    make_sprite(rng, sprites, &SpriteType::Tornado, &sprite_position)
+    let sprite_position: MapPosition = match at {
+        Some(at) => *at << 4,
+        None => (
+            rng.get_random((bounds.get_width() << 4) as i16 - 800) + 400,
+            rng.get_random((bounds.get_height() << 4) as i16 - 200) + 100,
+        )
+            .into(),
+    };
+    make_sprite(rng, sprites, &SpriteType::Tornado, &sprite_position)?;
+    events.send_message(
+        GameMessage::MessageTornadoSighted,
+        Some(
+            (
+                (sprite_position.get_x() >> 4) + 3,
+                (sprite_position.get_y() >> 4) + 2,
+            )
+                .into(),
+        ),
+        true,
+        true,
+    );
+    Ok(())
 }
 
 /// Start a new monster sprite.
@@ -180,9 +205,10 @@ pub fn make_tornado(
 pub fn make_monster(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
+    events: &mut CityEvents,
     map: &TileMap,
     maximum_pollution_at: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     let mut done = false;
     let mut position: MapPosition = (0, 0).into();
 
@@ -210,7 +236,7 @@ pub fn make_monster(
             if tile_raw == river_tile_type_value
                 || tile_raw == river_tile_type_value + TILE_BULL_BIT
             {
-                make_monster_at(rng, sprites, &position)?;
+                make_monster_at(rng, sprites, events, &position, maximum_pollution_at)?;
                 done = true;
                 break;
             }
@@ -220,20 +246,33 @@ pub fn make_monster(
     if done {
         Ok(())
     } else {
-        make_monster_at(rng, sprites, &(60, 50).into())
+        make_monster_at(rng, sprites, events, &(60, 50).into(), maximum_pollution_at)
     }
 }
 
-/// Start a new monster sprite at the given map tile.
-fn make_monster_at(
+/// Start a new monster sprite at the given map tile, heading for the most polluted place.
+pub fn make_monster_at(
     rng: &mut MicropolisRandom,
     sprites: &mut ActiveSpritesList,
+    events: &mut CityEvents,
     position: &MapPosition,
-) -> Result<(), String> {
+    maximum_pollution_at: &MapPosition,
+) -> Result<(), MicropolisError> {
     let monster_position: MapPosition =
         ((position.get_x() << 4) + 48, position.get_y() << 4).into();
-    make_sprite(rng, sprites, &SpriteType::Monster, &monster_position)?;
-    // TODO: sendMessage(MESSAGE_MONSTER_SIGHTED, x + 5, y, true, true)
+    sprites.add_sprite(Sprite::new(
+        rng,
+        "".into(),
+        &SpriteType::Monster,
+        monster_position,
+        Some(maximum_pollution_at),
+    )?);
+    events.send_message(
+        GameMessage::MessageMonsterSighted,
+        Some(*position + (5, 0).into()),
+        true,
+        true,
+    );
     Ok(())
 }
 
@@ -243,7 +282,7 @@ pub fn make_sprite(
     sprites: &mut ActiveSpritesList,
     kind: &SpriteType,
     position: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if let Some(sprite) = sprites.get_sprite_mut(kind) {
         kind.init_sprite(rng, sprite, None)?;
     } else {
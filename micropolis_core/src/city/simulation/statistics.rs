@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::map::MapPosition;
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct SimulationStatistics {
     /// Number of road tiles in the game.
     ///
@@ -22,6 +24,8 @@ pub struct SimulationStatistics {
     pub police_station_count: u16,
     /// Fire station population.
     pub fire_station_count: u16,
+    /// Number of burning tiles.
+    pub fires_count: u16,
     /// Seaport station population.
     pub seaport_count: u16,
     /// Airport station population.
@@ -46,3 +50,19 @@ pub struct SimulationStatistics {
     /// Affected by distance from city center, development dencity, pollution and crime.
     pub average_land_value: u16,
 }
+
+impl SimulationStatistics {
+    /// Reset the building counters before a new map scan.
+    pub fn clear_census(&mut self) {
+        self.road_total = 0;
+        self.rail_total = 0;
+        self.hospital_count = 0;
+        self.church_count = 0;
+        self.stadium_count = 0;
+        self.police_station_count = 0;
+        self.fire_station_count = 0;
+        self.fires_count = 0;
+        self.seaport_count = 0;
+        self.airport_count = 0;
+    }
+}
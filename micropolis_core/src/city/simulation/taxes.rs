@@ -1,6 +1,15 @@
-use crate::city::budget::MoneyValue;
+use serde::{Deserialize, Serialize};
 
-use super::statistics::SimulationStatistics;
+use crate::{
+    city::{
+        budget::{CityBudget, MoneyValue},
+        population::CityPopulation,
+        treasury::{CityTreasury, TransactionCategory},
+    },
+    game::GameLevelDifficulty,
+};
+
+use super::{parameters::SimulationParameters, statistics::SimulationStatistics};
 
 // TODO: break out into individual user-configurable parameters
 const R_LEVELS: [f64; 3] = [0.7, 0.9, 1.2];
@@ -14,7 +23,21 @@ pub const TAX_TABLE: [i16; 21] = [
 
 pub const EXTERNAL_MARKET_PARAMETERS_TABLE: [f64; 3] = [1.2, 1.1, 0.98];
 
-#[derive(Clone, Debug, PartialEq, Default)]
+/// Yearly cost of a police station.
+const POLICE_STATION_COST: MoneyValue = 100;
+/// Yearly cost of a fire station.
+const FIRE_STATION_COST: MoneyValue = 100;
+
+/// State of the city the taxes and the department funds are assessed on.
+pub struct TaxAssessment<'a> {
+    pub difficulty: &'a GameLevelDifficulty,
+    pub population: &'a CityPopulation,
+    pub statistics: &'a SimulationStatistics,
+    /// City time at which the taxes are collected, to date the transactions.
+    pub city_time: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SimulationTaxes {
     /// City tax rate.
     pub city_tax: u16,
@@ -26,8 +49,23 @@ pub struct SimulationTaxes {
     pub tax_flag: bool,
 }
 
+impl Default for SimulationTaxes {
+    fn default() -> Self {
+        Self {
+            city_tax: 7,
+            city_tax_average: 0,
+            tax_flag: false,
+        }
+    }
+}
+
 impl SimulationTaxes {
-    /// Collect city taxes.
+    /// Collect city taxes, then pay for the roads, fire and police departments.
+    ///
+    /// The tax income and the spending are recorded in the treasury ledger.
+    /// Returns the resulting cash flow.
+    ///
+    /// See `Micropolis::collectTax` in the C++ code.
     ///
     /// @bug Function seems to be doing different things depending on
     ///      Micropolis::totalPop value. With an non-empty city it does fund
@@ -36,9 +74,224 @@ impl SimulationTaxes {
     ///
     /// @bug If Micropolis::taxFlag is set, no variable is touched which seems
     ///      non-robust at least
-    pub fn collect_taxes(&mut self, cash_flow: &mut MoneyValue, statistics: &SimulationStatistics) {
-        *cash_flow = 0;
-
+    pub fn collect_taxes(
+        &mut self,
+        assessment: &TaxAssessment,
+        parameters: &mut SimulationParameters,
+        budget: &mut CityBudget,
+        treasury: &mut CityTreasury,
+    ) -> MoneyValue {
+        let TaxAssessment {
+            difficulty,
+            population,
+            statistics,
+            city_time,
+        } = *assessment;
         // TODO: apparently tax_flag is never set to true so this always run
+        if self.tax_flag {
+            return 0;
+        }
+        self.city_tax_average = 0;
+
+        let level = difficulty.to_usize().unwrap();
+        let total_population = (population.get_residential() as u32
+            / parameters.get_residential_population_denominator())
+            + population.get_commercial() as u32
+            + population.get_industrial() as u32;
+
+        budget
+            .get_police_department_mut()
+            .set_funds(statistics.police_station_count as MoneyValue * POLICE_STATION_COST);
+        budget
+            .get_fire_department_mut()
+            .set_funds(statistics.fire_station_count as MoneyValue * FIRE_STATION_COST);
+        budget.get_roads_mut().set_funds(
+            ((statistics.road_total as u32 + statistics.rail_total as u32 * 2) as f64
+                * R_LEVELS[level]) as MoneyValue,
+        );
+        budget.set_tax_funds(
+            ((total_population as u64 * statistics.average_land_value as u64 / 120
+                * self.city_tax as u64) as f64
+                * F_LEVELS[level]) as MoneyValue,
+        );
+
+        if total_population == 0 {
+            // nobody lives here
+            parameters.reset_fund_effects();
+            return 0;
+        }
+
+        let expenses = budget.get_police_department().get_funds()
+            + budget.get_fire_department().get_funds()
+            + budget.get_roads().get_funds();
+        let cash_flow = budget.get_tax_funds() - expenses;
+
+        // TODO: budget window when auto budget is off (doBudgetNow)
+        budget.update_budget_lines(treasury.get_balance());
+        treasury.record(
+            city_time,
+            TransactionCategory::Taxes,
+            budget.get_tax_funds(),
+        );
+        treasury.spend(
+            city_time,
+            TransactionCategory::RoadsMaintenance,
+            budget.get_roads().get_value(),
+        );
+        treasury.spend(
+            city_time,
+            TransactionCategory::FireDepartment,
+            budget.get_fire_department().get_value(),
+        );
+        treasury.spend(
+            city_time,
+            TransactionCategory::PoliceDepartment,
+            budget.get_police_department().get_value(),
+        );
+        parameters.update_fund_effects(budget);
+
+        cash_flow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SimulationTaxes, TaxAssessment};
+    use crate::{
+        city::{
+            budget::CityBudget,
+            population::CityPopulation,
+            simulation::{
+                parameters::{SimulationParameters, MAX_FIRE_EFFECT, MAX_ROAD_EFFECT},
+                statistics::SimulationStatistics,
+            },
+            treasury::{CityTreasury, TransactionCategory},
+        },
+        game::GameLevelDifficulty,
+        map::{Map, MapRectangle, TileType},
+    };
+
+    fn build_population(residential: u16, commercial: u16, industrial: u16) -> CityPopulation {
+        let map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let mut population = CityPopulation::from_map(&map);
+        population.add_residential(residential);
+        population.add_commercial(commercial);
+        population.add_industrial(industrial);
+        population
+    }
+
+    fn build_statistics() -> SimulationStatistics {
+        SimulationStatistics {
+            road_total: 100,
+            rail_total: 10,
+            police_station_count: 2,
+            fire_station_count: 1,
+            average_land_value: 60,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collect_taxes() {
+        let mut taxes = SimulationTaxes::default();
+        let mut parameters = SimulationParameters::default();
+        let mut budget = CityBudget::new();
+        let mut treasury = CityTreasury::new(10000);
+
+        // 8000 / 8 + 600 + 400 = 2000 taxable citizens
+        let population = build_population(8000, 600, 400);
+        let cash_flow = taxes.collect_taxes(
+            &TaxAssessment {
+                difficulty: &GameLevelDifficulty::Normal,
+                population: &population,
+                statistics: &build_statistics(),
+                city_time: 48,
+            },
+            &mut parameters,
+            &mut budget,
+            &mut treasury,
+        );
+
+        // 2000 * 60 / 120 * 7 * 1.2
+        assert_eq!(budget.get_tax_funds(), 8400);
+        assert_eq!(budget.get_police_department().get_funds(), 200);
+        assert_eq!(budget.get_fire_department().get_funds(), 100);
+        // (100 + 10 * 2) * 0.9
+        assert_eq!(budget.get_roads().get_funds(), 108);
+        assert_eq!(cash_flow, 8400 - 408);
+        assert_eq!(treasury.get_balance(), 10000 + 8400 - 408);
+        let summary = treasury.get_year_summary(48);
+        assert_eq!(summary.income, 8400);
+        assert_eq!(summary.expenses, 408);
+        assert_eq!(
+            summary
+                .by_category
+                .get(&TransactionCategory::PoliceDepartment),
+            Some(&-200)
+        );
+        assert_eq!(parameters.get_road_effect(), MAX_ROAD_EFFECT);
+        assert_eq!(parameters.get_fire_effect(), MAX_FIRE_EFFECT);
+    }
+
+    #[test]
+    fn test_collect_taxes_underfunded() {
+        let mut taxes = SimulationTaxes {
+            city_tax: 0,
+            ..Default::default()
+        };
+        let mut parameters = SimulationParameters::default();
+        let mut budget = CityBudget::new();
+        let mut treasury = CityTreasury::new(150);
+
+        let population = build_population(800, 10, 10);
+        let cash_flow = taxes.collect_taxes(
+            &TaxAssessment {
+                difficulty: &GameLevelDifficulty::Hard,
+                population: &population,
+                statistics: &build_statistics(),
+                city_time: 48,
+            },
+            &mut parameters,
+            &mut budget,
+            &mut treasury,
+        );
+
+        // roads (144) are funded first, the fire department gets what remains
+        assert_eq!(cash_flow, -(144 + 100 + 200));
+        assert_eq!(budget.get_roads().get_value(), 144);
+        assert_eq!(budget.get_fire_department().get_value(), 6);
+        assert_eq!(budget.get_police_department().get_value(), 0);
+        assert_eq!(treasury.get_balance(), 0);
+        assert_eq!(parameters.get_road_effect(), MAX_ROAD_EFFECT);
+        assert_eq!(parameters.get_fire_effect(), 60);
+        assert_eq!(parameters.get_police_effect(), 0);
+    }
+
+    #[test]
+    fn test_collect_taxes_empty_city() {
+        let mut taxes = SimulationTaxes::default();
+        let mut parameters = SimulationParameters::default();
+        let mut budget = CityBudget::new();
+        let mut treasury = CityTreasury::new(500);
+        budget.get_roads_mut().set_funds(100);
+        parameters.update_fund_effects(&budget);
+        assert_eq!(parameters.get_road_effect(), 0);
+
+        let cash_flow = taxes.collect_taxes(
+            &TaxAssessment {
+                difficulty: &GameLevelDifficulty::Easy,
+                population: &build_population(0, 0, 0),
+                statistics: &build_statistics(),
+                city_time: 48,
+            },
+            &mut parameters,
+            &mut budget,
+            &mut treasury,
+        );
+        assert_eq!(cash_flow, 0);
+        assert_eq!(treasury.get_balance(), 500);
+        assert!(treasury.get_ledger().is_empty());
+        assert_eq!(parameters.get_road_effect(), MAX_ROAD_EFFECT);
     }
 }
@@ -1,4 +1,31 @@
-use crate::map::{tiles::TILE_LOW_MASK, MapPosition, TileMap, TileType};
+use std::cmp;
+
+use crate::{
+    city::{power::CityPower, zoning::ZoneType, City},
+    error::MicropolisError,
+    map::{
+        tiles::{TILE_BULL_BIT, TILE_BURN_BULL_CONDUCT_BIT, TILE_LOW_MASK, TILE_POWER_BIT},
+        tiles::{TILE_BURN_BIT, TILE_CONDUCT_BIT, TILE_ZONE_BIT},
+        MapPosition, Tile, TileMap, TileType,
+    },
+    utils::clamp,
+};
+
+use super::Simulation;
+
+/// Zone score above which a zone may grow, and below which it may decline.
+const ZONE_SCORE_THRESHOLD: i32 = 350;
+/// Offset of the zone score before comparing it to a signed random number.
+const ZONE_SCORE_RANDOM_OFFSET: i32 = 26380;
+
+/// Offsets of the neighboring lots where a house can be built, the first one being the center.
+const HOUSE_LOTS_DX: [i32; 9] = [0, -1, 0, 1, -1, 1, -1, 0, 1];
+const HOUSE_LOTS_DY: [i32; 9] = [0, -1, -1, -1, 0, 0, 1, 1, 1];
+/// Offsets of the adjacent tiles of a lot.
+const LOT_NEIGHBORS_DX: [i32; 4] = [0, 1, 0, -1];
+const LOT_NEIGHBORS_DY: [i32; 4] = [-1, 0, 1, 0];
+/// Tile offset, from the top-left corner of a zone, of the tiles traversed column by column.
+const ZONE_BORDER_OFFSETS: [u16; 9] = [0, 3, 6, 1, 4, 7, 2, 5, 8];
 
 /// Count the number of single tile houses in a residential zone.
 ///
@@ -10,8 +37,8 @@ pub fn count_free_population(map: &TileMap, at: &MapPosition) -> u16 {
         TileType::Hhthr.to_u16().unwrap(),
     );
 
-    for x in (at.get_x() - 1)..(at.get_x() + 1) {
-        for y in (at.get_y() - 1)..(at.get_y() + 1) {
+    for x in (at.get_x() - 1)..=(at.get_x() + 1) {
+        for y in (at.get_y() - 1)..=(at.get_y() + 1) {
             if let Some(tile) = map.get_tile_at(&(x, y).into()) {
                 let tile_value = tile.get_raw() & TILE_LOW_MASK;
                 if tile_value >= house_type_min && tile_value <= house_type_max {
@@ -53,3 +80,808 @@ pub fn get_industrial_zone_population(tile_value: u16) -> u16 {
         (((tile_value - TileType::IndustrialZoneBase.to_u16().unwrap()) / 9) % 4) + 1
     }
 }
+
+/// Read the low tile value at the given position.
+fn get_tile_value(map: &TileMap, at: &MapPosition) -> Result<u16, MicropolisError> {
+    map.get_tile_at(at)
+        .map(|tile| tile.get_raw() & TILE_LOW_MASK)
+        .ok_or(MicropolisError::OutOfBounds(*at))
+}
+
+/// Set the raw value of the tile at the given position, if inside the map.
+fn set_tile_raw(map: &mut TileMap, at: &MapPosition, raw: u16) -> Result<(), MicropolisError> {
+    if map.in_bounds(at) {
+        map.set_tile_at(at, Tile::from_raw(raw)?);
+    }
+    Ok(())
+}
+
+/// Copy the power grid status of the zone at the given position into its center tile.
+///
+/// Power plants always power themselves.
+///
+/// Returns true if the zone is powered.
+pub fn set_zone_power(map: &mut TileMap, power: &CityPower, at: &MapPosition) -> bool {
+    let powered = power.is_powered_at(at);
+    if let Some(tile) = map.get_tile_mut_at(at) {
+        let raw = tile.get_raw();
+        let tile_value = raw & TILE_LOW_MASK;
+        let powered = powered
+            || tile_value == TileType::Nuclear.to_u16().unwrap()
+            || tile_value == TileType::PowerPlant.to_u16().unwrap();
+        tile.set_raw(if powered {
+            raw | TILE_POWER_BIT
+        } else {
+            raw & !TILE_POWER_BIT
+        });
+        powered
+    } else {
+        false
+    }
+}
+
+/// Place a 3x3 zone around the given center position, starting from the top-left tile `base`.
+///
+/// Returns false if the zone was not placed because of a fire or a flood.
+///
+/// See zone.cpp::zonePlop in the original codebase.
+pub fn zone_plop(
+    map: &mut TileMap,
+    power: &CityPower,
+    at: &MapPosition,
+    base: u16,
+) -> Result<bool, MicropolisError> {
+    let (flood, road_base) = (
+        TileType::Flood.to_u16().unwrap(),
+        TileType::HorizontalBridge.to_u16().unwrap(),
+    );
+    for y in -1..=1 {
+        for x in -1..=1 {
+            let position = *at + (x, y).into();
+            if let Some(tile) = map.get_tile_at(&position) {
+                let tile_value = tile.get_raw() & TILE_LOW_MASK;
+                if tile_value >= flood && tile_value < road_base {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    let mut tile_raw = base;
+    for y in -1..=1 {
+        for x in -1..=1 {
+            set_tile_raw(
+                map,
+                &(*at + (x, y).into()),
+                tile_raw | TILE_BURN_BIT | TILE_CONDUCT_BIT,
+            )?;
+            tile_raw += 1;
+        }
+    }
+
+    set_zone_power(map, power, at);
+    if let Some(tile) = map.get_tile_mut_at(at) {
+        tile.set_raw(tile.get_raw() | TILE_ZONE_BIT | TILE_BULL_BIT);
+    }
+    Ok(true)
+}
+
+/// Evaluate a lot for building a house.
+///
+/// Returns -1 if the lot is not clear, otherwise 1 plus the number of adjacent roads.
+fn evaluate_lot(map: &TileMap, at: &MapPosition) -> Result<i16, MicropolisError> {
+    let residential_base = TileType::ResidentialBase.to_u16().unwrap();
+    let tile_value = get_tile_value(map, at)?;
+    if tile_value != 0 && (tile_value < residential_base || tile_value > residential_base + 8) {
+        return Ok(-1);
+    }
+
+    let mut score = 1;
+    for z in 0..4 {
+        let position = *at + (LOT_NEIGHBORS_DX[z], LOT_NEIGHBORS_DY[z]).into();
+        if let Some(tile) = map.get_tile_at(&position) {
+            if tile.get_raw() != 0
+                && tile.get_raw() & TILE_LOW_MASK <= TileType::LastRoad.to_u16().unwrap()
+            {
+                score += 1;
+            }
+        }
+    }
+    Ok(score)
+}
+
+impl Simulation {
+    /// Update the zone whose center is at the given position.
+    ///
+    /// See zone.cpp::doZone in the original codebase.
+    pub(super) fn do_zone(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        let is_zone_powered = set_zone_power(&mut city.map, &city.power, at);
+        city.power.count_zone(is_zone_powered);
+        let tile_value = get_tile_value(&city.map, at)?;
+
+        if tile_value > TileType::PortBase.to_u16().unwrap() {
+            return self.do_special_zone(
+                &mut city.rng,
+                &mut city.map,
+                &mut city.power,
+                &mut city.sprites,
+                &mut city.events,
+                at,
+                city.city_time,
+                is_zone_powered,
+                city.disasters.are_disasters_enabled(),
+                &city.difficulty,
+            );
+        }
+        if tile_value < TileType::Hospital.to_u16().unwrap() {
+            return self.do_residential(city, at, tile_value, is_zone_powered);
+        }
+        if tile_value < TileType::CommercialBase.to_u16().unwrap() {
+            return self.do_hospital_church(city, at, tile_value);
+        }
+        if tile_value < TileType::IndustrialBase.to_u16().unwrap() {
+            return self.do_commercial(city, at, tile_value, is_zone_powered);
+        }
+        self.do_industrial(city, at, tile_value, is_zone_powered)
+    }
+
+    /// Try to drive from the zone at the given position to a zone of the given type.
+    ///
+    /// Returns 1 if the trip succeeded, 0 if no destination was found
+    /// and -1 if the zone has no road access.
+    fn make_traffic(
+        city: &mut City,
+        at: &MapPosition,
+        destination_zone: &ZoneType,
+    ) -> Result<i8, MicropolisError> {
+        Ok(
+            match city.traffic.spawn_traffic(
+                &mut city.rng,
+                &city.map,
+                at,
+                destination_zone,
+                &mut city.sprites,
+            )? {
+                Some(true) => 1,
+                Some(false) => 0,
+                None => -1,
+            },
+        )
+    }
+
+    /// Draw whether a zone with the given score grows.
+    fn should_zone_grow(city: &mut City, score: i32) -> bool {
+        score > -ZONE_SCORE_THRESHOLD
+            && score - ZONE_SCORE_RANDOM_OFFSET > city.rng.get_random_16_signed() as i32
+    }
+
+    /// Draw whether a zone with the given score declines.
+    fn should_zone_decline(city: &mut City, score: i32) -> bool {
+        score < ZONE_SCORE_THRESHOLD
+            && score + ZONE_SCORE_RANDOM_OFFSET < (city.rng.get_random_16_signed() as i32)
+    }
+
+    /// Get the land value class (between 0 and 3) at the given position,
+    /// lowered by pollution.
+    fn get_land_pollution_value(&self, at: &MapPosition) -> u16 {
+        let land_value = *self.land_value_map.get_tile_at(at).unwrap_or(&0) as i16
+            - *self.pollution_density.get_tile_at(at).unwrap_or(&0) as i16;
+        match land_value {
+            n if n < 30 => 0,
+            n if n < 80 => 1,
+            n if n < 150 => 2,
+            _ => 3,
+        }
+    }
+
+    /// Increase the rate of growth at the given position by the given amount.
+    fn increase_rate_of_growth(&mut self, at: &MapPosition, amount: i16) {
+        if let Some(rate) = self.rate_of_growth.get_tile_mut_at(at) {
+            *rate = clamp(*rate + amount * 4, -200, 200);
+        }
+    }
+
+    /// Update a residential zone.
+    ///
+    /// See zone.cpp::doResidential in the original codebase.
+    fn do_residential(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        tile_value: u16,
+        is_zone_powered: bool,
+    ) -> Result<(), MicropolisError> {
+        let is_free_zone = tile_value == TileType::FreeZoneCenter.to_u16().unwrap();
+        let population = if is_free_zone {
+            count_free_population(&city.map, at)
+        } else {
+            get_residential_zone_population(tile_value)
+        };
+        city.population.add_residential(population);
+
+        let traffic_good = if population as i16 > city.rng.get_random(35) {
+            // try driving from residential to commercial
+            Self::make_traffic(city, at, &ZoneType::Commercial)?
+        } else {
+            1
+        };
+
+        if traffic_good == -1 {
+            let value = self.get_land_pollution_value(at);
+            return self.do_residential_out(city, at, population, value);
+        }
+
+        if is_free_zone || city.rng.get_random_16() & 0x07 == 0x00 {
+            let mut score = city.population.get_residential_valve() as i32
+                + self.evaluate_residential(at, traffic_good) as i32;
+            if !is_zone_powered {
+                score = -500;
+            }
+
+            if Self::should_zone_grow(city, score) {
+                if population == 0 && city.rng.get_random_16() & 0x03 == 0x00 {
+                    return self.make_hospital(city, at);
+                }
+                let value = self.get_land_pollution_value(at);
+                return self.do_residential_in(city, at, tile_value, population, value);
+            }
+
+            if Self::should_zone_decline(city, score) {
+                let value = self.get_land_pollution_value(at);
+                return self.do_residential_out(city, at, population, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate the desirability of a residential zone, from its land value and pollution.
+    fn evaluate_residential(&self, at: &MapPosition, traffic_good: i8) -> i16 {
+        if traffic_good < 0 {
+            return -3000;
+        }
+
+        let value = *self.land_value_map.get_tile_at(at).unwrap_or(&0) as i16
+            - *self.pollution_density.get_tile_at(at).unwrap_or(&0) as i16;
+        let value = if value < 0 {
+            0
+        } else {
+            cmp::min(value * 32, 6000)
+        };
+        value - 3000
+    }
+
+    /// Grow a residential zone.
+    fn do_residential_in(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        tile_value: u16,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        if *self.pollution_density.get_tile_at(at).unwrap_or(&0) > 128 {
+            return Ok(());
+        }
+
+        if tile_value == TileType::FreeZoneCenter.to_u16().unwrap() {
+            if population < 8 {
+                Self::build_house(city, at, value)?;
+                self.increase_rate_of_growth(at, 1);
+            } else if city.population.get_density_at(at) > 64 {
+                Self::residential_plop(city, at, 0, value)?;
+                self.increase_rate_of_growth(at, 8);
+            }
+            return Ok(());
+        }
+
+        if population < 40 {
+            Self::residential_plop(city, at, population / 8 - 1, value)?;
+            self.increase_rate_of_growth(at, 8);
+        }
+        Ok(())
+    }
+
+    /// Shrink a residential zone.
+    fn do_residential_out(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        if population == 0 {
+            return Ok(());
+        }
+
+        if population > 16 {
+            Self::residential_plop(city, at, (population - 24) / 8, value)?;
+            self.increase_rate_of_growth(at, -8);
+            return Ok(());
+        }
+
+        let free_zone_center = TileType::FreeZoneCenter.to_u16().unwrap();
+        if population == 16 {
+            // downgrade to a free zone with houses
+            self.increase_rate_of_growth(at, -8);
+            set_tile_raw(
+                &mut city.map,
+                at,
+                free_zone_center | TILE_BURN_BULL_CONDUCT_BIT | TILE_ZONE_BIT,
+            )?;
+            for x in (at.get_x() - 1)..=(at.get_x() + 1) {
+                for y in (at.get_y() - 1)..=(at.get_y() + 1) {
+                    let position: MapPosition = (x, y).into();
+                    if let Some(tile) = city.map.get_tile_at(&position) {
+                        if tile.get_raw() & TILE_LOW_MASK != free_zone_center {
+                            let house = TileType::House.to_u16().unwrap()
+                                + value
+                                + city.rng.get_random(2) as u16;
+                            set_tile_raw(
+                                &mut city.map,
+                                &position,
+                                house | TILE_BURN_BULL_CONDUCT_BIT,
+                            )?;
+                        }
+                    }
+                }
+            }
+            set_zone_power(&mut city.map, &city.power, at);
+            return Ok(());
+        }
+
+        // remove one house
+        self.increase_rate_of_growth(at, -1);
+        let (house_low, house_high) = (
+            TileType::House.to_u16().unwrap(),
+            TileType::Hhthr.to_u16().unwrap(),
+        );
+        let mut z = 0;
+        for x in (at.get_x() - 1)..=(at.get_x() + 1) {
+            for y in (at.get_y() - 1)..=(at.get_y() + 1) {
+                let position: MapPosition = (x, y).into();
+                if let Some(tile) = city.map.get_tile_at(&position) {
+                    let tile_value = tile.get_raw() & TILE_LOW_MASK;
+                    if tile_value >= house_low && tile_value <= house_high {
+                        set_tile_raw(
+                            &mut city.map,
+                            &position,
+                            (ZONE_BORDER_OFFSETS[z] + free_zone_center - 4)
+                                | TILE_BURN_BULL_CONDUCT_BIT,
+                        )?;
+                        return Ok(());
+                    }
+                }
+                z += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Build a house on the best lot around a free residential zone.
+    fn build_house(city: &mut City, at: &MapPosition, value: u16) -> Result<(), MicropolisError> {
+        let (mut best_lot, mut best_score) = (0, 0);
+        for z in 1..9 {
+            let position = *at + (HOUSE_LOTS_DX[z], HOUSE_LOTS_DY[z]).into();
+            if !city.map.in_bounds(&position) {
+                continue;
+            }
+            let score = evaluate_lot(&city.map, &position)?;
+            if score != 0 {
+                if score > best_score {
+                    best_score = score;
+                    best_lot = z;
+                }
+                if score == best_score && city.rng.get_random_16() & 0x07 == 0x00 {
+                    best_lot = z;
+                }
+            }
+        }
+
+        if best_lot != 0 {
+            let position = *at + (HOUSE_LOTS_DX[best_lot], HOUSE_LOTS_DY[best_lot]).into();
+            let house =
+                TileType::House.to_u16().unwrap() + city.rng.get_random(2) as u16 + value * 3;
+            set_tile_raw(&mut city.map, &position, house | TILE_BURN_BULL_CONDUCT_BIT)?;
+        }
+        Ok(())
+    }
+
+    /// Place a residential zone of the given density and land value class.
+    fn residential_plop(
+        city: &mut City,
+        at: &MapPosition,
+        density: u16,
+        value: u16,
+    ) -> Result<bool, MicropolisError> {
+        let base =
+            ((value * 4) + density) * 9 + TileType::ResidentialZoneBase.to_u16().unwrap() - 4;
+        zone_plop(&mut city.map, &city.power, at, base)
+    }
+
+    /// Replace an empty residential zone by a hospital or a church, if needed.
+    fn make_hospital(&mut self, city: &mut City, at: &MapPosition) -> Result<(), MicropolisError> {
+        if self.census.get_need_hospital() > 0 {
+            zone_plop(
+                &mut city.map,
+                &city.power,
+                at,
+                TileType::Hospital.to_u16().unwrap() - 4,
+            )?;
+            self.census.set_need_hospital(0);
+            return Ok(());
+        }
+
+        if self.census.get_need_church() > 0 {
+            zone_plop(
+                &mut city.map,
+                &city.power,
+                at,
+                TileType::Church.to_u16().unwrap() - 4,
+            )?;
+            self.census.set_need_church(0);
+        }
+        Ok(())
+    }
+
+    /// Update a hospital or a church.
+    ///
+    /// They are replaced by residential zones when the city has too many of them.
+    fn do_hospital_church(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        tile_value: u16,
+    ) -> Result<(), MicropolisError> {
+        let (zone_center, too_many) = if tile_value == TileType::Hospital.to_u16().unwrap() {
+            self.statistics.hospital_count += 1;
+            (TileType::Hospital, self.census.get_need_hospital() == -1)
+        } else if tile_value == TileType::Church.to_u16().unwrap() {
+            self.statistics.church_count += 1;
+            (TileType::Church, self.census.get_need_church() == -1)
+        } else {
+            return Ok(());
+        };
+
+        if city.city_time & 0x0F == 0x00 {
+            self.repair_zone(&mut city.map, at, zone_center, 3)?;
+        }
+        if too_many && city.rng.get_random(20) == 0 {
+            zone_plop(
+                &mut city.map,
+                &city.power,
+                at,
+                TileType::ResidentialBase.to_u16().unwrap(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Update a commercial zone.
+    ///
+    /// See zone.cpp::doCommercial in the original codebase.
+    fn do_commercial(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        tile_value: u16,
+        is_zone_powered: bool,
+    ) -> Result<(), MicropolisError> {
+        let population = get_commercial_zone_population(tile_value);
+        city.population.add_commercial(population);
+
+        let traffic_good = if population as i16 > city.rng.get_random(5) {
+            // try driving from commercial to industrial
+            Self::make_traffic(city, at, &ZoneType::Industrial)?
+        } else {
+            1
+        };
+
+        if traffic_good == -1 {
+            let value = self.get_land_pollution_value(at);
+            return self.do_commercial_out(city, at, population, value);
+        }
+
+        if city.rng.get_random_16() & 0x07 == 0x00 {
+            let mut score = city.population.get_commercial_valve() as i32
+                + self.evaluate_commercial(at, traffic_good) as i32;
+            if !is_zone_powered {
+                score = -500;
+            }
+
+            if traffic_good != 0 && Self::should_zone_grow(city, score) {
+                let value = self.get_land_pollution_value(at);
+                return self.do_commercial_in(city, at, population, value);
+            }
+
+            if Self::should_zone_decline(city, score) {
+                let value = self.get_land_pollution_value(at);
+                return self.do_commercial_out(city, at, population, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate the desirability of a commercial zone, from its distance to the city center.
+    fn evaluate_commercial(&self, at: &MapPosition, traffic_good: i8) -> i16 {
+        if traffic_good < 0 {
+            return -3000;
+        }
+        *self.commercial_rate_map.get_tile_at(at).unwrap_or(&0)
+    }
+
+    /// Grow a commercial zone.
+    fn do_commercial_in(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        let land_value = *self.land_value_map.get_tile_at(at).unwrap_or(&0) as u16 >> 5;
+        if population > land_value {
+            return Ok(());
+        }
+
+        if population < 5 {
+            Self::commercial_plop(city, at, population, value)?;
+            self.increase_rate_of_growth(at, 8);
+        }
+        Ok(())
+    }
+
+    /// Shrink a commercial zone.
+    fn do_commercial_out(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        if population > 1 {
+            Self::commercial_plop(city, at, population - 2, value)?;
+            self.increase_rate_of_growth(at, -8);
+        } else if population == 1 {
+            zone_plop(
+                &mut city.map,
+                &city.power,
+                at,
+                TileType::CommercialBase.to_u16().unwrap(),
+            )?;
+            self.increase_rate_of_growth(at, -8);
+        }
+        Ok(())
+    }
+
+    /// Place a commercial zone of the given density and land value class.
+    fn commercial_plop(
+        city: &mut City,
+        at: &MapPosition,
+        density: u16,
+        value: u16,
+    ) -> Result<bool, MicropolisError> {
+        let base = ((value * 5) + density) * 9 + TileType::CommercialZoneBase.to_u16().unwrap() - 4;
+        zone_plop(&mut city.map, &city.power, at, base)
+    }
+
+    /// Update an industrial zone.
+    ///
+    /// See zone.cpp::doIndustrial in the original codebase.
+    fn do_industrial(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        tile_value: u16,
+        is_zone_powered: bool,
+    ) -> Result<(), MicropolisError> {
+        let population = get_industrial_zone_population(tile_value);
+        city.population.add_industrial(population);
+        // TODO: industrial smoke animation (setSmoke)
+
+        let traffic_good = if population as i16 > city.rng.get_random(5) {
+            // try driving from industrial to residential
+            Self::make_traffic(city, at, &ZoneType::Residential)?
+        } else {
+            1
+        };
+
+        if traffic_good == -1 {
+            let value = city.rng.get_random_16() as u16 & 0x01;
+            return self.do_industrial_out(city, at, population, value);
+        }
+
+        if city.rng.get_random_16() & 0x07 == 0x00 {
+            let mut score = city.population.get_industrial_valve() as i32
+                + Self::evaluate_industrial(traffic_good) as i32;
+            if !is_zone_powered {
+                score = -500;
+            }
+
+            if Self::should_zone_grow(city, score) {
+                let value = city.rng.get_random_16() as u16 & 0x01;
+                return self.do_industrial_in(city, at, population, value);
+            }
+
+            if Self::should_zone_decline(city, score) {
+                let value = city.rng.get_random_16() as u16 & 0x01;
+                return self.do_industrial_out(city, at, population, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate the desirability of an industrial zone.
+    fn evaluate_industrial(traffic_good: i8) -> i16 {
+        if traffic_good < 0 {
+            -1000
+        } else {
+            0
+        }
+    }
+
+    /// Grow an industrial zone.
+    fn do_industrial_in(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        if population < 4 {
+            Self::industrial_plop(city, at, population, value)?;
+            self.increase_rate_of_growth(at, 8);
+        }
+        Ok(())
+    }
+
+    /// Shrink an industrial zone.
+    fn do_industrial_out(
+        &mut self,
+        city: &mut City,
+        at: &MapPosition,
+        population: u16,
+        value: u16,
+    ) -> Result<(), MicropolisError> {
+        if population > 1 {
+            Self::industrial_plop(city, at, population - 2, value)?;
+            self.increase_rate_of_growth(at, -8);
+        } else if population == 1 {
+            zone_plop(
+                &mut city.map,
+                &city.power,
+                at,
+                TileType::IndustrialBase.to_u16().unwrap(),
+            )?;
+            self.increase_rate_of_growth(at, -8);
+        }
+        Ok(())
+    }
+
+    /// Place an industrial zone of the given density and land value class.
+    fn industrial_plop(
+        city: &mut City,
+        at: &MapPosition,
+        density: u16,
+        value: u16,
+    ) -> Result<bool, MicropolisError> {
+        let base = ((value * 4) + density) * 9 + TileType::IndustrialZoneBase.to_u16().unwrap() - 4;
+        zone_plop(&mut city.map, &city.power, at, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_free_population, get_residential_zone_population, zone_plop};
+    use crate::{
+        city::City,
+        game::GameScenario,
+        map::{tiles::TILE_LOW_MASK, MapPosition, TileType},
+    };
+
+    fn build_city(seed: i32) -> City {
+        let mut city = City::new("Zones".into(), GameScenario::None).unwrap();
+        city.rng.seed(seed);
+        city.sim.land_value_map.clear(100);
+        city.power.power_grid_map.clear(1);
+        city
+    }
+
+    fn get_tile_value(city: &City, at: &MapPosition) -> u16 {
+        city.map.get_tile_at(at).unwrap().get_raw() & TILE_LOW_MASK
+    }
+
+    #[test]
+    fn test_residential_zone_growth() {
+        let mut city = build_city(1234);
+        let at: MapPosition = (20, 20).into();
+        let residential_base = TileType::ResidentialBase.to_u16().unwrap();
+        assert!(zone_plop(&mut city.map, &city.power, &at, residential_base).unwrap());
+        assert_eq!(
+            get_tile_value(&city, &at),
+            TileType::FreeZoneCenter.to_u16().unwrap()
+        );
+        city.population.change_valves(2000, 0, 0);
+
+        let mut sim = city.sim.clone();
+        for _ in 0..200 {
+            sim.do_zone(&mut city, &at).unwrap();
+        }
+        assert!(count_free_population(&city.map, &at) > 0);
+        assert!(city.population.get_residential() > 0);
+        assert!(*sim.rate_of_growth.get_tile_at(&at).unwrap() > 0);
+
+        // an unpowered zone never grows
+        let mut city = build_city(1234);
+        city.power.power_grid_map.clear(0);
+        assert!(zone_plop(&mut city.map, &city.power, &at, residential_base).unwrap());
+        city.population.change_valves(2000, 0, 0);
+        let mut sim = city.sim.clone();
+        for _ in 0..200 {
+            sim.do_zone(&mut city, &at).unwrap();
+        }
+        assert_eq!(count_free_population(&city.map, &at), 0);
+    }
+
+    #[test]
+    fn test_residential_zone_decline_without_road() {
+        let mut city = build_city(42);
+        let at: MapPosition = (50, 50).into();
+        // fully developed zone (40 inhabitants), with no road around it
+        let base = TileType::ResidentialZoneBase.to_u16().unwrap() - 4 + 3 * 9;
+        assert!(zone_plop(&mut city.map, &city.power, &at, base).unwrap());
+        assert_eq!(
+            get_residential_zone_population(get_tile_value(&city, &at)),
+            40
+        );
+
+        let mut sim = city.sim.clone();
+        for _ in 0..50 {
+            sim.do_zone(&mut city, &at).unwrap();
+        }
+        assert!(get_tile_value(&city, &at) < base + 4);
+        assert!(*sim.rate_of_growth.get_tile_at(&at).unwrap() < 0);
+    }
+
+    #[test]
+    fn test_hospital_plop() {
+        let mut city = build_city(7);
+        let at: MapPosition = (30, 30).into();
+        let residential_base = TileType::ResidentialBase.to_u16().unwrap();
+        assert!(zone_plop(&mut city.map, &city.power, &at, residential_base).unwrap());
+
+        let mut sim = city.sim.clone();
+        sim.make_hospital(&mut city, &at).unwrap();
+        assert_eq!(
+            get_tile_value(&city, &at),
+            TileType::FreeZoneCenter.to_u16().unwrap()
+        );
+
+        sim.census.set_need_church(1);
+        sim.census.set_need_hospital(1);
+        sim.make_hospital(&mut city, &at).unwrap();
+        assert_eq!(
+            get_tile_value(&city, &at),
+            TileType::Hospital.to_u16().unwrap()
+        );
+        assert_eq!(sim.census.get_need_hospital(), 0);
+        assert_eq!(sim.census.get_need_church(), 1);
+
+        // hospitals are counted, and replaced by residential zones when too many
+        sim.census.set_need_hospital(-1);
+        sim.census.set_need_church(0);
+        for _ in 0..200 {
+            sim.do_zone(&mut city, &at).unwrap();
+        }
+        assert!(sim.statistics.hospital_count > 0);
+        assert_eq!(
+            get_tile_value(&city, &at),
+            TileType::FreeZoneCenter.to_u16().unwrap()
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use super::City;
+use crate::error::MicropolisError;
+
+/// Current version of the city snapshot format.
+///
+/// Must be incremented whenever the serialized state of a `City` changes:
+/// adding, removing, renaming or retyping a field of `City` or of any type it
+/// contains (map, overlays, power grids, treasury, sprites...), or changing
+/// the variants of one of their enums. Snapshots of any other version are
+/// rejected with `MicropolisError::UnsupportedVersion`.
+pub const CITY_SNAPSHOT_VERSION: u32 = 4;
+
+/// Versioned, serializable snapshot of the whole state of a city.
+///
+/// Captures the map, every simulation overlay map, the sprites, the
+/// simulation cycles and the RNG state, so that a restored city runs
+/// exactly like the original one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CitySnapshot {
+    /// Version of the snapshot format.
+    version: u32,
+    /// Captured city.
+    city: City,
+}
+
+impl CitySnapshot {
+    /// Capture the current state of the given city.
+    pub fn capture(city: &City) -> Self {
+        CitySnapshot {
+            version: CITY_SNAPSHOT_VERSION,
+            city: city.clone(),
+        }
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get_city(&self) -> &City {
+        &self.city
+    }
+
+    /// Restore the captured city.
+    ///
+    /// Fails if the snapshot was made with an incompatible format version.
+    pub fn restore(self) -> Result<City, MicropolisError> {
+        if self.version != CITY_SNAPSHOT_VERSION {
+            return Err(MicropolisError::UnsupportedVersion {
+                found: self.version,
+                expected: CITY_SNAPSHOT_VERSION,
+            });
+        }
+        Ok(self.city)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CitySnapshot, CITY_SNAPSHOT_VERSION};
+    use crate::{
+        city::{simulation::sprites::make_tornado, City},
+        engine::Engine,
+        error::MicropolisError,
+        game::GameScenario,
+        map::{MapPosition, Tile, TileType},
+    };
+
+    #[test]
+    fn test_city_snapshot_round_trip() {
+        let mut city = City::new("Snapshotville".into(), GameScenario::None).unwrap();
+        city.rng.seed(1234);
+        city.map
+            .set_tile_at(&(5, 5).into(), Tile::from_type(TileType::Fire).unwrap());
+        city.population
+            .get_density_map_mut()
+            .set_tile_at(&(5, 5).into(), 42);
+        city.traffic
+            .get_density_map_mut()
+            .set_tile_at(&(6, 6).into(), 200);
+        make_tornado(
+            &mut city.rng,
+            &mut city.sprites,
+            &mut city.events,
+            &city.map,
+            None,
+        )
+        .unwrap();
+
+        let snapshot = city.snapshot();
+        assert_eq!(snapshot.get_version(), CITY_SNAPSHOT_VERSION);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let mut restored = City::from_snapshot(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(restored, city);
+
+        // the RNG state is restored as well, so both cities keep running the same way
+        let bounds = city.map.bounds();
+        for _ in 0..100 {
+            assert_eq!(
+                MapPosition::new_random(&mut restored.rng, &bounds),
+                MapPosition::new_random(&mut city.rng, &bounds)
+            );
+        }
+        assert_eq!(restored, city);
+    }
+
+    #[test]
+    fn test_city_snapshot_keeps_running_the_same() {
+        let mut city = City::new("Snapshotville".into(), GameScenario::None).unwrap();
+        city.set_simulation_speed(3);
+        for x in 10..30 {
+            city.map
+                .set_tile_at(&(x, 20).into(), Tile::from_type(TileType::Fire).unwrap());
+        }
+        make_tornado(
+            &mut city.rng,
+            &mut city.sprites,
+            &mut city.events,
+            &city.map,
+            None,
+        )
+        .unwrap();
+        let mut original = Engine::with_seed(city, 1234);
+        for _ in 0..50 {
+            original.tick().unwrap();
+        }
+
+        // restore in the middle of the game, then run both cities side by side
+        let snapshot = original.get_city().snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let mut restored =
+            Engine::new(City::from_snapshot(serde_json::from_str(&json).unwrap()).unwrap());
+        for _ in 0..200 {
+            original.tick().unwrap();
+            restored.tick().unwrap();
+            assert_eq!(restored.get_city(), original.get_city());
+        }
+        assert_ne!(original.get_city(), snapshot.get_city());
+    }
+
+    #[test]
+    fn test_city_snapshot_version_mismatch() {
+        let city = City::new("Snapshotville".into(), GameScenario::None).unwrap();
+        let mut snapshot = CitySnapshot::capture(&city);
+        snapshot.version = CITY_SNAPSHOT_VERSION + 1;
+        assert!(matches!(
+            snapshot.restore(),
+            Err(MicropolisError::UnsupportedVersion { found, expected })
+                if found == CITY_SNAPSHOT_VERSION + 1 && expected == CITY_SNAPSHOT_VERSION
+        ));
+
+        // snapshots from before the last layout change are rejected as well
+        let mut snapshot = CitySnapshot::capture(&city);
+        snapshot.version = CITY_SNAPSHOT_VERSION - 1;
+        assert!(matches!(
+            snapshot.restore(),
+            Err(MicropolisError::UnsupportedVersion { found, expected })
+                if found == CITY_SNAPSHOT_VERSION - 1 && expected == CITY_SNAPSHOT_VERSION
+        ));
+    }
+}
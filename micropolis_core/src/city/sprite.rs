@@ -1,9 +1,28 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    map::{MapPosition, MapRectangle, TileMap, TileType, WORLD_HEIGHT, WORLD_WIDTH},
-    utils::random::MicropolisRandom,
+    error::MicropolisError,
+    map::{
+        tiles::{TILE_ANIM_BIT, TILE_BULL_BIT, TILE_BURN_BIT, TILE_LOW_MASK, TILE_ZONE_BIT},
+        tools::{apply_tool, EditingTool, ToolResult},
+        Map, MapPosition, MapRectangle, Tile, TileMap, TileType, WORLD_HEIGHT, WORLD_WIDTH,
+    },
+    messages::GameMessage,
+    utils::{
+        clamp,
+        hash::{StableHash, StableHasher},
+        random::MicropolisRandom,
+    },
 };
 
-use super::traffic::TrafficDensityMap;
+use super::{
+    disasters::CityDisasters,
+    events::{CityEvents, GameSound, SoundChannel},
+    traffic::TrafficDensityMap,
+    treasury::{CityTreasury, TransactionCategory},
+};
 
 const TRAIN_CX: [i32; 4] = [0, 16, 0, -16];
 const TRAIN_CY: [i32; 4] = [-16, 0, 16, 0];
@@ -14,7 +33,55 @@ const TRAIN_PIC2: [u32; 5] = [1, 2, 1, 2, 5];
 const HELI_CDX: [i32; 9] = [0, 0, 3, 5, 3, 0, -3, -5, -3];
 const HELI_CDY: [i32; 9] = [0, -5, -3, 0, 3, 5, 3, 0, -3];
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+const AIRPLANE_CDX: [i32; 12] = [0, 0, 6, 8, 6, 0, -6, -8, -6, 8, 8, 8];
+const AIRPLANE_CDY: [i32; 12] = [0, -8, -6, 0, 6, 8, 6, 0, -6, 0, 0, 0];
+
+const SHIP_DX: [i32; 9] = [0, 0, 1, 1, 1, 0, -1, -1, -1];
+const SHIP_DY: [i32; 9] = [0, -1, -1, 0, 1, 1, 1, 0, -1];
+const SHIP_PX: [i32; 9] = [0, 0, 2, 2, 2, 0, -2, -2, -2];
+const SHIP_PY: [i32; 9] = [0, -2, -2, 0, 2, 2, 2, 0, -2];
+/// Tiles a ship can sail through without crashing.
+const SHIP_CLEAR_TILES: [TileType; 8] = [
+    TileType::River,
+    TileType::Channel,
+    TileType::HorizontalPower,
+    TileType::VerticalPower,
+    TileType::UnderwaterHorizontalRail,
+    TileType::UnderwaterVerticalRail,
+    TileType::HorizontalBridgeOpened,
+    TileType::VerticalBridgeOpened,
+];
+
+const MONSTER_GX: [i32; 5] = [2, 2, -2, -2, 0];
+const MONSTER_GY: [i32; 5] = [-2, 2, 2, -2, 0];
+const MONSTER_ND1: [usize; 4] = [0, 1, 2, 3];
+const MONSTER_ND2: [usize; 4] = [1, 2, 3, 0];
+const MONSTER_NN1: [usize; 4] = [2, 5, 8, 11];
+const MONSTER_NN2: [usize; 4] = [11, 2, 5, 8];
+
+const TORNADO_CDX: [i32; 6] = [2, 3, 2, 0, -2, -3];
+const TORNADO_CDY: [i32; 6] = [-2, 0, 2, 3, 2, 0];
+
+/// Offsets (in pixels) of the fires started by an explosion.
+const EXPLOSION_FIRES: [(i32, i32); 5] = [(40, 16), (24, 0), (56, 0), (24, 32), (56, 32)];
+
+const BUS_DX: [i32; 5] = [0, 1, 0, -1, 0];
+const BUS_DY: [i32; 5] = [-1, 0, 1, 0, 0];
+const BUS_DIRECTION_FRAMES: [u32; 4] = [1, 2, 1, 2];
+/// Distance (in pixels) ahead of the bus at which the road is checked.
+const BUS_LOOK_AHEAD: i32 = 8;
+
+/// Tiles turning back into water when destroyed.
+const WET_TILES: [TileType; 6] = [
+    TileType::HorizontalPower,
+    TileType::VerticalPower,
+    TileType::UnderwaterHorizontalRail,
+    TileType::UnderwaterVerticalRail,
+    TileType::HorizontalBridgeOpened,
+    TileType::VerticalBridgeOpened,
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SpriteType {
     Train,
     Helicopter,
@@ -32,7 +99,7 @@ impl SpriteType {
         rng: &mut MicropolisRandom,
         sprite: &mut Sprite,
         max_pollution_at: Option<&MapPosition>,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         use SpriteType::*;
         match self {
             Train => {
@@ -74,7 +141,9 @@ impl SpriteType {
                 };
                 sprite.count = 1000;
                 sprite.destination = *max_pollution_at.ok_or_else(|| {
-                    "SpriteType::init_sprite: missing max_pollution_at for Monster".to_string()
+                    MicropolisError::InvalidArgument(
+                        "missing maximum pollution position for the monster".into(),
+                    )
                 })? << 4;
                 sprite.origin = sprite.position;
             }
@@ -129,35 +198,44 @@ impl SpriteType {
         Ok(())
     }
 
+    /// Move the given sprite by one step.
+    ///
+    /// The sprite must have been taken out of the `sprites` list beforehand.
     pub fn update_sprite(
         &self,
         rng: &mut MicropolisRandom,
-        map: &TileMap,
+        environment: &mut SpriteEnvironment,
         sprite: &mut Sprite,
-        sprite_cycle: u16,
-    ) -> Result<(), String> {
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
         use SpriteType::*;
         match self {
-            &Train => self.update_train(rng, map, sprite, sprite_cycle),
-            // &Helicopter => self.update_helicopter(rng, map, sprite, sprite_cycle),
-            _ => todo!(),
+            Train => self.update_train(rng, environment, sprite, sprites),
+            Helicopter => self.update_helicopter(rng, environment, sprite, sprites),
+            Airplane => self.update_airplane(rng, environment, sprite, sprites),
+            Ship => self.update_ship(rng, environment, sprite, sprites),
+            Monster => self.update_monster(rng, environment, sprite, sprites),
+            Tornado => self.update_tornado(rng, environment, sprite, sprites),
+            Explosion => self.update_explosion(rng, environment, sprite, sprites),
+            Bus => self.update_bus(rng, environment, sprite, sprites),
         }
     }
 
+    /// See `Micropolis::doTrainSprite` in the C++ code.
     fn update_train(
         &self,
         rng: &mut MicropolisRandom,
-        map: &TileMap,
+        environment: &mut SpriteEnvironment,
         sprite: &mut Sprite,
-        sprite_cycle: u16,
-    ) -> Result<(), String> {
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
         if sprite.frame == 3 || sprite.frame == 4 {
-            sprite.frame = *TRAIN_PIC2.get(sprite.direction as usize).unwrap();
+            sprite.frame = TRAIN_PIC2[sprite.direction];
         }
 
         sprite.position += MapPosition::new(TRAIN_DX[sprite.direction], TRAIN_DY[sprite.direction]);
 
-        if sprite_cycle & 0x03 != 0 {
+        if sprites.sprite_cycle & 0x03 != 0 {
             return Ok(());
         }
 
@@ -169,10 +247,10 @@ impl SpriteType {
             }
 
             let map_at = sprite.position + (TRAIN_CX[direction2] + 48, TRAIN_CY[direction2]).into();
-            let char = map.get_tile_char_at(&map_at).ok_or(format!(
-                "SpriteType.update_train: cannot get tile char at {}",
-                map_at
-            ))?;
+            let char = match Sprite::get_char(environment.map, &map_at) {
+                Some(char) => char,
+                None => continue,
+            };
             if (char >= TileType::UnderwaterHorizontalRail.to_u16().unwrap()
                 && char <= TileType::VerticalRailRoad.to_u16().unwrap())
                 || char == TileType::RailVerticalPowerHorizontal.to_u16().unwrap()
@@ -188,8 +266,9 @@ impl SpriteType {
                     TRAIN_PIC2[direction2]
                 };
 
-                if char == TileType::HorizontalRailRoad.to_u16().unwrap()
-                    || char == TileType::VerticalRailRoad.to_u16().unwrap()
+                // underwater rails: the train is not visible
+                if char == TileType::UnderwaterHorizontalRail.to_u16().unwrap()
+                    || char == TileType::UnderwaterVerticalRail.to_u16().unwrap()
                 {
                     sprite.frame = 5;
                 }
@@ -208,15 +287,14 @@ impl SpriteType {
         Ok(())
     }
 
+    /// See `Micropolis::doCopterSprite` in the C++ code.
     fn update_helicopter(
         &self,
         rng: &mut MicropolisRandom,
-        map: &TileMap,
+        environment: &mut SpriteEnvironment,
         sprite: &mut Sprite,
-        sprite_cycle: u16,
-        list: &ActiveSpritesList,
-        traffic_density_map: &TrafficDensityMap,
-    ) -> Result<(), String> {
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
         if sprite.sound_count > 0 {
             sprite.sound_count -= 1;
         }
@@ -227,22 +305,23 @@ impl SpriteType {
             }
             if sprite.count == 0 {
                 // attract copter to monster so that it blows up more often
-                if let Some(monster) = list.get_sprite(&SpriteType::Monster) {
+                if let Some(monster) = sprites.get_sprite(&SpriteType::Monster) {
                     sprite.destination = monster.position;
                 } else {
                     // attract copter to tornado so that it blows up more often
-                    if let Some(tornado) = list.get_sprite(&SpriteType::Tornado) {
+                    if let Some(tornado) = sprites.get_sprite(&SpriteType::Tornado) {
                         sprite.destination = tornado.position;
                     } else {
                         sprite.destination = sprite.origin;
                     }
                 }
-            }
 
-            // land
-            let (dist, _) = sprite.position.direction_towards(&sprite.origin);
-            if dist < 30 {
-                sprite.frame = 0;
+                // land
+                let (dist, _) = sprite.position.direction_towards(&sprite.origin);
+                if dist < 30 {
+                    sprite.frame = 0;
+                    return Ok(());
+                }
             }
         } else {
             let (dist, _) = sprite.position.direction_towards(&sprite.destination);
@@ -255,25 +334,31 @@ impl SpriteType {
         // send report
         if sprite.sound_count == 0 {
             let at = (sprite.position + (48, 0).into()) / 16;
-            if map.in_bounds(&at) {
+            if environment.map.in_bounds(&at) {
                 let chopper_position = at + (1, 1).into();
-                let traffic_density =
-                    *traffic_density_map
-                        .get_tile_at(&chopper_position)
-                        .ok_or(format!(
-                            "SpriteType::update_helicopter: cannot get traffic density at {}",
-                            chopper_position
-                        ))?;
+                let traffic_density = *environment
+                    .traffic_density_map
+                    .get_tile_at(&at)
+                    .ok_or(MicropolisError::OutOfBounds(at))?;
                 if traffic_density > 170 && rng.get_random_16() & 0x07 == 0 {
-                    // TODO: sendMessage(HEAVY_TRAFFIC, chopper_position, picture=true)
-                    // TODO: makeSound("city", "HeavyTraffic", chipper_position)
+                    environment.events.send_message(
+                        GameMessage::MessageHeavyTraffic,
+                        Some(chopper_position),
+                        true,
+                        false,
+                    );
+                    environment.events.make_sound(
+                        SoundChannel::City,
+                        GameSound::HeavyTraffic,
+                        Some(chopper_position),
+                    );
                     sprite.sound_count = 200;
                 }
             }
         }
 
         let mut z = sprite.frame as usize;
-        if sprite_cycle & 0x03 == 0x00 {
+        if sprites.sprite_cycle & 0x03 == 0x00 {
             let (_, dir) = sprite.position.direction_towards(&sprite.destination);
             z = Sprite::turn_towards(z, dir.to_usize().unwrap());
             sprite.frame = z as u32;
@@ -283,11 +368,684 @@ impl SpriteType {
 
         Ok(())
     }
+
+    /// See `Micropolis::doAirplaneSprite` in the C++ code.
+    fn update_airplane(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        let mut z = sprite.frame as usize;
+        if sprites.sprite_cycle.is_multiple_of(5) {
+            if z > 8 {
+                // take off
+                z -= 1;
+                if z < 9 {
+                    z = 3;
+                }
+            } else {
+                // go to destination
+                let (_, dir) = sprite.position.direction_towards(&sprite.destination);
+                z = Sprite::turn_towards(z, dir.to_usize().unwrap());
+            }
+            sprite.frame = z as u32;
+        }
+
+        // at destination
+        if sprite.position.distance_with(&sprite.destination) < 50 {
+            sprite.destination = (
+                rng.get_random(((WORLD_WIDTH as i16) << 4) + 100) as i32 - 50,
+                rng.get_random(((WORLD_HEIGHT as i16) << 4) + 100) as i32 - 50,
+            )
+                .into();
+        }
+
+        if environment.disasters_enabled
+            && sprites.explode_colliding(rng, environment.events, sprite, |other| {
+                other.kind == SpriteType::Helicopter || other.kind == SpriteType::Airplane
+            })?
+        {
+            let explosion_at = sprite.explode(environment.events);
+            CityDisasters::make_explosion_at(rng, sprites, &explosion_at)?;
+        }
+
+        sprite.position += (AIRPLANE_CDX[z], AIRPLANE_CDY[z]).into();
+        if !sprite.is_in_bounds(environment.map) {
+            sprite.frame = 0;
+        }
+
+        Ok(())
+    }
+
+    /// See `Micropolis::doShipSprite` in the C++ code.
+    fn update_ship(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        if sprite.sound_count > 0 {
+            sprite.sound_count -= 1;
+        }
+        if sprite.sound_count == 0 {
+            if rng.get_random_16() & 0x03 == 1 {
+                environment.events.make_sound(
+                    SoundChannel::City,
+                    GameSound::HonkHonkLow,
+                    Some(sprite.position >> 4),
+                );
+            }
+            sprite.sound_count = 200;
+        }
+
+        if sprite.count > 0 {
+            sprite.count -= 1;
+        }
+        let mut tile_value = TileType::River.to_u16().unwrap();
+        if sprite.count == 0 {
+            sprite.count = 9;
+            if sprite.frame as usize != sprite.new_direction {
+                sprite.frame =
+                    Sprite::turn_towards(sprite.frame as usize, sprite.new_direction) as u32;
+                return Ok(());
+            }
+
+            let start = (rng.get_random_16() & 0x07) as usize;
+            let mut found = false;
+            for p in start..(start + 8) {
+                let z = (p & 0x07) + 1;
+                if z == sprite.direction {
+                    continue;
+                }
+                let at = MapPosition::new(
+                    ((sprite.position.get_x() + (48 - 1)) >> 4) + SHIP_DX[z],
+                    (sprite.position.get_y() >> 4) + SHIP_DY[z],
+                );
+                if let Some(value) = environment.map.get_tile_char_at(&at) {
+                    tile_value = value;
+                    if value == TileType::Channel.to_u16().unwrap()
+                        || value == TileType::HorizontalBridgeOpened.to_u16().unwrap()
+                        || value == TileType::VerticalBridgeOpened.to_u16().unwrap()
+                        || Self::can_ship_turn_under(value, sprite.direction, z)
+                    {
+                        sprite.new_direction = z;
+                        sprite.frame =
+                            Sprite::turn_towards(sprite.frame as usize, sprite.new_direction)
+                                as u32;
+                        sprite.direction = z + 4;
+                        if sprite.direction > 8 {
+                            sprite.direction -= 8;
+                        }
+                        found = true;
+                        break;
+                    }
+                }
+            }
+            if !found {
+                sprite.direction = 10;
+                sprite.new_direction = (rng.get_random_16() & 0x07) as usize + 1;
+            }
+        } else {
+            let z = sprite.frame as usize;
+            if z == sprite.new_direction {
+                sprite.position += (SHIP_PX[z], SHIP_PY[z]).into();
+            }
+        }
+
+        if !sprite.is_in_bounds(environment.map) {
+            sprite.frame = 0;
+            return Ok(());
+        }
+
+        if !SHIP_CLEAR_TILES
+            .iter()
+            .any(|clear| clear.to_u16().unwrap() == tile_value)
+        {
+            let explosion_at = sprite.explode(environment.events);
+            CityDisasters::make_explosion_at(rng, sprites, &explosion_at)?;
+            Self::destroy_map_tile(
+                rng,
+                environment,
+                sprites,
+                &(sprite.position + (48, 0).into()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Can a ship make a U-turn at the given tile?
+    ///
+    /// See `Micropolis::tryOther` in the C++ code.
+    fn can_ship_turn_under(tile_value: u16, old_direction: usize, new_direction: usize) -> bool {
+        let mut z = old_direction + 4;
+        if z > 8 {
+            z -= 8;
+        }
+        new_direction == z
+            && (tile_value == TileType::HorizontalPower.to_u16().unwrap()
+                || tile_value == TileType::VerticalPower.to_u16().unwrap()
+                || tile_value == TileType::UnderwaterHorizontalRail.to_u16().unwrap()
+                || tile_value == TileType::UnderwaterVerticalRail.to_u16().unwrap())
+    }
+
+    /// See `Micropolis::doMonsterSprite` in the C++ code.
+    fn update_monster(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        if sprite.sound_count > 0 {
+            sprite.sound_count -= 1;
+        }
+
+        // d: walking direction (4 when turning), z: step of the animation
+        let (d, z) = if sprite.control < 0 {
+            let d = (sprite.frame as usize - 1) / 3;
+            if d < 4 {
+                // business as usual
+                let mut d = d;
+                let mut z = Self::monster_walk_step(sprite);
+                let (dist, dir) = sprite.position.direction_towards(&sprite.destination);
+                if dist < 60 {
+                    if sprite.flag == 0 {
+                        sprite.flag = 1;
+                        sprite.destination = sprite.origin;
+                    } else {
+                        sprite.frame = 0;
+                        return Ok(());
+                    }
+                }
+                let c = (dir.to_usize().unwrap() - 1) / 2;
+                if c != d && rng.get_random(10) == 0 {
+                    z = if rng.get_random_16() & 0x01 != 0 {
+                        MONSTER_ND1[d]
+                    } else {
+                        MONSTER_ND2[d]
+                    };
+                    d = 4;
+                    if sprite.sound_count == 0 {
+                        environment.events.make_sound(
+                            SoundChannel::City,
+                            GameSound::Monster,
+                            Some(sprite.position >> 4),
+                        );
+                        sprite.sound_count = 50 + rng.get_random(100) as u32;
+                    }
+                }
+                (d, z)
+            } else {
+                // turning
+                let z = (sprite.frame as usize - 13) & 0x03;
+                if rng.get_random_16() & 0x03 == 0 {
+                    let z = if rng.get_random_16() & 0x01 != 0 {
+                        MONSTER_NN1[z]
+                    } else {
+                        MONSTER_NN2[z]
+                    };
+                    ((z - 1) / 3, (z - 1) % 3)
+                } else {
+                    (4, z)
+                }
+            }
+        } else {
+            // somebody has taken control of the monster
+            (
+                (sprite.control as usize).min(4),
+                Self::monster_walk_step(sprite),
+            )
+        };
+
+        sprite.frame = ((d * 3) + z + 1).min(16) as u32;
+        sprite.position += (MONSTER_GX[d], MONSTER_GY[d]).into();
+
+        if sprite.count > 0 {
+            sprite.count -= 1;
+        }
+        let drowned =
+            match Sprite::get_char(environment.map, &(sprite.position + sprite.hot_offset)) {
+                Some(char) => {
+                    char == TileType::River.to_u16().unwrap()
+                        && sprite.count != 0
+                        && sprite.control == -1
+                }
+                None => true,
+            };
+        if drowned {
+            sprite.frame = 0;
+        }
+
+        sprites.explode_colliding(rng, environment.events, sprite, Sprite::is_vehicle)?;
+
+        Self::destroy_map_tile(
+            rng,
+            environment,
+            sprites,
+            &(sprite.position + (48, 16).into()),
+        )
+    }
+
+    /// Advance the monster in its walking animation cycle.
+    fn monster_walk_step(sprite: &mut Sprite) -> usize {
+        let z = (sprite.frame as usize - 1) % 3;
+        if z == 2 {
+            sprite.step = 0;
+        }
+        if z == 0 {
+            sprite.step = 1;
+        }
+        if sprite.step != 0 {
+            z + 1
+        } else {
+            z - 1
+        }
+    }
+
+    /// See `Micropolis::doTornadoSprite` in the C++ code.
+    fn update_tornado(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        sprite.frame = if sprite.frame == 2 {
+            if sprite.flag != 0 {
+                3
+            } else {
+                1
+            }
+        } else {
+            sprite.flag = if sprite.frame == 1 { 1 } else { 0 };
+            2
+        };
+
+        if sprite.count > 0 {
+            sprite.count -= 1;
+        }
+
+        sprites.explode_colliding(rng, environment.events, sprite, Sprite::is_vehicle)?;
+
+        let z = rng.get_random(5) as usize;
+        sprite.position += (TORNADO_CDX[z], TORNADO_CDY[z]).into();
+
+        if !sprite.is_in_bounds(environment.map) {
+            sprite.frame = 0;
+        }
+        if sprite.count != 0 && rng.get_random(500) == 0 {
+            sprite.frame = 0;
+        }
+
+        Self::destroy_map_tile(
+            rng,
+            environment,
+            sprites,
+            &(sprite.position + (48, 40).into()),
+        )
+    }
+
+    /// See `Micropolis::doExplosionSprite` in the C++ code.
+    fn update_explosion(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        if sprites.sprite_cycle & 0x01 == 0x00 {
+            if sprite.frame == 1 {
+                environment.events.make_sound(
+                    SoundChannel::City,
+                    GameSound::ExplosionHigh,
+                    Some((sprite.position + sprite.hot_offset) >> 4),
+                );
+                environment.events.send_message(
+                    GameMessage::MessageExplosionReported,
+                    Some((sprite.position >> 4) + (3, 0).into()),
+                    false,
+                    false,
+                );
+            }
+            sprite.frame += 1;
+        }
+
+        if sprite.frame > 6 {
+            sprite.frame = 0;
+            for offset in EXPLOSION_FIRES.iter() {
+                Self::start_fire(rng, environment.map, &(sprite.position + (*offset).into()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// See `Micropolis::doBusSprite` in the C++ code.
+    fn update_bus(
+        &self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprite: &mut Sprite,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        let mut turned = false;
+        if sprite.turn != 0 {
+            let is_vertical = sprite.direction & 0x01 != 0;
+            if sprite.turn < 0 {
+                // counter-clockwise
+                sprite.frame = if is_vertical { 4 } else { 3 };
+                sprite.turn += 1;
+                sprite.direction = (sprite.direction + 3) & 0x03;
+            } else {
+                // clockwise
+                sprite.frame = if is_vertical { 3 } else { 4 };
+                sprite.turn -= 1;
+                sprite.direction = (sprite.direction + 1) & 0x03;
+            }
+            turned = true;
+        } else if sprite.frame == 3 || sprite.frame == 4 {
+            // finish turn
+            turned = true;
+            sprite.frame = BUS_DIRECTION_FRAMES[sprite.direction];
+        }
+
+        let direction = sprite.direction;
+        let hot_spot = sprite.position + sprite.hot_offset;
+        let (mut dx, mut dy, mut speed) = (0, 0, 0);
+        if sprite.speed != 0 {
+            // cruise at traffic speed
+            let hot_tile = hot_spot >> 4;
+            let traffic = if environment.map.in_bounds(&hot_tile) {
+                environment
+                    .traffic_density_map
+                    .get_tile_at(&hot_tile)
+                    .map_or(0, |density| density >> 6)
+            } else {
+                0
+            };
+            speed = match traffic {
+                0 => 8,
+                1 | 2 => 4,
+                _ => 1,
+            }
+            .min(sprite.speed as i32);
+
+            if turned {
+                speed = speed.min(1);
+                dx = BUS_DX[direction] * speed;
+                dy = BUS_DY[direction] * speed;
+            } else {
+                dx = BUS_DX[direction] * speed;
+                dy = BUS_DY[direction] * speed;
+
+                // drift into the right lane
+                let drift = |z: i32, current: i32| if z != 0 { z.signum() } else { current };
+                match direction {
+                    0 => dx = drift((hot_tile.get_x() << 4) + 4 - hot_spot.get_x(), dx),
+                    1 => dy = drift((hot_tile.get_y() << 4) + 4 - hot_spot.get_y(), dy),
+                    2 => dx = drift((hot_tile.get_x() << 4) - hot_spot.get_x(), dx),
+                    _ => dy = drift((hot_tile.get_y() << 4) - hot_spot.get_y(), dy),
+                }
+            }
+        }
+
+        let bounds = environment.map.bounds();
+        let ahead_tile = |at: MapPosition| {
+            MapPosition::new(
+                clamp(at.get_x() >> 4, 0, bounds.get_width() as i32 - 1),
+                clamp(at.get_y() >> 4, 0, bounds.get_height() as i32 - 1),
+            )
+        };
+        let ahead = hot_spot
+            + (
+                BUS_DX[direction] * BUS_LOOK_AHEAD,
+                BUS_DY[direction] * BUS_LOOK_AHEAD,
+            )
+                .into();
+        let next_tile = ahead_tile(ahead + (dx, dy).into());
+        if next_tile != ahead_tile(ahead) {
+            match Self::can_drive_on(environment.map, &next_tile) {
+                // can't drive forward into a new tile: fast buses bulldoze their way through
+                0 if speed == 8 => Self::bulldoze_for_bus(rng, environment, &next_tile)?,
+                // bumpy
+                -1 => {
+                    dx /= 2;
+                    dy /= 2;
+                }
+                _ => {}
+            }
+        }
+
+        sprite.position += (dx, dy).into();
+
+        if environment.disasters_enabled
+            && sprites.explode_colliding(rng, environment.events, sprite, |other| {
+                other.kind == SpriteType::Bus
+                    || (other.kind == SpriteType::Train && other.frame != 5)
+            })?
+        {
+            let explosion_at = sprite.explode(environment.events);
+            CityDisasters::make_explosion_at(rng, sprites, &explosion_at)?;
+        }
+
+        Ok(())
+    }
+
+    /// Can a bus drive on the tile at the given position?
+    ///
+    /// Returns 1 for a road or a railroad, -1 for bumpy terrain and 0 when the
+    /// way is blocked.
+    ///
+    /// See `Micropolis::canDriveOn` in the C++ code.
+    fn can_drive_on(map: &TileMap, position: &MapPosition) -> i8 {
+        let tile = match map.get_tile_char_at(position) {
+            Some(tile) => tile,
+            None => return 0,
+        };
+        let raw = |tile_type: TileType| tile_type.to_u16().unwrap();
+        let is_road = tile >= raw(TileType::HorizontalBridge)
+            && tile <= raw(TileType::LastRoad)
+            && tile != raw(TileType::HorizontalBridgeOpened)
+            && tile != raw(TileType::VerticalBridgeOpened);
+        if is_road
+            || tile == raw(TileType::HorizontalRailRoad)
+            || tile == raw(TileType::VerticalRailRoad)
+        {
+            return 1;
+        }
+        // river edges, trees and rubble, power lines and explosions (`tally` in the C++ code)
+        let is_bumpy = (tile >= raw(TileType::FirstRiverEdge) && tile <= raw(TileType::LastRubble))
+            || (tile >= raw(TileType::LhPower)
+                && tile <= raw(TileType::RailHorizontalPowerVertical))
+            || (tile >= raw(TileType::TINYEXP) && tile <= raw(TileType::LASTTINYEXP) + 2);
+        if tile == raw(TileType::Dirt) || is_bumpy {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// Bulldoze the tile blocking a bus, charging the city for it.
+    ///
+    /// See `Micropolis::bulldozerTool` in the C++ code.
+    fn bulldoze_for_bus(
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        position: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        let result = apply_tool(
+            rng,
+            environment.map,
+            position,
+            &EditingTool::Bulldozer,
+            false,
+            true,
+            environment.treasury.get_balance(),
+            environment.events,
+        )?;
+        if let ToolResult::Succeeded(effects) = result {
+            environment.treasury.spend(
+                environment.city_time,
+                TransactionCategory::Construction,
+                effects.get_cost(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Start a fire at the given position in **pixels**, if the tile there can burn.
+    ///
+    /// See `Micropolis::startFire` in the C++ code.
+    fn start_fire(
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        let position = *at >> 4;
+        if let Some(raw) = map.get_tile_at(&position).map(Tile::get_raw) {
+            let can_burn = raw & TILE_BURN_BIT == TILE_BURN_BIT
+                || raw & TILE_LOW_MASK == TileType::Dirt.to_u16().unwrap();
+            if can_burn && raw & TILE_ZONE_BIT == 0x00 {
+                map.set_tile_at(&position, Tile::from_raw(CityDisasters::random_fire(rng))?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Destroy the map tile at the given position in **pixels**.
+    ///
+    /// See `Micropolis::destroyMapTile` in the C++ code.
+    fn destroy_map_tile(
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+        sprites: &mut ActiveSpritesList,
+        at: &MapPosition,
+    ) -> Result<(), MicropolisError> {
+        let position = *at >> 4;
+        let raw = match environment.map.get_tile_at(&position) {
+            Some(tile) => tile.get_raw(),
+            None => return Ok(()),
+        };
+        let tile_value = raw & TILE_LOW_MASK;
+        if tile_value < TileType::TreeBase.to_u16().unwrap() {
+            return Ok(());
+        }
+
+        if raw & TILE_BURN_BIT == 0x00 {
+            // bridges fall into the water
+            if tile_value >= TileType::HorizontalBridge.to_u16().unwrap()
+                && tile_value <= TileType::LastRoad.to_u16().unwrap()
+            {
+                environment
+                    .map
+                    .set_tile_at(&position, Tile::from_type(TileType::River)?);
+            }
+            return Ok(());
+        }
+
+        if raw & TILE_ZONE_BIT == TILE_ZONE_BIT {
+            Self::start_fire_in_zone(environment, &position, tile_value);
+            if tile_value > TileType::ResidentialZoneBase.to_u16().unwrap() {
+                CityDisasters::make_explosion_at(rng, sprites, at)?;
+            }
+        }
+
+        let is_wet = WET_TILES
+            .iter()
+            .any(|wet| wet.to_u16().unwrap() == tile_value);
+        let destroyed = if is_wet {
+            TileType::River.to_u16().unwrap()
+        } else {
+            TileType::TINYEXP.to_u16().unwrap() | TILE_BULL_BIT | TILE_ANIM_BIT
+        };
+        environment
+            .map
+            .set_tile_at(&position, Tile::from_raw(destroyed)?);
+        Ok(())
+    }
+
+    /// Set the whole zone with the given center on fire.
+    ///
+    /// See `Micropolis::startFireInZone` in the C++ code.
+    fn start_fire_in_zone(
+        environment: &mut SpriteEnvironment,
+        center: &MapPosition,
+        center_value: u16,
+    ) {
+        if let Some(&rate) = environment.rate_of_growth_map.get_tile_at(center) {
+            environment
+                .rate_of_growth_map
+                .set_tile_at(center, clamp(rate - 20, -200, 200));
+        }
+
+        let size = if center_value < TileType::PortBase.to_u16().unwrap() {
+            2
+        } else if center_value == TileType::Airport.to_u16().unwrap() {
+            5
+        } else {
+            4
+        };
+        for x in -1..size {
+            for y in -1..size {
+                if let Some(tile) = environment.map.get_tile_mut_at(&(*center + (x, y).into())) {
+                    let raw = tile.get_raw();
+                    if raw & TILE_LOW_MASK >= TileType::HorizontalBridge.to_u16().unwrap() {
+                        tile.set_raw(raw | TILE_BULL_BIT);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parts of the city the sprites interact with while moving.
+pub struct SpriteEnvironment<'a> {
+    pub map: &'a mut TileMap,
+    pub events: &'a mut CityEvents,
+    pub traffic_density_map: &'a TrafficDensityMap,
+    pub rate_of_growth_map: &'a mut Map<i16>,
+    /// Funds charged when a bus bulldozes its way through.
+    pub treasury: &'a mut CityTreasury,
+    /// Current city time, to date the transactions.
+    pub city_time: u32,
+    /// Can the sprites crash into each other?
+    pub disasters_enabled: bool,
+}
+
+/// Identifier of a sprite, stable for as long as the sprite exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SpriteId(u32);
+
+/// Handle of a follower (e.g. a camera) attached to a sprite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SpriteFollowHandle(u32);
+
+/// Read-only view of an active sprite, for the front-ends to draw it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteView {
+    pub id: SpriteId,
+    pub kind: SpriteType,
+    /// Top-left corner of the sprite image, in world **pixels**.
+    pub position: MapPosition,
+    /// Size of the sprite image, in **pixels**.
+    pub size: MapRectangle,
+    /// Position of the hot-spot (e.g. for a camera to center on), in world **pixels**.
+    pub hot_spot: MapPosition,
+    /// Animation frame to draw, starting at 1.
+    pub frame: u32,
+    /// Current direction, whose meaning depends on the sprite type.
+    pub direction: usize,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 /// A Sprite is a moveable, animatable entity on the map.
 pub struct Sprite {
+    /// Identifier of the sprite, unique in its `ActiveSpritesList`.
+    pub(crate) id: SpriteId,
     /// Entity type of the sprite.
     pub(crate) kind: SpriteType,
     /// Name of the sprite.
@@ -306,22 +1064,22 @@ pub struct Sprite {
     pub(crate) origin: MapPosition,
     /// Destination.
     pub(crate) destination: MapPosition,
-    /// TODO: ?
+    /// General purpose count-down (e.g. remaining lifetime, or ticks before the next turn).
     pub(crate) count: u32,
-    /// TODO: ?
+    /// Number of ticks before the sprite can make a sound again.
     pub(crate) sound_count: u32,
     /// Direction.
     pub(crate) direction: usize,
     /// New direction.
     pub(crate) new_direction: usize,
-    /// TODO: ?
+    /// Direction of the monster walking animation (1 forward, 0 backward).
     pub(crate) step: u32,
-    /// TODO: ?
+    /// Sprite specific state (returning monster, tornado animation).
     pub(crate) flag: u32,
-    /// TODO: ?
+    /// Direction imposed by the player, or -1 when the sprite moves on its own.
     pub(crate) control: i32,
-    /// TODO: ?
-    pub(crate) turn: u32,
+    /// Number of quarter turns left to make (negative for counter-clockwise turns).
+    pub(crate) turn: i32,
     /// Speed.
     pub(crate) speed: u32,
     /// Acceleration.
@@ -335,8 +1093,9 @@ impl Sprite {
         kind: &SpriteType,
         position: MapPosition,
         max_pollution_at: Option<&MapPosition>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, MicropolisError> {
         let mut sprite = Self {
+            id: SpriteId(0),
             kind: kind.clone(),
             name,
             frame: 0,
@@ -361,9 +1120,34 @@ impl Sprite {
         Ok(sprite)
     }
 
+    pub fn get_id(&self) -> SpriteId {
+        self.id
+    }
+
+    pub fn get_kind(&self) -> &SpriteType {
+        &self.kind
+    }
+
+    /// Build the read-only view of the sprite.
+    pub fn view(&self) -> SpriteView {
+        SpriteView {
+            id: self.id,
+            kind: self.kind.clone(),
+            position: self.position + self.offset,
+            size: self.size.clone(),
+            hot_spot: self.position + self.hot_offset,
+            frame: self.frame,
+            direction: self.direction,
+        }
+    }
+
     pub fn is_in_bounds(&self, map: &TileMap) -> bool {
+        let bounds = map.bounds();
         let (x, y) = (self.position + self.hot_offset).as_tuple();
-        x >= 0 && y >= 0 && x < ((WORLD_WIDTH as i32) << 4) && y < ((WORLD_HEIGHT as i32) << 4)
+        x >= 0
+            && y >= 0
+            && x < ((bounds.get_width() as i32) << 4)
+            && y < ((bounds.get_height() as i32) << 4)
     }
 
     pub fn collides_with(&self, other: &Self) -> bool {
@@ -373,33 +1157,74 @@ impl Sprite {
                 < 30
     }
 
+    /// Turn by one eighth from the present direction towards the destination direction.
+    ///
+    /// Directions go from 1 (north) to 8 (north-west), clockwise.
     pub fn turn_towards(present_direction: usize, destination_direction: usize) -> usize {
-        match (present_direction, destination_direction) {
-            (p, d) if p == d => p,
-            (p, d) if p < d => {
-                if (d - p) < 4 {
-                    p + 1
-                } else {
-                    p - 1
-                }
-            }
-            (p, d) if p >= d => {
-                if (p - d) < 4 {
-                    p - 1
-                } else {
-                    p + 1
-                }
-            }
-            (p, _) if p > 8 => 1,
-            (p, _) if p < 1 => 8,
-            (p, _) => p,
+        let (p, d) = (present_direction, destination_direction);
+        let turned = match p {
+            p if p == d => return p,
+            p if p < d && d - p < 4 => p + 1,
+            p if p < d => p - 1,
+            p if p - d < 4 => p - 1,
+            p => p + 1,
+        };
+        match turned {
+            t if t > 8 => 1,
+            t if t < 1 => 8,
+            t => t,
         }
     }
+
+    /// Is the sprite a vehicle that a monster or a tornado can destroy?
+    pub fn is_vehicle(&self) -> bool {
+        matches!(
+            self.kind,
+            SpriteType::Airplane | SpriteType::Helicopter | SpriteType::Ship | SpriteType::Train
+        )
+    }
+
+    /// Value of the tile at the given position in **pixels**, if in bounds.
+    ///
+    /// See `Micropolis::getChar` in the C++ code.
+    fn get_char(map: &TileMap, at: &MapPosition) -> Option<u16> {
+        map.get_tile_char_at(&(*at >> 4))
+    }
+
+    /// Blow up the sprite and report the crash.
+    ///
+    /// Returns the position (in pixels) of the explosion to make.
+    ///
+    /// See `Micropolis::explodeSprite` in the C++ code.
+    fn explode(&mut self, events: &mut CityEvents) -> MapPosition {
+        self.frame = 0;
+        let explosion_at = self.position + self.hot_offset;
+        let at = explosion_at >> 4;
+        let message = match self.kind {
+            SpriteType::Airplane => Some(GameMessage::MessagePlaneCrashed),
+            SpriteType::Ship => Some(GameMessage::MessageShipCrashed),
+            SpriteType::Train | SpriteType::Bus => Some(GameMessage::MessageTrainCrashed),
+            SpriteType::Helicopter => Some(GameMessage::MessageHelicopterCrashed),
+            _ => None,
+        };
+        if let Some(message) = message {
+            events.send_message(message, Some(at), true, false);
+        }
+        events.make_sound(SoundChannel::City, GameSound::ExplosionHigh, Some(at));
+        explosion_at
+    }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ActiveSpritesList {
     sprite_cycle: u16,
     pool: Vec<Sprite>,
+    /// Identifier of the next sprite to add.
+    next_id: u32,
+    /// Sprite followed by each follower.
+    followers: BTreeMap<SpriteFollowHandle, SpriteId>,
+    /// Handle of the next follower.
+    next_follow_handle: u32,
 }
 
 impl ActiveSpritesList {
@@ -407,12 +1232,56 @@ impl ActiveSpritesList {
         Self {
             sprite_cycle: 0,
             pool: vec![],
+            next_id: 1,
+            followers: BTreeMap::new(),
+            next_follow_handle: 1,
         }
     }
 
-    /// Add a new sprite to the pool.
-    pub fn add_sprite(&mut self, sprite: Sprite) {
+    /// Add a new sprite to the pool, returning its identifier.
+    pub fn add_sprite(&mut self, mut sprite: Sprite) -> SpriteId {
+        sprite.id = SpriteId(self.next_id);
+        self.next_id += 1;
         self.pool.push(sprite);
+        SpriteId(self.next_id - 1)
+    }
+
+    /// List the views of all the active sprites.
+    pub fn get_views(&self) -> Vec<SpriteView> {
+        self.pool
+            .iter()
+            .filter(|s| s.frame != 0)
+            .map(Sprite::view)
+            .collect()
+    }
+
+    /// Returns the sprite with the given identifier, if it still exists.
+    pub fn get_sprite_by_id(&self, id: SpriteId) -> Option<&Sprite> {
+        self.pool.iter().find(|s| s.id == id)
+    }
+
+    /// Start following the sprite with the given identifier.
+    ///
+    /// Returns None if there is no such active sprite.
+    pub fn follow_sprite(&mut self, id: SpriteId) -> Option<SpriteFollowHandle> {
+        self.get_sprite_by_id(id).filter(|s| s.frame != 0)?;
+        let handle = SpriteFollowHandle(self.next_follow_handle);
+        self.next_follow_handle += 1;
+        self.followers.insert(handle, id);
+        Some(handle)
+    }
+
+    /// Stop following a sprite.
+    pub fn unfollow_sprite(&mut self, handle: SpriteFollowHandle) {
+        self.followers.remove(&handle);
+    }
+
+    /// Returns the view of the sprite followed with the given handle.
+    ///
+    /// Returns None once the sprite has been destroyed.
+    pub fn get_followed_sprite(&self, handle: SpriteFollowHandle) -> Option<SpriteView> {
+        let id = self.followers.get(&handle)?;
+        self.get_sprite_by_id(*id).map(Sprite::view)
     }
 
     /// Returns the sprite of the given type, if available and active.
@@ -434,9 +1303,324 @@ impl ActiveSpritesList {
         }
     }
 
-    /// Destroy the sprite by taking it out of the active list.
-    /// TODO: break the connection between any views that are following this sprite.
-    pub fn destroy_sprite(&mut self, sprite: &Sprite) {
-        self.pool.retain(|s| s != sprite);
+    /// Move all the active sprites by one step, and drop the de-activated anonymous ones.
+    ///
+    /// See `Micropolis::moveObjects` in the C++ code.
+    pub fn move_sprites(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        environment: &mut SpriteEnvironment,
+    ) -> Result<(), MicropolisError> {
+        self.sprite_cycle = self.sprite_cycle.wrapping_add(1);
+
+        // sprites created during this step (e.g. explosions) only move on the next one
+        let mut remaining = self.pool.len();
+        let mut i = 0;
+        while remaining > 0 {
+            remaining -= 1;
+            if self.pool[i].frame == 0 {
+                if self.pool[i].name.is_empty() {
+                    let id = self.pool[i].id;
+                    self.destroy_sprite(id);
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+
+            // take the sprite out of the pool while it interacts with the other ones
+            let mut sprite = self.pool.remove(i);
+            let kind = sprite.kind.clone();
+            let result = kind.update_sprite(rng, environment, &mut sprite, self);
+            self.pool.insert(i, sprite);
+            result?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Blow up the active sprite of the given type, if any.
+    ///
+    /// Returns true if a sprite exploded.
+    pub fn explode_sprite(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        events: &mut CityEvents,
+        kind: &SpriteType,
+    ) -> Result<bool, MicropolisError> {
+        let explosion_at = match self.get_sprite_mut(kind) {
+            Some(sprite) => sprite.explode(events),
+            None => return Ok(false),
+        };
+        CityDisasters::make_explosion_at(rng, self, &explosion_at)?;
+        Ok(true)
+    }
+
+    /// Blow up the active sprites colliding with the given one and accepted by the filter.
+    ///
+    /// Returns true if any sprite exploded.
+    fn explode_colliding<F>(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        events: &mut CityEvents,
+        sprite: &Sprite,
+        filter: F,
+    ) -> Result<bool, MicropolisError>
+    where
+        F: Fn(&Sprite) -> bool,
+    {
+        let mut exploded = false;
+        for i in 0..self.pool.len() {
+            if filter(&self.pool[i]) && sprite.collides_with(&self.pool[i]) {
+                let explosion_at = self.pool[i].explode(events);
+                CityDisasters::make_explosion_at(rng, self, &explosion_at)?;
+                exploded = true;
+            }
+        }
+        Ok(exploded)
+    }
+
+    /// Destroy the sprite by taking it out of the active list, and release its followers.
+    pub fn destroy_sprite(&mut self, id: SpriteId) {
+        self.pool.retain(|s| s.id != id);
+        self.followers.retain(|_, followed| *followed != id);
+    }
+}
+
+impl StableHash for SpriteType {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(self.clone() as u8);
+    }
+}
+
+impl StableHash for SpriteId {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(self.0);
+    }
+}
+
+impl StableHash for SpriteFollowHandle {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(self.0);
+    }
+}
+
+impl StableHash for Sprite {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.id.stable_hash(hasher);
+        self.kind.stable_hash(hasher);
+        self.name.stable_hash(hasher);
+        hasher.write_u32(self.frame);
+        self.position.stable_hash(hasher);
+        self.size.stable_hash(hasher);
+        self.offset.stable_hash(hasher);
+        self.hot_offset.stable_hash(hasher);
+        self.origin.stable_hash(hasher);
+        self.destination.stable_hash(hasher);
+        hasher.write_u32(self.count);
+        hasher.write_u32(self.sound_count);
+        hasher.write_usize(self.direction);
+        hasher.write_usize(self.new_direction);
+        hasher.write_u32(self.step);
+        hasher.write_u32(self.flag);
+        hasher.write_i32(self.control);
+        hasher.write_i32(self.turn);
+        hasher.write_u32(self.speed);
+        hasher.write_u32(self.acceleration);
+    }
+}
+
+impl StableHash for ActiveSpritesList {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u16(self.sprite_cycle);
+        self.pool.stable_hash(hasher);
+        hasher.write_u32(self.next_id);
+        self.followers.stable_hash(hasher);
+        hasher.write_u32(self.next_follow_handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActiveSpritesList, Sprite, SpriteEnvironment, SpriteType};
+    use crate::{
+        city::{
+            events::{CityEvents, GameEvent},
+            simulation::Simulation,
+            traffic::CityTraffic,
+            treasury::{CityTreasury, TransactionCategory},
+        },
+        map::{tiles::TILE_BULL_BIT, Map, MapPosition, MapRectangle, Tile, TileType},
+        messages::GameMessage,
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_move_sprites() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        for x in 0..120 {
+            map.set_tile_at(&(x, 10).into(), Tile::from_type(TileType::LhRail).unwrap());
+        }
+        let traffic = CityTraffic::from_map(&map);
+        let mut rate_of_growth = Simulation::new(&map).get_rate_of_growth_map().clone();
+        let mut events = CityEvents::new();
+        let mut treasury = CityTreasury::new(1000);
+        let mut rng = MicropolisRandom::from_seed(1234);
+
+        let mut sprites = ActiveSpritesList::new();
+        for (kind, position) in [
+            (SpriteType::Train, MapPosition::new(281, 166)),
+            // airplane and helicopter flying into each other
+            (SpriteType::Airplane, MapPosition::new(1000, 1000)),
+            (SpriteType::Helicopter, MapPosition::new(1008, 1024)),
+        ] {
+            let sprite = Sprite::new(&mut rng, "".into(), &kind, position, None).unwrap();
+            sprites.add_sprite(sprite);
+        }
+
+        let mut environment = SpriteEnvironment {
+            map: &mut map,
+            events: &mut events,
+            traffic_density_map: traffic.get_density_map(),
+            rate_of_growth_map: &mut rate_of_growth,
+            treasury: &mut treasury,
+            city_time: 0,
+            disasters_enabled: true,
+        };
+        sprites.move_sprites(&mut rng, &mut environment).unwrap();
+        assert!(sprites.get_sprite(&SpriteType::Airplane).is_none());
+        assert!(sprites.get_sprite(&SpriteType::Helicopter).is_none());
+        assert!(sprites.get_sprite(&SpriteType::Explosion).is_some());
+        let crashes: Vec<GameMessage> = environment
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::Message(message) => Some(message.message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            crashes,
+            vec![
+                GameMessage::MessageHelicopterCrashed,
+                GameMessage::MessagePlaneCrashed
+            ]
+        );
+
+        for _ in 0..20 {
+            sprites.move_sprites(&mut rng, &mut environment).unwrap();
+        }
+        assert!(sprites.get_sprite(&SpriteType::Explosion).is_none());
+        let fire = environment.map.get_tile_char_at(&(65, 63).into()).unwrap();
+        assert!(
+            fire >= TileType::Fire.to_u16().unwrap()
+                && fire <= TileType::LastFire.to_u16().unwrap()
+        );
+
+        // the train follows the rails
+        let train = sprites.get_sprite(&SpriteType::Train).unwrap();
+        assert_eq!(train.position.get_y(), 166);
+        assert_ne!(train.position.get_x(), 281);
+        assert_eq!(sprites.pool.len(), 1);
+    }
+
+    #[test]
+    fn test_bus_bulldozes_its_way() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        for x in 0..20 {
+            map.set_tile_at(&(x, 10).into(), Tile::from_type(TileType::Roads).unwrap());
+        }
+        let fountain = TileType::FOUNTAIN.to_u16().unwrap() | TILE_BULL_BIT;
+        map.set_tile_at(&(12, 10).into(), Tile::from_raw(fountain).unwrap());
+        let traffic = CityTraffic::from_map(&map);
+        let mut rate_of_growth = Simulation::new(&map).get_rate_of_growth_map().clone();
+        let mut events = CityEvents::new();
+        let mut treasury = CityTreasury::new(1000);
+        let mut rng = MicropolisRandom::from_seed(1234);
+
+        // driving right at full speed, with the fountain just ahead
+        let mut sprites = ActiveSpritesList::new();
+        let bus = Sprite::new(
+            &mut rng,
+            "".into(),
+            &SpriteType::Bus,
+            (137, 172).into(),
+            None,
+        )
+        .unwrap();
+        sprites.add_sprite(bus);
+        let mut environment = SpriteEnvironment {
+            map: &mut map,
+            events: &mut events,
+            traffic_density_map: traffic.get_density_map(),
+            rate_of_growth_map: &mut rate_of_growth,
+            treasury: &mut treasury,
+            city_time: 42,
+            disasters_enabled: false,
+        };
+        sprites.move_sprites(&mut rng, &mut environment).unwrap();
+
+        assert_eq!(
+            environment.map.get_tile_char_at(&(12, 10).into()),
+            TileType::Dirt.to_u16()
+        );
+        assert_eq!(environment.treasury.get_balance(), 999);
+        let transaction = environment.treasury.get_ledger().back().unwrap();
+        assert_eq!(transaction.category, TransactionCategory::Construction);
+        assert_eq!(transaction.city_time, 42);
+        let bus = sprites.get_sprite(&SpriteType::Bus).unwrap();
+        assert_eq!(bus.position, (145, 172).into());
+    }
+
+    #[test]
+    fn test_sprite_views_and_followers() {
+        let mut rng = MicropolisRandom::from_seed(1234);
+        let mut sprites = ActiveSpritesList::new();
+        let train = Sprite::new(
+            &mut rng,
+            "".into(),
+            &SpriteType::Train,
+            (100, 200).into(),
+            None,
+        )
+        .unwrap();
+        let train_id = sprites.add_sprite(train);
+        let bus = Sprite::new(&mut rng, "".into(), &SpriteType::Bus, (0, 0).into(), None).unwrap();
+        let bus_id = sprites.add_sprite(bus);
+        assert_ne!(train_id, bus_id);
+
+        let views = sprites.get_views();
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].id, train_id);
+        assert_eq!(views[0].kind, SpriteType::Train);
+        assert_eq!(views[0].position, MapPosition::new(132, 184));
+        assert_eq!(views[0].hot_spot, MapPosition::new(140, 192));
+        assert_eq!(views[0].frame, 1);
+
+        let camera = sprites.follow_sprite(train_id).unwrap();
+        let other_camera = sprites.follow_sprite(train_id).unwrap();
+        assert_ne!(camera, other_camera);
+        assert_eq!(sprites.get_followed_sprite(camera).unwrap().id, train_id);
+        sprites.unfollow_sprite(other_camera);
+        assert!(sprites.get_followed_sprite(other_camera).is_none());
+
+        let serialized = serde_json::to_string(&sprites).unwrap();
+        let deserialized: ActiveSpritesList = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, sprites);
+
+        sprites.destroy_sprite(train_id);
+        assert!(sprites.get_followed_sprite(camera).is_none());
+        assert!(sprites.follow_sprite(train_id).is_none());
+        assert_eq!(sprites.get_views().len(), 1);
+    }
+
+    #[test]
+    fn test_turn_towards() {
+        assert_eq!(Sprite::turn_towards(1, 3), 2);
+        assert_eq!(Sprite::turn_towards(1, 7), 8);
+        assert_eq!(Sprite::turn_towards(8, 2), 1);
+        assert_eq!(Sprite::turn_towards(5, 5), 5);
     }
 }
@@ -0,0 +1,260 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::hash::{StableHash, StableHasher};
+
+use super::{
+    budget::{BudgetLine, BudgetLineKind},
+    City,
+};
+
+/// Parts of the city state that are hashed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CitySubsystem {
+    /// Tile map.
+    Tiles,
+    /// Overlay maps (population and traffic density, power grid, pollution, land value...).
+    Overlays,
+    /// Treasury, cash flow, taxes and budget.
+    Funds,
+    /// Population counts, zone counts and growth valves.
+    Population,
+    /// Disasters, their timers and schedule, and the fires with the wind.
+    Disasters,
+    /// Active sprites.
+    Sprites,
+    /// State of the random number generator.
+    Random,
+    /// City time and simulation cycle counters.
+    Counters,
+}
+
+impl CitySubsystem {
+    /// Every subsystem, in hashing order.
+    pub const ALL: [CitySubsystem; 8] = [
+        CitySubsystem::Tiles,
+        CitySubsystem::Overlays,
+        CitySubsystem::Funds,
+        CitySubsystem::Population,
+        CitySubsystem::Disasters,
+        CitySubsystem::Sprites,
+        CitySubsystem::Random,
+        CitySubsystem::Counters,
+    ];
+}
+
+impl fmt::Display for CitySubsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Canonical hash of the whole state of a city, split by subsystem.
+///
+/// Hashes are stable across platforms, runs and Rust versions, so two clients
+/// (or two replays) running the same game can compare them after every tick to
+/// detect a desync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CityStateHash {
+    tiles: u64,
+    overlays: u64,
+    funds: u64,
+    population: u64,
+    disasters: u64,
+    sprites: u64,
+    random: u64,
+    counters: u64,
+}
+
+impl CityStateHash {
+    /// Hash the current state of the given city.
+    pub fn compute(city: &City) -> Self {
+        CityStateHash {
+            tiles: StableHasher::hash_one(&city.map),
+            overlays: Self::hash_with(|hasher| {
+                city.population.get_density_map().stable_hash(hasher);
+                city.traffic.get_density_map().stable_hash(hasher);
+                city.power.power_grid_map.stable_hash(hasher);
+                city.sim.hash_overlay_maps(hasher);
+            }),
+            funds: Self::hash_with(|hasher| {
+                hasher.write_i64(city.treasury.get_balance());
+                hasher.write_i64(city.cash_flow);
+                let taxes = city.sim.get_taxes();
+                hasher.write_u16(taxes.city_tax);
+                hasher.write_u16(taxes.city_tax_average);
+                hasher.write_i64(city.budget.get_tax_funds());
+                for kind in [
+                    BudgetLineKind::FireDepartment,
+                    BudgetLineKind::PoliceDepartment,
+                    BudgetLineKind::Roads,
+                ] {
+                    Self::hash_budget_line(city.budget.get_line(kind), hasher);
+                }
+            }),
+            population: StableHasher::hash_one(&city.population),
+            disasters: Self::hash_with(|hasher| {
+                city.disasters.stable_hash(hasher);
+                city.fires.stable_hash(hasher);
+            }),
+            sprites: StableHasher::hash_one(&city.sprites),
+            random: StableHasher::hash_one(&city.rng),
+            counters: Self::hash_with(|hasher| {
+                hasher.write_u32(city.city_time);
+                hasher.write_u8(city.simulation_speed);
+                hasher.write_u32(city.roads_total);
+                hasher.write_u32(city.rail_total);
+                hasher.write_u32(city.fires_count);
+                hasher.write_u8(city.traffic.get_model() as u8);
+                city.sim.hash_counters(hasher);
+            }),
+        }
+    }
+
+    fn hash_with<F: FnOnce(&mut StableHasher)>(feed: F) -> u64 {
+        let mut hasher = StableHasher::new();
+        feed(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_budget_line(line: &BudgetLine, hasher: &mut StableHasher) {
+        hasher.write_i64(line.get_funds());
+        hasher.write_i64(line.get_value());
+        hasher.write_u64(line.get_percentage().value().to_bits());
+    }
+
+    /// Hash of the given subsystem.
+    pub fn get(&self, subsystem: CitySubsystem) -> u64 {
+        use CitySubsystem::*;
+        match subsystem {
+            Tiles => self.tiles,
+            Overlays => self.overlays,
+            Funds => self.funds,
+            Population => self.population,
+            Disasters => self.disasters,
+            Sprites => self.sprites,
+            Random => self.random,
+            Counters => self.counters,
+        }
+    }
+
+    /// Single hash value covering every subsystem.
+    pub fn combined(&self) -> u64 {
+        Self::hash_with(|hasher| {
+            for subsystem in CitySubsystem::ALL.iter() {
+                hasher.write_u64(self.get(*subsystem));
+            }
+        })
+    }
+
+    /// First subsystem, in hashing order, whose hash differs from the other state.
+    pub fn first_mismatch(&self, other: &CityStateHash) -> Option<CitySubsystem> {
+        CitySubsystem::ALL
+            .iter()
+            .find(|subsystem| self.get(**subsystem) != other.get(**subsystem))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CitySubsystem;
+    use crate::{
+        city::{
+            disasters::DisasterKind, simulation::sprites::make_tornado,
+            treasury::TransactionCategory, City,
+        },
+        game::GameScenario,
+        map::{Tile, TileType},
+    };
+
+    #[test]
+    fn test_city_state_hash() {
+        let mut city = City::new("Hashville".into(), GameScenario::None).unwrap();
+        city.rng.seed(1234);
+        let hash = city.state_hash();
+        assert_eq!(hash, city.state_hash());
+        assert_eq!(hash.first_mismatch(&hash), None);
+
+        // snapshots and copies hash the same
+        let restored = City::from_snapshot(city.snapshot()).unwrap();
+        assert_eq!(restored.state_hash().combined(), hash.combined());
+
+        let mut other = city.clone();
+        other
+            .treasury
+            .spend(0, TransactionCategory::Construction, 100);
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Funds)
+        );
+
+        other
+            .map
+            .set_tile_at(&(5, 5).into(), Tile::from_type(TileType::Fire).unwrap());
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Tiles)
+        );
+        assert_ne!(other.state_hash().combined(), hash.combined());
+
+        let mut other = city.clone();
+        make_tornado(
+            &mut other.rng,
+            &mut other.sprites,
+            &mut other.events,
+            &other.map,
+            None,
+        )
+        .unwrap();
+        let other_hash = other.state_hash();
+        assert_eq!(
+            hash.first_mismatch(&other_hash),
+            Some(CitySubsystem::Sprites)
+        );
+        assert_ne!(
+            hash.get(CitySubsystem::Random),
+            other_hash.get(CitySubsystem::Random)
+        );
+        assert_eq!(
+            hash.get(CitySubsystem::Overlays),
+            other_hash.get(CitySubsystem::Overlays)
+        );
+
+        let mut other = city.clone();
+        other.population.add_residential(10);
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Population)
+        );
+        let mut other = city.clone();
+        other.population.change_valves(0, 0, 1);
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Population)
+        );
+
+        let mut other = city.clone();
+        other
+            .disasters
+            .schedule_disaster(DisasterKind::Flood, 100, None);
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Disasters)
+        );
+        let mut other = city.clone();
+        other.fires.track_fire_at(&(5, 5).into());
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Disasters)
+        );
+
+        let mut other = city.clone();
+        other.city_time += 1;
+        assert_eq!(
+            hash.first_mismatch(&other.state_hash()),
+            Some(CitySubsystem::Counters)
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use std::cmp;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    error::MicropolisError,
     map::{
         tiles::TILE_LOW_MASK, Map, MapClusteringStrategy, MapPosition, MapPositionOffset,
         MapRectangle, Tile, TileMap, TileType, WORLD_HEIGHT, WORLD_WIDTH,
@@ -9,6 +12,9 @@ use crate::{
 };
 
 use super::{sprite::ActiveSpritesList, sprite::SpriteType, zoning::ZoneType};
+use routing::{CommuteReport, TrafficModel};
+
+pub mod routing;
 
 /// Maximum number of map tiles to drive, looking for a destination.
 const MAX_TRAFFIC_LOOKUP_DISTANCE: usize = 30;
@@ -33,21 +39,37 @@ impl TrafficDensityMap {
 }
 
 /// Traffic simulation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CityTraffic {
+    /// How trips are simulated.
+    model: TrafficModel,
     density_map: TrafficDensityMap,
+    /// Last position where heavy traffic was reported, if any.
+    traffic_max_location: Option<MapPosition>,
+    /// Number of positions saved during the current trip.
     positions_stack_pointer: usize,
-    positions_stack: [MapPosition; MAX_TRAFFIC_LOOKUP_DISTANCE],
+    /// Positions of the current trip (starting at index 1), saved every other move.
+    positions_stack: [MapPosition; MAX_TRAFFIC_LOOKUP_DISTANCE + 1],
 }
 
 impl CityTraffic {
     pub fn from_map(map: &TileMap) -> Self {
         Self {
+            model: TrafficModel::RandomWalk,
             density_map: TrafficDensityMap::density_map_with_dimensions(&map.bounds(), 0x00),
-            positions_stack: [MapPosition::new(0, 0); MAX_TRAFFIC_LOOKUP_DISTANCE],
+            traffic_max_location: None,
+            positions_stack: [MapPosition::new(0, 0); MAX_TRAFFIC_LOOKUP_DISTANCE + 1],
             positions_stack_pointer: 0,
         }
     }
 
+    pub fn get_model(&self) -> TrafficModel {
+        self.model
+    }
+    pub fn set_model(&mut self, model: TrafficModel) {
+        self.model = model;
+    }
+
     pub fn get_density_map(&self) -> &TrafficDensityMap {
         &self.density_map
     }
@@ -56,6 +78,11 @@ impl CityTraffic {
         &mut self.density_map
     }
 
+    /// Last position where heavy traffic was reported, if any.
+    pub fn get_traffic_max_location(&self) -> Option<MapPosition> {
+        self.traffic_max_location
+    }
+
     /// Spawn traffic starting from the road tile a the given position.
     ///
     /// Returns true if a connection was found.
@@ -66,17 +93,43 @@ impl CityTraffic {
         at: &MapPosition,
         destination_zone: &ZoneType,
         sprites: &mut ActiveSpritesList,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, MicropolisError> {
+        self.positions_stack_pointer = 0;
+
         let position = *at;
-        if self.try_driving_to(rng, map, &position, destination_zone)? {
-            self.add_to_traffic_density_map(rng, map, sprites)?;
-            Ok(true)
-        } else {
-            Ok(false)
+        match self.model {
+            TrafficModel::RandomWalk => {
+                if self.try_driving_to(rng, map, &position, destination_zone)? {
+                    self.add_to_traffic_density_map(rng, map, sprites)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            TrafficModel::ShortestPath => {
+                match routing::find_route(map, &self.density_map, &position, destination_zone)? {
+                    Some(route) => {
+                        // like random walks, count the traffic every other move
+                        for position in route.iter().skip(2).step_by(2) {
+                            self.add_traffic_at(rng, map, position, sprites)?;
+                        }
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
         }
     }
 
-    /// Find a connection over a road from the given position.
+    /// Analyze the shortest-path commutes of all the residential zones of the given map,
+    /// with the current congestion.
+    ///
+    /// Does not change the traffic density map.
+    pub fn analyze_commutes(&self, map: &TileMap) -> Result<CommuteReport, MicropolisError> {
+        routing::analyze_commutes(map, &self.density_map)
+    }
+
+    /// Find a connection over a road from the given zone center position.
     ///
     /// Returns Some(true) if a connection was found, Some(false) if not and None if
     /// no connection to a road was found.
@@ -87,18 +140,13 @@ impl CityTraffic {
         starting_at: &MapPosition,
         destination_zone: &ZoneType,
         sprites: &mut ActiveSpritesList,
-    ) -> Result<Option<bool>, String> {
-        self.positions_stack_pointer = 0;
-
-        let position = *starting_at;
-        if Self::find_perimeter_road(map, &position)?.0 {
-            if self.try_driving_to(rng, map, &position, destination_zone)? {
-                self.add_to_traffic_density_map(rng, map, sprites)?;
-                return Ok(Some(true));
-            }
-            return Ok(Some(false));
+    ) -> Result<Option<bool>, MicropolisError> {
+        let (found_road, road_position) = Self::find_perimeter_road(map, starting_at)?;
+        if !found_road {
+            return Ok(None);
         }
-        Ok(None)
+        self.spawn_traffic_at(rng, map, &road_position, destination_zone, sprites)
+            .map(Some)
     }
 
     /// Update the traffic density map from the positions stack.
@@ -107,32 +155,48 @@ impl CityTraffic {
         rng: &mut MicropolisRandom,
         map: &TileMap,
         sprites: &mut ActiveSpritesList,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         while self.positions_stack_pointer > 0 {
             let position = self.stack_pop_position();
-            if let Some(tile_value) = map
-                .get_tile_at(&position)
-                .map(|t| t.get_raw() & TILE_LOW_MASK)
+            self.add_traffic_at(rng, map, &position, sprites)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the traffic of one trip at the given position, if it is a road.
+    fn add_traffic_at(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        map: &TileMap,
+        position: &MapPosition,
+        sprites: &mut ActiveSpritesList,
+    ) -> Result<(), MicropolisError> {
+        let position = *position;
+        if let Some(tile_value) = map
+            .get_tile_at(&position)
+            .map(|t| t.get_raw() & TILE_LOW_MASK)
+        {
+            if tile_value >= TileType::HorizontalBridge.to_u16().unwrap()
+                && tile_value < TileType::HorizontalPower.to_u16().unwrap()
             {
-                if tile_value >= TileType::HorizontalBridge.to_u16().unwrap()
-                    && tile_value < TileType::HorizontalPower.to_u16().unwrap()
-                {
-                    // update traffic density
-                    let mut traffic = self.density_map.get_tile_at(&position).ok_or(format!(
-                        "CityTraffic::add_to_traffic_density_map cannot get density value at {}",
-                        position
-                    ))? + 50;
-                    traffic = cmp::min(traffic, 240);
-                    self.density_map.set_tile_at(&position, traffic);
-
-                    // check for heavy traffic
-                    if traffic >= 240 && rng.get_random(5) == 0 {
-                        let traffic_max = position;
-                        // direct helicopter towards heavy traffic
-                        if let Some(sprite) = sprites.get_sprite_mut(&SpriteType::Helicopter) {
-                            if sprite.control == -1 {
-                                sprite.destination = traffic_max * 16;
-                            }
+                // update traffic density
+                let traffic = *self
+                    .density_map
+                    .get_tile_at(&position)
+                    .ok_or(MicropolisError::OutOfBounds(position))?
+                    as u16
+                    + 50;
+                let traffic = cmp::min(traffic, 240) as u8;
+                self.density_map.set_tile_at(&position, traffic);
+
+                // check for heavy traffic
+                if traffic >= 240 && rng.get_random(5) == 0 {
+                    self.traffic_max_location = Some(position);
+                    // direct helicopter towards heavy traffic
+                    if let Some(sprite) = sprites.get_sprite_mut(&SpriteType::Helicopter) {
+                        if sprite.control == -1 {
+                            sprite.destination = position * 16;
                         }
                     }
                 }
@@ -144,30 +208,25 @@ impl CityTraffic {
 
     fn stack_push_position(&mut self, position: &MapPosition) {
         self.positions_stack_pointer += 1;
-        assert!(self.positions_stack_pointer < MAX_TRAFFIC_LOOKUP_DISTANCE);
-        self.positions_stack[self.positions_stack_pointer] = position.clone();
+        assert!(self.positions_stack_pointer < MAX_TRAFFIC_LOOKUP_DISTANCE + 1);
+        self.positions_stack[self.positions_stack_pointer] = *position;
     }
 
     fn stack_pop_position(&mut self) -> MapPosition {
         assert!(self.positions_stack_pointer > 0);
+        let position = self.positions_stack[self.positions_stack_pointer];
         self.positions_stack_pointer -= 1;
-        self.positions_stack[self.positions_stack_pointer]
-    }
-
-    fn can_drive_to(&self, from: &MapPosition, towards_zone: &ZoneType) -> Result<bool, String> {
-        let mut current_position = from;
-        for dist in 0..MAX_TRAFFIC_LOOKUP_DISTANCE {}
-        todo!()
+        position
     }
 
     /// Find a connection to a road at the given perimeter position.
     pub fn find_perimeter_road(
         map: &TileMap,
         position: &MapPosition,
-    ) -> Result<(bool, MapPosition), String> {
+    ) -> Result<(bool, MapPosition), MicropolisError> {
         for z in 0..12 {
             let t = *position + (PERIMETER_ROAD_EDGES_X[z], PERIMETER_ROAD_EDGES_Y[z]).into();
-            if let Some(tile) = map.get_tile_at(&position) {
+            if let Some(tile) = map.get_tile_at(&t) {
                 if tile.is_driveable() {
                     return Ok((true, t));
                 }
@@ -176,24 +235,29 @@ impl CityTraffic {
         Ok((false, position.clone()))
     }
 
-    /// Try to drive to the given destination.
+    /// Try to drive to the given destination, with a random walk over the roads.
+    ///
+    /// The positions of the trip are saved on the positions stack.
+    ///
+    /// See `Micropolis::tryDrive` in the C++ code.
     fn try_driving_to(
         &mut self,
         rng: &mut MicropolisRandom,
         map: &TileMap,
         from: &MapPosition,
         destination_zone: &ZoneType,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, MicropolisError> {
         let mut previous_direction = MapPositionOffset::None;
         let mut current_position = *from;
 
-        for mut distance in 0..MAX_TRAFFIC_LOOKUP_DISTANCE {
+        let mut distance = 0;
+        while distance < MAX_TRAFFIC_LOOKUP_DISTANCE {
             let direction =
                 Self::try_random_driving(rng, map, &current_position, &previous_direction)?;
             if direction != MapPositionOffset::None {
                 // road found
                 current_position = direction.apply(&current_position);
-                previous_direction = previous_direction.rotated_180();
+                previous_direction = direction.rotated_180();
 
                 if distance & 0x01 != 0x00 {
                     self.stack_push_position(&current_position);
@@ -208,6 +272,7 @@ impl CityTraffic {
             } else {
                 return Ok(false);
             }
+            distance += 1;
         }
 
         // exceeded MAX_TRAFFIC_LOOKUP_DISTANCE
@@ -220,7 +285,7 @@ impl CityTraffic {
         map: &TileMap,
         from: &MapPosition,
         from_direction: &MapPositionOffset,
-    ) -> Result<MapPositionOffset, String> {
+    ) -> Result<MapPositionOffset, MicropolisError> {
         let mut directions = [MapPositionOffset::None; 4];
 
         // find connections from current position
@@ -274,7 +339,7 @@ impl CityTraffic {
         map: &TileMap,
         position: &MapPosition,
         destination_zone: &ZoneType,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, MicropolisError> {
         let destination_zone_index = destination_zone.to_usize().unwrap();
         let (target_low, target_high) = (
             DRIVING_DONE_TARGET_LOW[destination_zone_index]
@@ -285,15 +350,13 @@ impl CityTraffic {
                 .unwrap(),
         );
 
-        let get_low_tile_value = |map: &TileMap, pos: &MapPosition| -> Result<usize, String> {
-            Ok(map
-                .get_tile_at(&pos)
-                .ok_or(format!(
-                    "CityTraffic::is_driving_done cannot get tile map at {}",
-                    pos
-                ))
-                .map(|t| t.get_raw() & TILE_LOW_MASK)? as usize)
-        };
+        let get_low_tile_value =
+            |map: &TileMap, pos: &MapPosition| -> Result<usize, MicropolisError> {
+                Ok(map
+                    .get_tile_at(pos)
+                    .ok_or(MicropolisError::OutOfBounds(*pos))
+                    .map(|t| t.get_raw() & TILE_LOW_MASK)? as usize)
+            };
 
         let (x, y) = position.as_tuple();
         if y > 0 {
@@ -324,3 +387,77 @@ impl CityTraffic {
         Ok(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CityTraffic;
+    use crate::{
+        city::{sprite::ActiveSpritesList, zoning::ZoneType},
+        map::{Map, MapPosition, MapRectangle, Tile, TileType},
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_spawn_traffic() {
+        let mut rng = MicropolisRandom::from_seed(1234);
+        let mut sprites = ActiveSpritesList::new();
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        for x in 10..26 {
+            map.set_tile_at(
+                &MapPosition::new(x, 10),
+                Tile::from_type(TileType::Roads).unwrap(),
+            );
+        }
+        let mut traffic = CityTraffic::from_map(&map);
+
+        // no destination at the end of the road
+        assert!(!traffic
+            .spawn_traffic_at(
+                &mut rng,
+                &map,
+                &(10, 10).into(),
+                &ZoneType::Commercial,
+                &mut sprites
+            )
+            .unwrap());
+
+        // commercial zone at the end of the road
+        map.set_tile_at(
+            &MapPosition::new(26, 10),
+            Tile::from_type(TileType::CommercialClr).unwrap(),
+        );
+        assert!(traffic
+            .spawn_traffic_at(
+                &mut rng,
+                &map,
+                &(10, 10).into(),
+                &ZoneType::Commercial,
+                &mut sprites
+            )
+            .unwrap());
+        assert_eq!(traffic.positions_stack_pointer, 0);
+        assert_eq!(
+            traffic.get_density_map().get_tile_at(&(12, 10).into()),
+            Some(&50)
+        );
+        assert_eq!(
+            traffic.get_density_map().get_tile_at(&(12, 20).into()),
+            Some(&0)
+        );
+
+        // no road access
+        assert_eq!(
+            traffic
+                .spawn_traffic(
+                    &mut rng,
+                    &map,
+                    &(60, 60).into(),
+                    &ZoneType::Commercial,
+                    &mut sprites
+                )
+                .unwrap(),
+            None
+        );
+    }
+}
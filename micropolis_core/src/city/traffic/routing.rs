@@ -0,0 +1,255 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    city::zoning::ZoneType,
+    error::MicropolisError,
+    map::{
+        tiles::{TILE_LOW_MASK, TILE_ZONE_BIT},
+        Map, MapClusteringStrategy, MapPosition, MapPositionOffset, Tile, TileMap, TileType,
+    },
+};
+
+use super::{CityTraffic, TrafficDensityMap};
+
+/// Maximum cost of a route, congestion included.
+const MAX_ROUTE_COST: u32 = 120;
+/// Traffic density costing one more tile of travel on a road.
+const CONGESTION_DENSITY_STEP: u32 = 64;
+
+const ROUTE_DIRECTIONS: [MapPositionOffset; 4] = [
+    MapPositionOffset::North,
+    MapPositionOffset::East,
+    MapPositionOffset::South,
+    MapPositionOffset::West,
+];
+
+/// How trips are simulated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrafficModel {
+    /// Random walks over the roads, as in the original game.
+    RandomWalk,
+    /// Shortest-path trips over the roads and rails, avoiding congested roads.
+    ShortestPath,
+}
+
+/// City-wide analysis of the commutes from the residential zones to jobs and shops.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommuteReport {
+    /// Number of trips going through each tile.
+    pub flow: Map<u16>,
+    /// Number of residential zones reaching a destination.
+    pub trips_count: usize,
+    /// Average length of the trips, in tiles.
+    pub average_commute_length: f64,
+    /// Centers of the residential zones reaching no destination.
+    pub unreachable_zones: Vec<MapPosition>,
+}
+
+/// Analyze the commutes of all the residential zones of the given map.
+///
+/// Each residential zone takes the cheapest route from its perimeter road to the
+/// nearest commercial or industrial zone.
+pub(super) fn analyze_commutes(
+    map: &TileMap,
+    density_map: &TrafficDensityMap,
+) -> Result<CommuteReport, MicropolisError> {
+    let bounds = map.bounds();
+    let (residential_low, residential_high) = (
+        TileType::ResidentialBase.to_u16().unwrap(),
+        TileType::HospitalBase.to_u16().unwrap(),
+    );
+    let mut report = CommuteReport {
+        flow: Map::with_data(
+            vec![vec![0; bounds.get_height()]; bounds.get_width()],
+            MapClusteringStrategy::BlockSize1,
+        ),
+        trips_count: 0,
+        average_commute_length: 0.0,
+        unreachable_zones: Vec::new(),
+    };
+    let mut total_length = 0;
+
+    for x in 0..bounds.get_width() {
+        for y in 0..bounds.get_height() {
+            let zone_center = MapPosition::new(x as i32, y as i32);
+            let raw = map.get_tile_at(&zone_center).map_or(0, Tile::get_raw);
+            let tile_value = raw & TILE_LOW_MASK;
+            if raw & TILE_ZONE_BIT == 0x00
+                || tile_value < residential_low
+                || tile_value >= residential_high
+            {
+                continue;
+            }
+
+            let route = match CityTraffic::find_perimeter_road(map, &zone_center)? {
+                (true, road) => find_route(map, density_map, &road, &ZoneType::Commercial)?,
+                (false, _) => None,
+            };
+            match route {
+                Some(route) => {
+                    report.trips_count += 1;
+                    total_length += route.len();
+                    for position in &route {
+                        if let Some(flow) = report.flow.get_tile_mut_at(position) {
+                            *flow = flow.saturating_add(1);
+                        }
+                    }
+                }
+                None => report.unreachable_zones.push(zone_center),
+            }
+        }
+    }
+
+    if report.trips_count > 0 {
+        report.average_commute_length = total_length as f64 / report.trips_count as f64;
+    }
+    Ok(report)
+}
+
+/// Find the cheapest route from the given road position to a destination of the given
+/// zone type, over the roads and rails.
+///
+/// Each tile costs 1, plus a congestion cost depending on the traffic density for roads.
+/// Returns the positions of the route, starting position included, or None if no
+/// destination can be reached within `MAX_ROUTE_COST`.
+pub(super) fn find_route(
+    map: &TileMap,
+    density_map: &TrafficDensityMap,
+    from: &MapPosition,
+    destination_zone: &ZoneType,
+) -> Result<Option<Vec<MapPosition>>, MicropolisError> {
+    let bounds = map.bounds();
+    let height = bounds.get_height();
+    let index_of =
+        |position: &MapPosition| position.get_x() as usize * height + position.get_y() as usize;
+    let position_of =
+        |index: usize| MapPosition::new((index / height) as i32, (index % height) as i32);
+
+    let mut costs = vec![u32::MAX; bounds.get_width() * height];
+    let mut previous: Vec<Option<usize>> = vec![None; costs.len()];
+    let mut queue = BinaryHeap::new();
+    costs[index_of(from)] = 0;
+    queue.push(Reverse((0, index_of(from))));
+
+    while let Some(Reverse((cost, index))) = queue.pop() {
+        if cost > costs[index] {
+            continue;
+        }
+        let position = position_of(index);
+        if position != *from && CityTraffic::is_driving_done(map, &position, destination_zone)? {
+            let mut route = vec![position];
+            let mut current = index;
+            while let Some(before) = previous[current] {
+                route.push(position_of(before));
+                current = before;
+            }
+            route.reverse();
+            return Ok(Some(route));
+        }
+
+        for direction in &ROUTE_DIRECTIONS {
+            let neighbor = match direction.apply_with_bounds(&position, &bounds) {
+                Some(neighbor) => neighbor,
+                None => continue,
+            };
+            let tile = match map.get_tile_at(&neighbor) {
+                Some(tile) if tile.is_driveable() => tile,
+                _ => continue,
+            };
+            let neighbor_cost = cost + 1 + congestion_cost(tile, density_map, &neighbor);
+            let neighbor_index = index_of(&neighbor);
+            if neighbor_cost <= MAX_ROUTE_COST && neighbor_cost < costs[neighbor_index] {
+                costs[neighbor_index] = neighbor_cost;
+                previous[neighbor_index] = Some(index);
+                queue.push(Reverse((neighbor_cost, neighbor_index)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Extra cost of driving through the given tile because of the traffic.
+///
+/// Rails are never congested.
+fn congestion_cost(tile: &Tile, density_map: &TrafficDensityMap, at: &MapPosition) -> u32 {
+    let tile_value = tile.get_raw() & TILE_LOW_MASK;
+    if tile_value >= TileType::HorizontalBridge.to_u16().unwrap()
+        && tile_value < TileType::HorizontalPower.to_u16().unwrap()
+    {
+        density_map
+            .get_tile_at(at)
+            .map_or(0, |density| *density as u32 / CONGESTION_DENSITY_STEP)
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrafficModel;
+    use crate::{
+        city::{sprite::ActiveSpritesList, traffic::CityTraffic, zoning::ZoneType},
+        map::{tiles::TILE_ZONE_BIT, Map, MapPosition, MapRectangle, Tile, TileType},
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_shortest_path_commutes() {
+        let mut map =
+            Map::tilemap_with_dimensions(&MapRectangle::new(120, 100), TileType::Dirt).unwrap();
+        let zone_center = TileType::FreeZoneCenter.to_u16().unwrap() | TILE_ZONE_BIT;
+        // residential zone with a road to a commercial zone
+        map.set_tile_at(&(10, 10).into(), Tile::from_raw(zone_center).unwrap());
+        for x in 9..=20 {
+            map.set_tile_at(&(x, 8).into(), Tile::from_type(TileType::Roads).unwrap());
+        }
+        map.set_tile_at(
+            &(21, 8).into(),
+            Tile::from_type(TileType::CommercialClr).unwrap(),
+        );
+        // residential zone without any road
+        map.set_tile_at(&(50, 50).into(), Tile::from_raw(zone_center).unwrap());
+
+        let mut traffic = CityTraffic::from_map(&map);
+        let report = traffic.analyze_commutes(&map).unwrap();
+        assert_eq!(report.trips_count, 1);
+        assert_eq!(report.average_commute_length, 12.0);
+        assert_eq!(report.unreachable_zones, vec![MapPosition::new(50, 50)]);
+        assert_eq!(report.flow.get_tile_at(&(15, 8).into()), Some(&1));
+        assert_eq!(report.flow.get_tile_at(&(15, 9).into()), Some(&0));
+
+        traffic.set_model(TrafficModel::ShortestPath);
+        let mut rng = MicropolisRandom::from_seed(1234);
+        let mut sprites = ActiveSpritesList::new();
+        assert_eq!(
+            traffic
+                .spawn_traffic(
+                    &mut rng,
+                    &map,
+                    &(10, 10).into(),
+                    &ZoneType::Commercial,
+                    &mut sprites
+                )
+                .unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            traffic.get_density_map().get_tile_at(&(11, 8).into()),
+            Some(&50)
+        );
+        assert_eq!(
+            traffic
+                .spawn_traffic(
+                    &mut rng,
+                    &map,
+                    &(10, 10).into(),
+                    &ZoneType::Residential,
+                    &mut sprites
+                )
+                .unwrap(),
+            Some(false)
+        );
+    }
+}
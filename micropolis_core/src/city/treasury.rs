@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::budget::MoneyValue;
+use crate::error::MicropolisError;
+
+/// Number of city time units per month.
+const CITY_TIME_UNITS_PER_MONTH: u32 = 4;
+/// Number of city time units per year.
+const CITY_TIME_UNITS_PER_YEAR: u32 = CITY_TIME_UNITS_PER_MONTH * 12;
+/// Number of years of transactions kept in the ledger.
+const LEDGER_HISTORY_YEARS: u32 = 10;
+
+/// What a transaction of the city treasury was for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TransactionCategory {
+    /// Cost of building, bulldozing or terraforming with an editing tool.
+    Construction,
+    /// Yearly tax income.
+    Taxes,
+    /// Yearly spending on roads and rails maintenance.
+    RoadsMaintenance,
+    /// Yearly spending on the fire department.
+    FireDepartment,
+    /// Yearly spending on the police department.
+    PoliceDepartment,
+}
+
+/// A single entry of the city ledger.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// City time at which the transaction happened.
+    pub city_time: u32,
+    /// What the transaction was for.
+    pub category: TransactionCategory,
+    /// Amount of the transaction: positive for an income, negative for an expense.
+    pub amount: MoneyValue,
+}
+
+/// Summary of the transactions over a period of time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerSummary {
+    /// Total income.
+    pub income: MoneyValue,
+    /// Total expenses, as a positive value.
+    pub expenses: MoneyValue,
+    /// Net amount for each category with at least one transaction.
+    pub by_category: BTreeMap<TransactionCategory, MoneyValue>,
+}
+
+impl LedgerSummary {
+    /// Net amount over the period.
+    pub fn get_net(&self) -> MoneyValue {
+        self.income - self.expenses
+    }
+
+    fn add(&mut self, transaction: &Transaction) {
+        if transaction.amount >= 0 {
+            self.income += transaction.amount;
+        } else {
+            self.expenses -= transaction.amount;
+        }
+        *self.by_category.entry(transaction.category).or_insert(0) += transaction.amount;
+    }
+}
+
+/// Funds of the city, with an itemized ledger of where the money went.
+///
+/// The balance is signed: the city can go into debt.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CityTreasury {
+    /// Funds available to the city (`totalFunds` in the C++ code).
+    balance: MoneyValue,
+    /// Transactions of the last `LEDGER_HISTORY_YEARS` years, oldest first.
+    ledger: VecDeque<Transaction>,
+}
+
+impl CityTreasury {
+    pub fn new(balance: MoneyValue) -> Self {
+        CityTreasury {
+            balance,
+            ledger: VecDeque::new(),
+        }
+    }
+
+    pub fn get_balance(&self) -> MoneyValue {
+        self.balance
+    }
+
+    /// Can the city pay for the given cost without going into debt?
+    pub fn can_afford(&self, cost: MoneyValue) -> bool {
+        self.balance >= cost
+    }
+
+    /// Record an income (positive amount) or an expense (negative amount).
+    pub fn record(&mut self, city_time: u32, category: TransactionCategory, amount: MoneyValue) {
+        if amount == 0 {
+            return;
+        }
+        self.balance += amount;
+        self.ledger.push_back(Transaction {
+            city_time,
+            category,
+            amount,
+        });
+
+        let history = LEDGER_HISTORY_YEARS * CITY_TIME_UNITS_PER_YEAR;
+        while let Some(oldest) = self.ledger.front() {
+            if oldest.city_time + history > city_time {
+                break;
+            }
+            self.ledger.pop_front();
+        }
+    }
+
+    /// Record an expense of the given cost.
+    pub fn spend(&mut self, city_time: u32, category: TransactionCategory, cost: MoneyValue) {
+        self.record(city_time, category, -cost);
+    }
+
+    /// Record an expense of the given cost, unless it would put the city into debt.
+    pub fn try_spend(
+        &mut self,
+        city_time: u32,
+        category: TransactionCategory,
+        cost: MoneyValue,
+    ) -> Result<(), MicropolisError> {
+        if !self.can_afford(cost) {
+            return Err(MicropolisError::InsufficientFunds {
+                cost,
+                available: self.balance,
+            });
+        }
+        self.spend(city_time, category, cost);
+        Ok(())
+    }
+
+    /// Transactions of the last years, oldest first.
+    pub fn get_ledger(&self) -> &VecDeque<Transaction> {
+        &self.ledger
+    }
+
+    /// Summarize the transactions of the month containing the given city time.
+    pub fn get_month_summary(&self, city_time: u32) -> LedgerSummary {
+        self.summarize(
+            city_time / CITY_TIME_UNITS_PER_MONTH,
+            CITY_TIME_UNITS_PER_MONTH,
+        )
+    }
+
+    /// Summarize the transactions of the year containing the given city time.
+    pub fn get_year_summary(&self, city_time: u32) -> LedgerSummary {
+        self.summarize(
+            city_time / CITY_TIME_UNITS_PER_YEAR,
+            CITY_TIME_UNITS_PER_YEAR,
+        )
+    }
+
+    fn summarize(&self, period: u32, period_length: u32) -> LedgerSummary {
+        let mut summary = LedgerSummary::default();
+        self.ledger
+            .iter()
+            .filter(|transaction| transaction.city_time / period_length == period)
+            .for_each(|transaction| summary.add(transaction));
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CityTreasury, TransactionCategory};
+    use crate::error::MicropolisError;
+
+    #[test]
+    fn test_treasury_debt() {
+        let mut treasury = CityTreasury::new(100);
+        assert!(treasury.can_afford(100));
+        assert!(!treasury.can_afford(101));
+
+        treasury.spend(0, TransactionCategory::Construction, 150);
+        assert_eq!(treasury.get_balance(), -50);
+        assert!(!treasury.can_afford(1));
+
+        treasury.record(1, TransactionCategory::Taxes, 80);
+        assert_eq!(treasury.get_balance(), 30);
+        assert_eq!(treasury.get_ledger().len(), 2);
+
+        assert!(matches!(
+            treasury.try_spend(2, TransactionCategory::Construction, 31),
+            Err(MicropolisError::InsufficientFunds {
+                cost: 31,
+                available: 30
+            })
+        ));
+        assert_eq!(treasury.get_balance(), 30);
+        treasury
+            .try_spend(2, TransactionCategory::Construction, 30)
+            .unwrap();
+        assert_eq!(treasury.get_balance(), 0);
+    }
+
+    #[test]
+    fn test_treasury_summaries() {
+        let mut treasury = CityTreasury::new(10000);
+        treasury.spend(1, TransactionCategory::Construction, 100);
+        treasury.spend(2, TransactionCategory::Construction, 10);
+        treasury.spend(5, TransactionCategory::Construction, 500);
+        treasury.record(47, TransactionCategory::Taxes, 1200);
+        treasury.spend(47, TransactionCategory::PoliceDepartment, 200);
+        treasury.spend(48, TransactionCategory::RoadsMaintenance, 30);
+
+        let january = treasury.get_month_summary(0);
+        assert_eq!(january.income, 0);
+        assert_eq!(january.expenses, 110);
+        assert_eq!(january.get_net(), -110);
+
+        let first_year = treasury.get_year_summary(12);
+        assert_eq!(first_year.income, 1200);
+        assert_eq!(first_year.expenses, 810);
+        assert_eq!(
+            first_year
+                .by_category
+                .get(&TransactionCategory::Construction),
+            Some(&-610)
+        );
+        assert_eq!(
+            first_year.by_category.get(&TransactionCategory::Taxes),
+            Some(&1200)
+        );
+        assert_eq!(
+            first_year
+                .by_category
+                .get(&TransactionCategory::RoadsMaintenance),
+            None
+        );
+
+        let second_year = treasury.get_year_summary(48);
+        assert_eq!(second_year.get_net(), -30);
+        assert_eq!(treasury.get_balance(), 10000 - 610 + 1200 - 200 - 30);
+
+        // old transactions are eventually dropped from the ledger
+        treasury.record(48 * 10 + 47, TransactionCategory::Taxes, 1);
+        assert_eq!(treasury.get_ledger().len(), 2);
+        assert_eq!(treasury.get_year_summary(0).get_net(), 0);
+    }
+}
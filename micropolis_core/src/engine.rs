@@ -0,0 +1,145 @@
+use crate::{
+    city::{
+        events::{CityEvents, GameEvent},
+        replay::GameCommand,
+        simulation::Simulation,
+        sprite::SpriteView,
+        state_hash::CityStateHash,
+        City,
+    },
+    error::MicropolisError,
+    map::TileMap,
+    utils::random::MicropolisRandom,
+};
+
+/// Entry point running a whole game.
+///
+/// Owns the city, with its event queue, sub-simulations and random number
+/// generator. Like in the C++ engine, that single generator drives the
+/// simulation steps, the sprites and the editing tools.
+pub struct Engine {
+    city: City,
+    /// Number of frame ticks run so far.
+    ticks: u64,
+}
+
+impl Engine {
+    /// Run the given city, keeping the state of its random number generator.
+    pub fn new(city: City) -> Self {
+        Engine { city, ticks: 0 }
+    }
+
+    /// Run the given city, seeding its random number generator.
+    pub fn with_seed(mut city: City, seed: i32) -> Self {
+        city.get_rng_mut().seed(seed);
+        Self::new(city)
+    }
+
+    /// Advance the game by one frame tick.
+    ///
+    /// The simulator cycles through its 16 phases, one phase per simulation step,
+    /// and the sprites move.
+    pub fn tick(&mut self) -> Result<(), MicropolisError> {
+        self.city.step()?;
+        self.ticks += 1;
+        Ok(())
+    }
+
+    /// Apply a player command to the city.
+    pub fn execute(&mut self, command: &GameCommand) -> Result<(), MicropolisError> {
+        command.apply(&mut self.city)
+    }
+
+    /// Number of frame ticks run so far.
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn get_city(&self) -> &City {
+        &self.city
+    }
+
+    pub fn get_rng(&self) -> &MicropolisRandom {
+        self.city.get_rng()
+    }
+
+    pub fn get_map(&self) -> &TileMap {
+        self.city.get_map()
+    }
+
+    pub fn get_sim(&self) -> &Simulation {
+        self.city.get_sim()
+    }
+
+    /// Read-only views of the active sprites, for the front-ends to draw them.
+    pub fn get_sprites(&self) -> Vec<SpriteView> {
+        self.city.get_sprites().get_views()
+    }
+
+    pub fn get_events(&self) -> &CityEvents {
+        self.city.get_events()
+    }
+
+    /// Remove and return the events emitted since the last call, oldest first.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        self.city.drain_events()
+    }
+
+    /// Compute the canonical hash of the whole state of the city.
+    pub fn state_hash(&self) -> CityStateHash {
+        self.city.state_hash()
+    }
+
+    /// Stop the engine, returning the city.
+    pub fn into_city(self) -> City {
+        self.city
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Engine;
+    use crate::{
+        city::{disasters::DisasterKind, replay::GameCommand, City},
+        game::GameScenario,
+    };
+
+    #[test]
+    fn test_engine_tick() {
+        let mut city = City::new("Engineville".into(), GameScenario::None).unwrap();
+        city.set_simulation_speed(3);
+        let mut engine = Engine::with_seed(city.clone(), 1234);
+        engine
+            .execute(&GameCommand::TriggerDisaster {
+                kind: DisasterKind::Monster,
+                at: Some((60, 50).into()),
+            })
+            .unwrap();
+        assert_eq!(engine.get_sprites().len(), 1);
+
+        // at normal speed, the simulator runs one phase every 5 frame ticks
+        let start_time = engine.get_city().get_city_time();
+        for _ in 0..5 * 16 {
+            engine.tick().unwrap();
+        }
+        assert_eq!(engine.get_ticks(), 80);
+        assert_eq!(engine.get_sim().get_phase_cycle(), 0);
+        assert_eq!(engine.get_city().get_city_time(), start_time + 1);
+        assert!(!engine.drain_events().is_empty());
+
+        // the simulation is deterministic
+        let mut other = Engine::with_seed(city, 1234);
+        other
+            .execute(&GameCommand::TriggerDisaster {
+                kind: DisasterKind::Monster,
+                at: Some((60, 50).into()),
+            })
+            .unwrap();
+        for _ in 0..80 {
+            other.tick().unwrap();
+        }
+        other.drain_events();
+        assert_eq!(other.state_hash(), engine.state_hash());
+        assert_eq!(other.into_city(), engine.into_city());
+    }
+}
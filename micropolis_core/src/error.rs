@@ -0,0 +1,152 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+use crate::{
+    city::{budget::MoneyValue, state_hash::CitySubsystem},
+    map::{MapPosition, TileType},
+};
+
+/// Error returned by the fallible functions of the core crate.
+#[derive(Debug)]
+pub enum MicropolisError {
+    /// Raw tile value not matching any tile (or any animation frame).
+    InvalidTileValue(u16),
+    /// Tile type that cannot be put on a map.
+    InvalidTileType(TileType),
+    /// Map position outside of the map.
+    OutOfBounds(MapPosition),
+    /// Game resource, or city file, that cannot be read.
+    ResourceLoad { path: PathBuf, source: io::Error },
+    /// Failure when reading or writing a stream.
+    Io(io::Error),
+    /// Malformed data, with the line (starting at 1) it was found at when known.
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+    /// The city cannot pay for something.
+    InsufficientFunds {
+        cost: MoneyValue,
+        available: MoneyValue,
+    },
+    /// Argument outside of its valid range.
+    InvalidArgument(String),
+    /// Snapshot or recording made with an unsupported format version.
+    UnsupportedVersion { found: u32, expected: u32 },
+    /// Replayed game diverging from its recording.
+    Desync { tick: u64, subsystem: CitySubsystem },
+}
+
+impl MicropolisError {
+    /// Parse error with no known line.
+    pub fn parse<S: Into<String>>(message: S) -> Self {
+        MicropolisError::Parse {
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    /// Parse error at the given line (starting at 1).
+    pub fn parse_at<S: Into<String>>(line: usize, message: S) -> Self {
+        MicropolisError::Parse {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MicropolisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MicropolisError::*;
+        match self {
+            InvalidTileValue(raw) => write!(f, "invalid tile value {:0>4X}", raw),
+            InvalidTileType(tile_type) => write!(f, "invalid tile type {:?}", tile_type),
+            OutOfBounds(position) => write!(
+                f,
+                "position ({}, {}) is out of bounds",
+                position.get_x(),
+                position.get_y()
+            ),
+            ResourceLoad { path, source } => {
+                write!(f, "cannot load resource {}: {}", path.display(), source)
+            }
+            Io(source) => write!(f, "I/O error: {}", source),
+            Parse {
+                line: Some(line),
+                message,
+            } => write!(f, "parse error at line {}: {}", line, message),
+            Parse {
+                line: None,
+                message,
+            } => write!(f, "parse error: {}", message),
+            InsufficientFunds { cost, available } => write!(
+                f,
+                "insufficient funds: {} needed, {} available",
+                cost, available
+            ),
+            InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+            UnsupportedVersion { found, expected } => write!(
+                f,
+                "unsupported format version {} (expected {})",
+                found, expected
+            ),
+            Desync { tick, subsystem } => {
+                write!(f, "desync at tick {} in subsystem {}", tick, subsystem)
+            }
+        }
+    }
+}
+
+impl Error for MicropolisError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MicropolisError::ResourceLoad { source, .. } | MicropolisError::Io(source) => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MicropolisError {
+    fn from(error: io::Error) -> Self {
+        MicropolisError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io};
+
+    use super::MicropolisError;
+
+    #[test]
+    fn test_error_display_and_source() {
+        assert_eq!(
+            MicropolisError::OutOfBounds((120, 3).into()).to_string(),
+            "position (120, 3) is out of bounds"
+        );
+        assert_eq!(
+            MicropolisError::parse_at(12, "unexpected token").to_string(),
+            "parse error at line 12: unexpected token"
+        );
+        assert_eq!(
+            MicropolisError::InsufficientFunds {
+                cost: 500,
+                available: 20
+            }
+            .to_string(),
+            "insufficient funds: 500 needed, 20 available"
+        );
+
+        let error = MicropolisError::ResourceLoad {
+            path: "res/stri.301".into(),
+            source: io::Error::new(io::ErrorKind::NotFound, "no such file"),
+        };
+        assert_eq!(
+            error.to_string(),
+            "cannot load resource res/stri.301: no such file"
+        );
+        assert!(error.source().is_some());
+        assert!(MicropolisError::InvalidTileValue(0x0400).source().is_none());
+    }
+}
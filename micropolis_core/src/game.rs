@@ -1,7 +1,10 @@
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive as FromPrimitiveTrait, ToPrimitive as ToPrimitiveTrait};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+use crate::city::budget::MoneyValue;
+
+#[derive(Clone, Debug, PartialEq, Eq, FromPrimitive, ToPrimitive, Serialize, Deserialize)]
 pub enum GameLevelDifficulty {
     Easy = 0,
     Normal = 1,
@@ -9,7 +12,7 @@ pub enum GameLevelDifficulty {
 }
 
 impl GameLevelDifficulty {
-    pub fn starting_funds(&self) -> u32 {
+    pub fn starting_funds(&self) -> MoneyValue {
         use GameLevelDifficulty::*;
         match self {
             Easy => 20000,
@@ -29,7 +32,7 @@ impl GameLevelDifficulty {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameScenario {
     /// Free play.
     None,
@@ -72,7 +75,7 @@ impl GameScenario {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameSpeed {
     /// Determines how often the animation timer fires, in milliseconds.
     animations_delay: u16,
@@ -91,7 +94,7 @@ impl GameSpeed {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameSpeedPreset {
     Paused,
     Slow,
@@ -1,6 +1,10 @@
 use crate::{
-    city::City,
-    map::{tools::apply_tool, tools::tool_down, tools::EditingTool, MapPosition, TileMap},
+    city::{events::CityEvents, City},
+    error::MicropolisError,
+    map::{
+        tools::apply_tool, tools::tool_down, tools::EditingTool, tools::ToolResult, MapPosition,
+        TileMap,
+    },
     utils::random::MicropolisRandom,
 };
 
@@ -21,9 +25,10 @@ impl MicropolisCoreInterfacer {
         rng: &mut MicropolisRandom,
         position: &MapPosition,
         tool: &EditingTool,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         let total_funds = self.city.total_funds();
-        tool_down(
+        let mut events = CityEvents::new();
+        let result = tool_down(
             rng,
             self.city.get_map_mut(),
             position,
@@ -31,7 +36,10 @@ impl MicropolisCoreInterfacer {
             self.options.auto_bulldoze,
             self.options.animations_enabled,
             total_funds,
+            &mut events,
         )?;
+        self.city.get_events_mut().append(&mut events);
+        self.city.charge_tool_result(&result)?;
 
         self.city.get_sim_mut().reset_pass_counter();
         self.city.invalidate_map();
@@ -49,19 +57,10 @@ impl MicropolisCoreInterfacer {
         tool: &EditingTool,
         auto_bulldoze: bool,
         animations_enabled: bool,
-        total_funds: u32,
-    ) -> Result<(), String> {
+    ) -> Result<(), MicropolisError> {
         // do not drag big tools
         if tool.clone().size() > 1 {
-            apply_tool(
-                rng,
-                map,
-                to,
-                tool,
-                auto_bulldoze,
-                animations_enabled,
-                total_funds,
-            )?;
+            self.apply_tool_and_charge(rng, map, to, tool, auto_bulldoze, animations_enabled)?;
 
             self.city.get_sim_mut().reset_pass_counter(); // update editors overlapping this one
             self.city.invalidate_map();
@@ -72,15 +71,7 @@ impl MicropolisCoreInterfacer {
             return Ok(());
         }
         // ensure the start position is done
-        apply_tool(
-            rng,
-            map,
-            from,
-            tool,
-            auto_bulldoze,
-            animations_enabled,
-            total_funds,
-        )?;
+        self.apply_tool_and_charge(rng, map, from, tool, auto_bulldoze, animations_enabled)?;
         let direction = (*to - *from).unitary(-1);
         let (mut current_from, mut current_to) = (from.clone(), to.clone());
 
@@ -89,29 +80,27 @@ impl MicropolisCoreInterfacer {
             (true, false) => {
                 while current_from != current_to {
                     current_from = current_from.with_y_offset(direction.get_y() as i8);
-                    apply_tool(
+                    self.apply_tool_and_charge(
                         rng,
                         map,
                         &current_from,
                         tool,
                         auto_bulldoze,
                         animations_enabled,
-                        total_funds,
                     )?;
                 }
             }
             // horizontal line left or right
             (false, true) => {
                 while current_from != current_to {
-                    current_from = current_from.with_y_offset(direction.get_x() as i8);
-                    apply_tool(
+                    current_from = current_from.with_x_offset(direction.get_x() as i8);
+                    self.apply_tool_and_charge(
                         rng,
                         map,
                         &current_from,
                         tool,
                         auto_bulldoze,
                         animations_enabled,
-                        total_funds,
                     )?;
                 }
             }
@@ -125,29 +114,27 @@ impl MicropolisCoreInterfacer {
                     if sub_x >= delta.get_y() {
                         sub_x -= delta.get_y();
                         current_from = current_from.with_x_offset(direction.get_x() as i8);
-                        apply_tool(
+                        self.apply_tool_and_charge(
                             rng,
                             map,
                             &current_from,
                             tool,
                             auto_bulldoze,
                             animations_enabled,
-                            total_funds,
                         )?;
                     }
 
                     sub_y += sub_steps_count;
                     if sub_y >= delta.get_x() {
                         sub_y -= delta.get_x();
-                        current_from = current_from.with_x_offset(direction.get_y() as i8);
-                        apply_tool(
+                        current_from = current_from.with_y_offset(direction.get_y() as i8);
+                        self.apply_tool_and_charge(
                             rng,
                             map,
                             &current_from,
                             tool,
                             auto_bulldoze,
                             animations_enabled,
-                            total_funds,
                         )?;
                     }
                 }
@@ -158,4 +145,102 @@ impl MicropolisCoreInterfacer {
         self.city.invalidate_map();
         Ok(())
     }
+
+    /// Apply a tool with the current city funds, and charge its cost on success.
+    fn apply_tool_and_charge(
+        &mut self,
+        rng: &mut MicropolisRandom,
+        map: &mut TileMap,
+        position: &MapPosition,
+        tool: &EditingTool,
+        auto_bulldoze: bool,
+        animations_enabled: bool,
+    ) -> Result<ToolResult, MicropolisError> {
+        let result = apply_tool(
+            rng,
+            map,
+            position,
+            tool,
+            auto_bulldoze,
+            animations_enabled,
+            self.city.total_funds(),
+            self.city.get_events_mut(),
+        )?;
+        self.city.charge_tool_result(&result)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MicropolisCoreInterfacer, MicropolisCoreOptions};
+    use crate::{
+        city::{
+            treasury::{CityTreasury, TransactionCategory},
+            City,
+        },
+        game::GameScenario,
+        map::{
+            tiles::{TILE_BULL_BIT, TILE_BURN_BIT},
+            tools::EditingTool,
+            MapClusteringStrategy, Tile, TileMap, TileType,
+        },
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_tools_charge_the_treasury() {
+        let woods = TileType::Woods.to_u16().unwrap() | TILE_BURN_BIT | TILE_BULL_BIT;
+        let mut map = TileMap::with_data(
+            vec![vec![Tile::from_raw(woods).unwrap(); 100]; 120],
+            MapClusteringStrategy::BlockSize1,
+        );
+        let mut city =
+            City::with_map("Chargeville".into(), GameScenario::None, map.clone()).unwrap();
+        *city.get_treasury_mut() = CityTreasury::new(1000);
+        let mut interfacer = MicropolisCoreInterfacer {
+            city,
+            options: MicropolisCoreOptions {
+                animations_enabled: false,
+                sound_enabled: false,
+                auto_bulldoze: true,
+            },
+        };
+        let mut rng = MicropolisRandom::from_seed(1234);
+        let bulldozer = EditingTool::Bulldozer;
+
+        interfacer
+            .tool_down(&mut rng, &(10, 10).into(), &bulldozer)
+            .unwrap();
+        let treasury = interfacer.city.get_treasury();
+        assert_eq!(treasury.get_balance(), 999);
+        assert_eq!(treasury.get_ledger().len(), 1);
+
+        // vertical, horizontal and diagonal drags, one tile charged per step
+        for (from, to, tiles) in [
+            ((20, 10), (20, 13), 4),
+            ((30, 10), (33, 10), 4),
+            ((40, 10), (42, 12), 5),
+        ] {
+            let balance = interfacer.city.get_treasury().get_balance();
+            interfacer
+                .tool_drag(
+                    &mut rng,
+                    &mut map,
+                    &from.into(),
+                    &to.into(),
+                    &bulldozer,
+                    true,
+                    false,
+                )
+                .unwrap();
+            let treasury = interfacer.city.get_treasury();
+            assert_eq!(treasury.get_balance(), balance - tiles);
+        }
+        let treasury = interfacer.city.get_treasury();
+        assert_eq!(treasury.get_ledger().len(), 14);
+        assert!(treasury.get_ledger().iter().all(|transaction| {
+            transaction.category == TransactionCategory::Construction && transaction.amount == -1
+        }));
+    }
 }
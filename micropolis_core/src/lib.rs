@@ -1,6 +1,9 @@
 pub mod city;
+pub mod engine;
+pub mod error;
 pub mod game;
 pub mod interface;
 pub mod map;
 pub mod messages;
+pub mod resources;
 pub mod utils;
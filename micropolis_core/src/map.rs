@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    error::MicropolisError,
+    utils::hash::{StableHash, StableHasher},
+};
+
 pub mod animations;
 pub mod buildings;
 pub mod connect;
@@ -53,7 +58,7 @@ impl MapClusteringStrategy {
 /// A map is assumed to cover a 2D grid of #WORLD_W times #WORLD_H positions.
 /// A block of positions may be clustered, and represented by a single data
 /// value.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Map<T> {
     /// Blocks clustering strategy.
     clustering_strategy: MapClusteringStrategy,
@@ -186,7 +191,7 @@ impl Map<Tile> {
     pub fn tilemap_with_dimensions(
         dimensions: &MapRectangle,
         uniform_type: TileType,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, MicropolisError> {
         let tilemap =
             vec![vec![Tile::from_type(uniform_type)?; dimensions.height]; dimensions.width];
         Ok(Map {
@@ -200,3 +205,10 @@ impl Map<Tile> {
             .map(|t| t.get_raw() & TILE_LOW_MASK)
     }
 }
+
+impl<T: StableHash> StableHash for Map<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_usize(self.clustering_strategy.block_size());
+        self.data.stable_hash(hasher);
+    }
+}
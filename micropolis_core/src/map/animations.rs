@@ -1,6 +1,11 @@
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
 
-use loader::{load_sequences_from_file, TileAnimatorSequences};
+use crate::{
+    error::MicropolisError,
+    resources::{GameResource, ResourcePack},
+};
+
+use loader::load_sequences;
 
 use super::{
     tiles::TILE_ALL_BITS, tiles::TILE_ANIM_BIT, tiles::TILE_LOW_MASK, Tile, TileMap, TileType,
@@ -8,31 +13,36 @@ use super::{
 
 mod loader;
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TileMapAnimator {
-    sequences: TileAnimatorSequences,
+    /// Next tile value of each animated tile value.
+    sequences: Vec<u16>,
 }
 
 impl TileMapAnimator {
-    pub fn load() -> Result<Self, String> {
-        let mut filepath = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        filepath.pop();
-        filepath.push("./res/animations_sequences.txt");
+    /// Load the animation sequences embedded in the crate.
+    pub fn load() -> Result<Self, MicropolisError> {
+        Self::from_resources(&ResourcePack::embedded())
+    }
+
+    /// Load the animation sequences of the given resource pack.
+    pub fn from_resources(resources: &ResourcePack) -> Result<Self, MicropolisError> {
         Ok(Self {
-            sequences: load_sequences_from_file(filepath)?,
+            sequences: load_sequences(resources.get(GameResource::AnimationSequences))?.to_vec(),
         })
     }
 
-    pub fn animate_world(&self, map: &mut TileMap) -> Result<(), String> {
+    pub fn animate_world(&self, map: &mut TileMap) -> Result<(), MicropolisError> {
         for column in map.tiles_mut().iter_mut() {
             for tile in column.iter_mut() {
                 let mut tile_raw = tile.get_raw();
                 if tile_raw & TILE_ANIM_BIT != 0x00 {
                     let tile_flags = tile_raw & TILE_ALL_BITS;
                     tile_raw &= TILE_LOW_MASK;
-                    tile_raw = *self.sequences.get(tile_raw as usize).ok_or(format!(
-                        "TileAnimator.animate_world: out of bounds tile value {:0>4X}",
-                        tile_raw
-                    ))?;
+                    tile_raw = *self
+                        .sequences
+                        .get(tile_raw as usize)
+                        .ok_or(MicropolisError::InvalidTileValue(tile_raw))?;
                     tile_raw |= tile_flags;
                     *tile = Tile::from_raw(tile_raw)?;
                 }
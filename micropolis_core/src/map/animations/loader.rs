@@ -1,19 +1,17 @@
-use std::{collections::HashMap, fs::File, io::BufRead, io::BufReader, io::Lines, path::Path};
+use std::{collections::HashMap, io::BufRead, io::Lines};
+
+use crate::error::MicropolisError;
 
 const MAX_TILE_VALUE: usize = 1024;
 
 pub type TileAnimatorSequences = [u16; MAX_TILE_VALUE];
 
-pub(super) fn load_sequences_from_file<P: AsRef<Path>>(
-    file_path: P,
-) -> Result<TileAnimatorSequences, String> {
-    let file = File::open(file_path).map_err(|why| why.to_string())?;
-    let lines = BufReader::new(file).lines();
-    let parsed_lines = load_lines(lines)?;
-    Ok(load_sequences(parsed_lines)?)
+pub(super) fn load_sequences(data: &[u8]) -> Result<TileAnimatorSequences, MicropolisError> {
+    let parsed_lines = load_lines(data.lines())?;
+    build_sequences(parsed_lines)
 }
 
-fn load_lines(lines: Lines<BufReader<File>>) -> Result<Vec<(usize, Vec<u16>)>, String> {
+fn load_lines<R: BufRead>(lines: Lines<R>) -> Result<Vec<(usize, Vec<u16>)>, MicropolisError> {
     let mut line_number: usize = 0;
     let mut parsed_lines: Vec<(usize, Vec<u16>)> = vec![];
 
@@ -21,7 +19,7 @@ fn load_lines(lines: Lines<BufReader<File>>) -> Result<Vec<(usize, Vec<u16>)>, S
         line_number += 1;
         let mut line_string = match line {
             Ok(str) => str,
-            Err(why) => return Err(why.to_string()),
+            Err(why) => return Err(why.into()),
         };
         line_string = if let Some(comment_start_index) = line_string.find('#') {
             line_string.split_at(comment_start_index).0.trim().into()
@@ -33,7 +31,7 @@ fn load_lines(lines: Lines<BufReader<File>>) -> Result<Vec<(usize, Vec<u16>)>, S
         }
         let mut values: Vec<u16> = vec![];
         for value_raw in line_string.split("->").map(|v| v.trim()) {
-            let tile_value = decode_sequence_value(value_raw)?;
+            let tile_value = decode_sequence_value(value_raw, line_number)?;
             values.push(tile_value);
         }
         parsed_lines.push((line_number, values));
@@ -42,7 +40,9 @@ fn load_lines(lines: Lines<BufReader<File>>) -> Result<Vec<(usize, Vec<u16>)>, S
     Ok(parsed_lines)
 }
 
-fn load_sequences(parsed_lines: Vec<(usize, Vec<u16>)>) -> Result<[u16; MAX_TILE_VALUE], String> {
+fn build_sequences(
+    parsed_lines: Vec<(usize, Vec<u16>)>,
+) -> Result<[u16; MAX_TILE_VALUE], MicropolisError> {
     let mut next_hash = HashMap::new();
     let mut line_hash = HashMap::new();
 
@@ -50,14 +50,24 @@ fn load_sequences(parsed_lines: Vec<(usize, Vec<u16>)>) -> Result<[u16; MAX_TILE
         let mut previous: Option<u16> = None;
         for tile_value in values {
             if let Some(previous_value) = previous {
-                if !next_hash.contains_key(&tile_value) {
-                    next_hash.insert(previous_value, tile_value);
-                    line_hash.insert(previous_value, line_number);
-                } else if next_hash.get(&previous_value) != Some(&tile_value) {
-                    return Err(
-                        format!("map.animations.loader::load_sequences: impossible sequence, two 'next' tiles for tile value {:0>4X} (at lines {:?} and {}).", previous_value, line_hash.get(&previous_value), line_number),
-                    );
-                } // else: entry already in table and same successor -> no-op
+                match next_hash.get(&previous_value) {
+                    None => {
+                        next_hash.insert(previous_value, tile_value);
+                        line_hash.insert(previous_value, line_number);
+                    }
+                    Some(next_value) if *next_value != tile_value => {
+                        return Err(MicropolisError::parse_at(
+                            line_number,
+                            format!(
+                                "impossible sequence, two 'next' tiles for tile value {:0>4X} (previous one at line {:?})",
+                                previous_value,
+                                line_hash.get(&previous_value)
+                            ),
+                        ));
+                    }
+                    // entry already in table and same successor -> no-op
+                    _ => {}
+                }
             };
             previous = Some(tile_value);
         }
@@ -80,7 +90,7 @@ fn load_sequences(parsed_lines: Vec<(usize, Vec<u16>)>) -> Result<[u16; MAX_TILE
     Ok(sequences_buffer)
 }
 
-fn decode_sequence_value(raw: &str) -> Result<u16, String> {
+fn decode_sequence_value(raw: &str, line_number: usize) -> Result<u16, MicropolisError> {
     let hexadecimal = raw.contains('x') || raw.contains('X');
     // parsing
     let value = if hexadecimal {
@@ -89,17 +99,16 @@ fn decode_sequence_value(raw: &str) -> Result<u16, String> {
         u16::from_str_radix(raw, 10)
     }
     .map_err(|why| {
-        format!(
-            "map.animations.loader::decode_sequence_value('{}'): cannot parse as unsigned integer: {}",
-            raw,
-            why.to_string()
+        MicropolisError::parse_at(
+            line_number,
+            format!("cannot parse '{}' as unsigned integer: {}", raw, why),
         )
     })?;
     // sanity check
     if value as usize >= MAX_TILE_VALUE {
-        Err(format!(
-            "map.animations.loader::decode_sequence_value('{}'): value {} is too big",
-            raw, value
+        Err(MicropolisError::parse_at(
+            line_number,
+            format!("value {} is too big", raw),
         ))
     } else {
         Ok(value)
@@ -108,9 +117,8 @@ fn decode_sequence_value(raw: &str) -> Result<u16, String> {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
-
-    use super::load_sequences_from_file;
+    use super::load_sequences;
+    use crate::resources::GameResource;
 
     const EXPECTED_SEQUENCES: [u16; 1024] = [
         0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
@@ -200,10 +208,7 @@ mod tests {
     #[test]
     #[ignore] // FIXME: remove when this works
     fn test_sequences_loading() {
-        let mut filepath = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        filepath.pop();
-        filepath.push("res/animations_sequences.txt");
-        let sequences = load_sequences_from_file(filepath).unwrap();
+        let sequences = load_sequences(GameResource::AnimationSequences.embedded_data()).unwrap();
         assert_eq!(sequences.len(), EXPECTED_SEQUENCES.len());
         for index in 0..sequences.len() {
             assert_eq!(sequences.get(index), EXPECTED_SEQUENCES.get(index));
@@ -1,4 +1,5 @@
 use super::{tools::EditingTool, MapRectangle, Tile, TileType};
+use crate::error::MicropolisError;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BuildingInfo {
@@ -29,7 +30,7 @@ pub enum BuildingType {
 }
 
 impl BuildingType {
-    pub fn info(&self) -> Result<BuildingInfo, String> {
+    pub fn info(&self) -> Result<BuildingInfo, MicropolisError> {
         use BuildingType::*;
 
         Ok(match *self {
@@ -1,3 +1,6 @@
+use crate::city::budget::MoneyValue;
+use crate::error::MicropolisError;
+
 use super::{
     tiles::TILE_BULL_BIT, tiles::TILE_BURN_BIT, tiles::TILE_BURN_BULL_CONDUCT_BIT,
     tiles::TILE_CONDUCT_BIT, tiles::TILE_LOW_MASK, tools::ConnectTileCommand, tools::ToolEffects,
@@ -70,7 +73,7 @@ impl TileMapConnector {
         command: &ConnectTileCommand,
         mut effects: ToolEffects,
         auto_bulldoze: bool,
-    ) -> Result<ToolResult, String> {
+    ) -> Result<ToolResult, MicropolisError> {
         if !map.in_bounds(position) {
             return Ok(ToolResult::Failed);
         }
@@ -81,10 +84,7 @@ impl TileMapConnector {
                 if auto_bulldoze {
                     let mut tile_raw = effects
                         .get_map_value_at(map, position)
-                        .ok_or(format!(
-                            "TileMapConnector.connect_tile cannot read tile value at {}",
-                            position
-                        ))?
+                        .ok_or(MicropolisError::OutOfBounds(*position))?
                         .get_raw();
                     if tile_raw & TILE_BULL_BIT != 0x00 {
                         tile_raw &= TILE_LOW_MASK;
@@ -140,13 +140,10 @@ impl TileMapConnector {
         position: &MapPosition,
         mut effects: ToolEffects,
         auto_bulldoze: bool,
-    ) -> Result<ToolResult, String> {
+    ) -> Result<ToolResult, MicropolisError> {
         let mut tile_raw = effects
             .get_map_value_at(map, position)
-            .ok_or(format!(
-                "TileMapConnector::bulldoze_tile cannot get effects tile value at {}",
-                position
-            ))?
+            .ok_or(MicropolisError::OutOfBounds(*position))?
             .get_raw();
         if tile_raw & TILE_BULL_BIT == 0x00 {
             return Ok(ToolResult::Failed); // not bulldozeable
@@ -194,16 +191,13 @@ impl TileMapConnector {
         map: &TileMap,
         position: &MapPosition,
         mut effects: ToolEffects,
-    ) -> Result<ToolResult, String> {
-        let mut cost: u32 = 0;
+    ) -> Result<ToolResult, MicropolisError> {
+        let mut cost: MoneyValue = 0;
         let (x, y) = (position.x, position.y);
         let (world_width, world_heigth) = map.bounds().get_tuple();
         let mut tile_raw = effects
             .get_map_value_at(map, position)
-            .ok_or(format!(
-                "TileMapConnector::lay_down_road cannot get effects tile value at {}",
-                position
-            ))?
+            .ok_or(MicropolisError::OutOfBounds(*position))?
             .get_raw();
         match tile_raw {
             t if t == TileType::Dirt.to_u16().unwrap() => {
@@ -230,10 +224,7 @@ impl TileMapConnector {
                     tile_raw = Self::neutralize_road(
                         effects
                             .get_map_tile_at(map, &position.with_x_offset(1))
-                            .ok_or(format!(
-                        "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                        position.with_x_offset(1)
-                    ))?
+                            .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(1)))?
                             .get_raw(),
                     );
                     if tile_raw == TileType::VerticalRailRoad.to_u16().unwrap()
@@ -255,7 +246,7 @@ impl TileMapConnector {
         map: &TileMap,
         position: &MapPosition,
         mut effects: ToolEffects,
-    ) -> Result<ToolEffects, String> {
+    ) -> Result<ToolEffects, MicropolisError> {
         effects = Self::fix_single_tile(map, position, effects)?;
         let (x, y) = (position.x, position.y);
         let (world_width, world_heigth) = map.bounds().get_tuple();
@@ -281,17 +272,14 @@ impl TileMapConnector {
         map: &TileMap,
         position: &MapPosition,
         mut effects: ToolEffects,
-    ) -> Result<ToolEffects, String> {
+    ) -> Result<ToolEffects, MicropolisError> {
         let mut adjacent_tiles: usize = 0;
         let (x, y) = (position.x, position.y);
         let (world_width, world_heigth) = map.bounds().get_tuple();
         let mut tile_raw = Self::neutralize_road(
             effects
                 .get_map_tile_at(map, position)
-                .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position
-                ))?
+                .ok_or(MicropolisError::OutOfBounds(*position))?
                 .get_raw(),
         );
 
@@ -299,7 +287,7 @@ impl TileMapConnector {
             table: &[TileType; 16],
             adjacent: usize,
             flags: u16,
-        ) -> Result<Tile, String> {
+        ) -> Result<Tile, MicropolisError> {
             Tile::from_raw(table.get(adjacent).unwrap().to_u16().unwrap() | flags)
         }
 
@@ -311,10 +299,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_y_offset(-1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(-1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(-1)))?
                         .get_raw(),
                 );
                 if (tile_raw == TileType::HorizontalRailRoad.to_u16().unwrap()
@@ -332,10 +317,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(1)))?
                         .get_raw(),
                 );
                 if (tile_raw == TileType::VerticalRailRoad.to_u16().unwrap()
@@ -353,10 +335,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_y_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(1)))?
                         .get_raw(),
                 );
                 if (tile_raw == TileType::HorizontalRailRoad.to_u16().unwrap()
@@ -374,10 +353,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(-1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(-1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(-1)))?
                         .get_raw(),
                 );
                 if (tile_raw == TileType::VerticalRailRoad.to_u16().unwrap()
@@ -403,10 +379,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_y_offset(-1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(-1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(-1)))?
                         .get_raw(),
                 );
                 if tile_raw >= TileType::RailHorizontalPowerVertical.to_u16().unwrap()
@@ -423,10 +396,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(1)))?
                         .get_raw(),
                 );
                 if tile_raw >= TileType::RailHorizontalPowerVertical.to_u16().unwrap()
@@ -443,10 +413,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_y_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(1)))?
                         .get_raw(),
                 );
                 if tile_raw >= TileType::RailHorizontalPowerVertical.to_u16().unwrap()
@@ -463,10 +430,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(-1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(-1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(-1)))?
                         .get_raw(),
                 );
                 if tile_raw >= TileType::RailHorizontalPowerVertical.to_u16().unwrap()
@@ -490,10 +454,7 @@ impl TileMapConnector {
             if y > 0 {
                 tile_raw = effects
                     .get_map_tile_at(map, &position.with_y_offset(-1))
-                    .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(-1)
-                ))?
+                    .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(-1)))?
                     .get_raw();
                 if tile_raw & TILE_CONDUCT_BIT != 0x00 {
                     tile_raw &= TILE_LOW_MASK;
@@ -517,10 +478,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(1)))?
                         .get_raw(),
                 );
                 if tile_raw & TILE_CONDUCT_BIT != 0x00 {
@@ -545,10 +503,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_y_offset(1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_y_offset(1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_y_offset(1)))?
                         .get_raw(),
                 );
                 if tile_raw & TILE_CONDUCT_BIT != 0x00 {
@@ -573,10 +528,7 @@ impl TileMapConnector {
                 tile_raw = Self::neutralize_road(
                     effects
                         .get_map_tile_at(map, &position.with_x_offset(-1))
-                        .ok_or(format!(
-                    "TileMapConnector::fix_single_tile cannot read effects map tile value at {}",
-                    position.with_x_offset(-1)
-                ))?
+                        .ok_or(MicropolisError::OutOfBounds(position.with_x_offset(-1)))?
                         .get_raw(),
                 );
                 if tile_raw & TILE_CONDUCT_BIT != 0x00 {
@@ -9,6 +9,7 @@ use water::{make_lakes, make_rivers, plop_big_river, plop_small_river, smooth_ri
 
 use super::MapClusteringStrategy;
 use super::{Map, MapPosition, MapRectangle, Tile, TileMap, TileType};
+use crate::error::MicropolisError;
 use crate::utils::random::MicropolisRandom;
 use crate::utils::Percentage;
 
@@ -73,7 +74,7 @@ impl MapGenerator {
         rng: &mut MicropolisRandom,
         seed: i32,
         dimensions: &MapRectangle,
-    ) -> Result<GeneratedTileMap, String> {
+    ) -> Result<GeneratedTileMap, MicropolisError> {
         // random setup
         rng.seed(seed);
 
@@ -132,7 +133,7 @@ impl MapGenerator {
         &self,
         rng: &mut MicropolisRandom,
         dimensions: &MapRectangle,
-    ) -> Result<TileMap, String> {
+    ) -> Result<TileMap, MicropolisError> {
         let mut terrain = Self::make_naked_island(rng, dimensions);
         smooth_rivers(rng, &mut terrain)?;
         make_forests(rng, self.level_trees, &mut terrain)?;
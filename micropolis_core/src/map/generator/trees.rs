@@ -1,4 +1,6 @@
 use crate::{
+    city::events::CityEvents,
+    error::MicropolisError,
     map::{
         tiles::{TILE_BLBNBIT_MASK, TILE_BURN_BULL_BIT},
         tools::ToolEffects,
@@ -16,7 +18,7 @@ pub fn make_forests(
     rng: &mut MicropolisRandom,
     level_trees: i16,
     terrain: &mut TileMap,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     let amount = match level_trees {
         level if level < 0 => 50 + rng.get_random(100),
         level => 3 + level,
@@ -69,7 +71,7 @@ fn splash_trees(
     }
 }
 
-fn smooth_trees(terrain: &mut TileMap) -> Result<(), String> {
+fn smooth_trees(terrain: &mut TileMap) -> Result<(), MicropolisError> {
     let mut effects = ToolEffects::new(true);
     let map_size = terrain.bounds();
     for x in 0..map_size.width {
@@ -78,7 +80,7 @@ fn smooth_trees(terrain: &mut TileMap) -> Result<(), String> {
             effects = smooth_trees_at(terrain, &position, effects, false)?;
         }
     }
-    let _ = effects.modify_world(terrain);
+    let _ = effects.modify_world(terrain, &mut CityEvents::new());
 
     Ok(())
 }
@@ -88,7 +90,7 @@ pub fn smooth_trees_at(
     position: &MapPosition,
     effects: ToolEffects,
     preserve: bool,
-) -> Result<ToolEffects, String> {
+) -> Result<ToolEffects, MicropolisError> {
     if let Some(tile) = effects.get_map_tile_at(terrain, position) {
         if !tile.is_tree() {
             return Ok(effects);
@@ -113,13 +115,15 @@ pub fn smooth_trees_at(
 
     let woods_type_raw = TileType::Woods
         .to_u16()
-        .ok_or("Woods tile type raw conversion error")?;
+        .ok_or(MicropolisError::InvalidTileType(TileType::Woods))?;
 
     let table_index = (bit_index & 0x0F) as usize;
-    let temp = *SMOOTH_FOREST_EDGES_TABLE.get(table_index).ok_or(format!(
-        "MapGenerator.smooth_trees_at SMOOTH_FOREST_EDGES_TABLE overflow: {}",
-        table_index
-    ))?;
+    let temp = *SMOOTH_FOREST_EDGES_TABLE.get(table_index).ok_or_else(|| {
+        MicropolisError::InvalidArgument(format!(
+            "smooth forest edges table index {} overflow",
+            table_index
+        ))
+    })?;
     Ok(match temp {
         0 => {
             if preserve {
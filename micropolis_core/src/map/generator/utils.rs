@@ -1,4 +1,5 @@
 use crate::{
+    error::MicropolisError,
     map::{MapPosition, MapPositionOffset, TileMap, TileType},
     utils::random::MicropolisRandom,
 };
@@ -38,7 +39,7 @@ pub fn put_tile_on_terrain(
     terrain: &mut TileMap,
     new_tile_type: TileType,
     at: &MapPosition,
-) -> Result<(), String> {
+) -> Result<(), MicropolisError> {
     if new_tile_type == TileType::Dirt {
         return Ok(());
     }
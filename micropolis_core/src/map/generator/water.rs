@@ -1,4 +1,5 @@
 use crate::{
+    error::MicropolisError,
     map::{
         tiles::TILE_LOW_MASK,
         tiles_type::{WOODS_HIGH, WOODS_LOW},
@@ -195,14 +196,17 @@ fn make_river(
     last_local_direction
 }
 
-pub fn smooth_rivers(rng: &mut MicropolisRandom, terrain: &mut TileMap) -> Result<(), String> {
+pub fn smooth_rivers(
+    rng: &mut MicropolisRandom,
+    terrain: &mut TileMap,
+) -> Result<(), MicropolisError> {
     let map_size = terrain.bounds();
     let dirt_type_raw = TileType::Dirt
         .to_u16()
-        .ok_or("Dirt tile type raw conversion error")?;
+        .ok_or(MicropolisError::InvalidTileType(TileType::Dirt))?;
     let river_type_raw = TileType::River
         .to_u16()
-        .ok_or("River tile type raw conversion error")?;
+        .ok_or(MicropolisError::InvalidTileType(TileType::River))?;
     for x in 0..map_size.width {
         for y in 0..map_size.height {
             let position: MapPosition = (x, y).into();
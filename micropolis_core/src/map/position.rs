@@ -10,7 +10,10 @@ use num_traits::abs;
 use num_traits::{FromPrimitive as FromPrimitiveTrait, ToPrimitive as ToPrimitiveTrait};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::random::MicropolisRandom;
+use crate::utils::{
+    hash::{StableHash, StableHasher},
+    random::MicropolisRandom,
+};
 
 const DIRECTION_GD_TAB: [usize; 13] = [0, 3, 2, 1, 3, 4, 5, 7, 6, 5, 7, 8, 1];
 
@@ -120,6 +123,10 @@ impl MapPosition {
     }
 
     /// Compute the distance & direction to get from this position to the given destination.
+    ///
+    /// The distance is the Manhattan distance.
+    ///
+    /// See `Micropolis::getDir` in the C++ code.
     pub fn direction_towards(&self, destination: &MapPosition) -> (u32, MapPositionOffset) {
         let diff = *destination - *self;
         let mut z = match diff.as_tuple() {
@@ -140,20 +147,15 @@ impl MapPosition {
         };
 
         let (diff_x, diff_y) = diff.absolute().as_tuple();
-        z = if diff_x * 2 < diff_y {
-            z + 1
-        } else if diff_y * 2 < diff_y {
-            // TODO: always false!
-            z - 1
-        } else if z < 0 || z > 12 {
-            0
-        } else {
-            z
-        };
+        if diff_x * 2 < diff_y {
+            z += 1;
+        } else if diff_y * 2 < diff_x {
+            z -= 1;
+        }
 
         (
             (diff_x + diff_y) as u32,
-            MAP_POSITION_DIRECTIONS[DIRECTION_GD_TAB[z]],
+            MapPositionOffset::from_usize(DIRECTION_GD_TAB[z]).unwrap(),
         )
     }
 }
@@ -364,7 +366,7 @@ impl MapPositionOffset {
         if 0 <= applied.x
             && applied.x < bounds.width as i32
             && 0 <= applied.y
-            && applied.y <= bounds.height as i32
+            && applied.y < bounds.height as i32
         {
             Some(applied)
         } else {
@@ -460,6 +462,26 @@ impl MapPositionOffset {
     }
 }
 
+impl StableHash for MapPosition {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_i32(self.x);
+        hasher.write_i32(self.y);
+    }
+}
+
+impl StableHash for MapRectangle {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_usize(self.width);
+        hasher.write_usize(self.height);
+    }
+}
+
+impl StableHash for MapPositionOffset {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(*self as u8);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{MapPosition, MapPositionOffset, MapRectangle};
@@ -2,6 +2,11 @@ use std::{fmt, rc::Rc};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    error::MicropolisError,
+    utils::hash::{StableHash, StableHasher},
+};
+
 use super::{
     buildings::BuildingInfo,
     tiles_type::{TileType, WOODS_HIGH, WOODS_LOW},
@@ -89,21 +94,20 @@ impl fmt::Display for Tile {
 }
 
 impl Tile {
-    pub fn from_raw(raw: u16) -> Result<Self, String> {
+    pub fn from_raw(raw: u16) -> Result<Self, MicropolisError> {
         Ok(Self {
             raw,
             tile_type: TileType::from_u16(raw & TILE_TYPE_MASK),
         })
     }
 
-    pub fn from_type(tile_type: TileType) -> Result<Self, String> {
+    pub fn from_type(tile_type: TileType) -> Result<Self, MicropolisError> {
         match tile_type {
-            TileType::Invalid => Err(format!("Tile::from_type invalid type '{:?}'", tile_type)),
+            TileType::Invalid => Err(MicropolisError::InvalidTileType(tile_type)),
             _ => Ok(Tile {
-                raw: tile_type.to_u16().ok_or(format!(
-                    "Tile::from_type cannot cast type '{:?}' to raw",
-                    tile_type
-                ))?,
+                raw: tile_type
+                    .to_u16()
+                    .ok_or_else(|| MicropolisError::InvalidTileType(tile_type.clone()))?,
                 tile_type: Some(tile_type),
             }),
         }
@@ -120,11 +124,10 @@ impl Tile {
     pub fn get_type(&self) -> &Option<TileType> {
         &self.tile_type
     }
-    pub fn set_type(&mut self, tile_type: TileType) -> Result<(), String> {
-        let type_raw = tile_type.to_u16().ok_or(format!(
-            "Tile.set_type cannot cast type '{:?}' to raw",
-            tile_type
-        ))?;
+    pub fn set_type(&mut self, tile_type: TileType) -> Result<(), MicropolisError> {
+        let type_raw = tile_type
+            .to_u16()
+            .ok_or_else(|| MicropolisError::InvalidTileType(tile_type.clone()))?;
         let status_raw = self.raw & TILE_STATUS_MASK;
         self.raw = status_raw | type_raw;
         self.tile_type = Some(tile_type);
@@ -167,7 +170,7 @@ impl Tile {
     }
 
     pub fn is_conductive(&self) -> bool {
-        self.get_type_raw() & TILE_POWER_BIT == TILE_POWER_BIT
+        self.raw & TILE_CONDUCT_BIT == TILE_CONDUCT_BIT
     }
 
     /// Can the current tile be used as a road?
@@ -181,17 +184,23 @@ impl Tile {
 
     /// Is the current tile vulnerable to an earthquake?
     pub fn is_vulnerable(&self) -> bool {
-        let tile_value = self.get_type_raw() & TILE_LOW_MASK;
+        let tile_value = self.raw & TILE_LOW_MASK;
         tile_value >= TileType::ResidentialBase.to_u16().unwrap()
             && tile_value <= TileType::LastZone.to_u16().unwrap()
-            && tile_value & TILE_ZONE_BIT == 0x00
+            && self.raw & TILE_ZONE_BIT == 0x00
     }
 
     /// Is the current tile floodable?
     pub fn is_floodable(&self) -> bool {
-        let tile_value = self.get_type_raw() & TILE_LOW_MASK;
-        tile_value == TileType::Dirt.to_u16().unwrap()
-            || (tile_value & (TILE_BULL_BIT | TILE_BURN_BIT) == TILE_BULL_BIT | TILE_BURN_BIT)
+        self.raw == TileType::Dirt.to_u16().unwrap()
+            || (self.raw & (TILE_BULL_BIT | TILE_BURN_BIT) == TILE_BULL_BIT | TILE_BURN_BIT)
+    }
+}
+
+/// Only the raw value is hashed, the tile type being derived from it.
+impl StableHash for Tile {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u16(self.raw);
     }
 }
 
@@ -211,4 +220,25 @@ mod tests {
         assert_eq!(TILE_STATUS_MASK, 0xFC00);
         assert_eq!(TILE_TYPE_MASK, 0x03FF);
     }
+
+    #[test]
+    fn test_tile_vulnerability() {
+        let house = TileType::ResidentialBase.to_u16().unwrap() + 1;
+        assert!(Tile::from_raw(house | TILE_BURN_BULL_BIT)
+            .unwrap()
+            .is_vulnerable());
+        assert!(!Tile::from_raw(house | TILE_ZONE_BIT)
+            .unwrap()
+            .is_vulnerable());
+        assert!(!Tile::from_type(TileType::Dirt).unwrap().is_vulnerable());
+
+        assert!(Tile::from_type(TileType::Dirt).unwrap().is_floodable());
+        let woods = TileType::Woods.to_u16().unwrap();
+        assert!(Tile::from_raw(woods | TILE_BURN_BULL_BIT)
+            .unwrap()
+            .is_floodable());
+        assert!(!Tile::from_raw(woods | TILE_BURN_BIT)
+            .unwrap()
+            .is_floodable());
+    }
 }
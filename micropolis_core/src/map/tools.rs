@@ -1,11 +1,21 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use toolbox::{
     tool_build_building, tool_bulldozer, tool_forest, tool_land, tool_network, tool_park,
     tool_rail, tool_road, tool_water, tool_wire,
 };
 
-use crate::{city::budget::MoneyValue, utils::random::MicropolisRandom};
+use crate::{
+    city::{
+        budget::MoneyValue,
+        events::{CityEvents, GameSound, SoundChannel, UiCallback},
+    },
+    error::MicropolisError,
+    messages::GameMessage,
+    utils::random::MicropolisRandom,
+};
 
 use super::{buildings::BuildingType, tiles::TILE_LOW_MASK, MapPosition, Tile, TileMap};
 
@@ -27,7 +37,7 @@ pub enum ConnectTileCommand {
     Wire,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EditingTool {
     Residential,
     Commercial,
@@ -155,9 +165,11 @@ pub struct ToolEffects {
     /// Ignore any incurred cost.
     free: bool,
     /// Accumulated cost of the modifications.
-    cost: u32,
+    cost: MoneyValue,
     /// Set of modifications in the world, indexed by position.
     modifications: HashMap<MapPosition, Tile>,
+    /// Messages and sounds for the front-end, sent when the world is modified.
+    events: CityEvents,
 }
 
 impl ToolEffects {
@@ -166,10 +178,16 @@ impl ToolEffects {
             free,
             cost: 0,
             modifications: HashMap::new(),
+            events: CityEvents::new(),
         }
     }
 
-    pub fn add_cost(mut self, cost: u32) -> Self {
+    /// Accumulated cost of the modifications.
+    pub fn get_cost(&self) -> MoneyValue {
+        self.cost
+    }
+
+    pub fn add_cost(mut self, cost: MoneyValue) -> Self {
         if !self.free {
             self.cost += cost;
         }
@@ -181,9 +199,30 @@ impl ToolEffects {
         self
     }
 
+    /// Notify the front-end that the tool `name` was applied at the given position.
+    pub fn did_tool(mut self, name: &str, position: &MapPosition) -> Self {
+        self.events.callback(UiCallback::DidTool {
+            name: name.to_owned(),
+            position: *position,
+        });
+        self
+    }
+
+    /// Play a sound once the modifications are applied.
+    pub fn make_sound(
+        mut self,
+        channel: SoundChannel,
+        sound: GameSound,
+        position: &MapPosition,
+    ) -> Self {
+        self.events.make_sound(channel, sound, Some(*position));
+        self
+    }
+
     pub fn clear(&mut self) {
         self.cost = 0;
         self.modifications.clear();
+        self.events.clear();
     }
 
     /// Consume the given tool result to apply it to the current instance
@@ -200,6 +239,8 @@ impl ToolEffects {
                     }
                     hm
                 };
+                // the other effects continue from a clone of these ones
+                self.events = other.events;
                 None
             }
             _ => Some(result),
@@ -208,9 +249,14 @@ impl ToolEffects {
 
     /// Perform the effects stored in the structure to the simulation world.
     ///
-    /// Returns the total cost of the operation.
+    /// Returns the total cost of the operation, and moves the front-end
+    /// notifications into `events`.
     #[must_use]
-    pub fn modify_world(&mut self, map: &mut TileMap) -> Option<u32> {
+    pub fn modify_world(
+        &mut self,
+        map: &mut TileMap,
+        events: &mut CityEvents,
+    ) -> Option<MoneyValue> {
         // modify the world
         if self
             .modifications
@@ -223,23 +269,25 @@ impl ToolEffects {
             return None;
         }
 
-        // send the notification
-        // TODO: messaging system
+        // send the notifications
+        events.append(&mut self.events);
 
+        let cost = self.cost;
         self.clear();
-        Some(self.cost)
+        Some(cost)
     }
 
     /// Apply the modifications if there are enough funds.
     pub fn modify_world_if_enough_money(
         &mut self,
         map: &mut TileMap,
-        total_funds: u32,
-    ) -> (bool, Option<u32>) {
-        if self.cost < total_funds {
+        total_funds: MoneyValue,
+        events: &mut CityEvents,
+    ) -> (bool, Option<MoneyValue>) {
+        if self.cost > total_funds {
             (false, None)
         } else {
-            let cost = self.modify_world(map);
+            let cost = self.modify_world(map, events);
             (true, cost)
         }
     }
@@ -266,6 +314,9 @@ impl ToolEffects {
     }
 }
 
+/// Apply a tool at the given position, notifying the player of any failure.
+///
+/// On success, the returned effects hold the cost to charge to the city.
 pub fn tool_down(
     rng: &mut MicropolisRandom,
     map: &mut TileMap,
@@ -273,33 +324,38 @@ pub fn tool_down(
     tool: &EditingTool,
     auto_bulldoze: bool,
     animations_enabled: bool,
-    total_funds: u32,
-) -> Result<(), String> {
-    Ok(
-        match apply_tool(
-            rng,
-            map,
-            position,
-            tool,
-            auto_bulldoze,
-            animations_enabled,
-            total_funds,
-        )? {
-            ToolResult::NeedBulldoze => {
-                // TODO: send MESSAGE_BULLDOZE_AREA_FIRST
-                // TODO: play interface sound "UhUh" at (x << 4, y << 4
-                // TODO: played sound should only be heard by the calling user
-            }
-            ToolResult::NoMoney => {
-                // TODO: send MESSAGE_NOT_ENOUGH_FUNDS
-                // TODO: play interface sound "Sorry" at (x << 4, y << 4
-                // TODO: played sound should only be heard by the calling user
-            }
-            _ => {}
-        },
-    )
+    total_funds: MoneyValue,
+    events: &mut CityEvents,
+) -> Result<ToolResult, MicropolisError> {
+    let result = apply_tool(
+        rng,
+        map,
+        position,
+        tool,
+        auto_bulldoze,
+        animations_enabled,
+        total_funds,
+        events,
+    )?;
+    match result {
+        ToolResult::NeedBulldoze => {
+            events.send_message(GameMessage::MessageBulldozeAreaFirst, None, false, true);
+            // TODO: played sound should only be heard by the calling user
+            events.make_sound(SoundChannel::Interface, GameSound::UhUh, Some(*position));
+        }
+        ToolResult::NoMoney => {
+            events.send_message(GameMessage::MessageNotEnoughFunds, None, false, true);
+            // TODO: played sound should only be heard by the calling user
+            events.make_sound(SoundChannel::Interface, GameSound::Sorry, Some(*position));
+        }
+        _ => {}
+    }
+    Ok(result)
 }
 
+/// Apply a tool at the given position.
+///
+/// On success, the messages and sounds of the tool are queued in `events`.
 pub fn apply_tool(
     rng: &mut MicropolisRandom,
     map: &mut TileMap,
@@ -307,8 +363,9 @@ pub fn apply_tool(
     tool: &EditingTool,
     auto_bulldoze: bool,
     animations_enabled: bool,
-    total_funds: u32,
-) -> Result<ToolResult, String> {
+    total_funds: MoneyValue,
+    events: &mut CityEvents,
+) -> Result<ToolResult, MicropolisError> {
     use EditingTool::*;
 
     // TODO: handle free tool / free terrain editing scenarios
@@ -407,7 +464,7 @@ pub fn apply_tool(
     match result.clone() {
         ToolResult::Succeeded(mut chained_effects) => {
             if chained_effects
-                .modify_world_if_enough_money(map, total_funds)
+                .modify_world_if_enough_money(map, total_funds, events)
                 .0
             {
                 Ok(result)
@@ -425,6 +482,100 @@ fn apply_build_building(
     building: BuildingType,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_building(map, center, effects, &building.info()?, auto_bulldoze)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_tool, EditingTool};
+    use crate::{
+        city::events::{CityEvents, GameEvent, GameSound, SoundChannel, SoundEvent, UiCallback},
+        map::{tiles::TILE_ZONE_BIT, MapClusteringStrategy, MapPosition, Tile, TileMap, TileType},
+        utils::random::MicropolisRandom,
+    };
+
+    #[test]
+    fn test_tool_events() {
+        let mut rng = MicropolisRandom::from_seed(1234);
+        let dirt = Tile::from_type(TileType::Dirt).unwrap();
+        let mut map = TileMap::with_data(
+            vec![vec![dirt; 100]; 120],
+            MapClusteringStrategy::BlockSize1,
+        );
+        let mut events = CityEvents::new();
+
+        let water = (10, 10).into();
+        let result = apply_tool(
+            &mut rng,
+            &mut map,
+            &water,
+            &EditingTool::Water,
+            true,
+            false,
+            1000,
+            &mut events,
+        )
+        .unwrap();
+        assert!(result.is_success());
+        assert_eq!(
+            events.drain(),
+            vec![GameEvent::Callback(UiCallback::DidTool {
+                name: "Water".into(),
+                position: water,
+            })]
+        );
+
+        // coal power plant centered on (20, 20)
+        let plant = (20, 20).into();
+        let coal_base = TileType::CoalBase.to_u16().unwrap();
+        for dx in 0..4 {
+            for dy in 0..4 {
+                let zone_bit = if (dx, dy) == (1, 1) { TILE_ZONE_BIT } else { 0 };
+                let tile = Tile::from_raw((coal_base + (dy * 4 + dx) as u16) | zone_bit).unwrap();
+                map.set_tile_at(&MapPosition::new(19 + dx, 19 + dy), tile);
+            }
+        }
+        let result = apply_tool(
+            &mut rng,
+            &mut map,
+            &plant,
+            &EditingTool::Bulldozer,
+            true,
+            false,
+            1000,
+            &mut events,
+        )
+        .unwrap();
+        assert!(result.is_success());
+        assert_eq!(
+            events.drain(),
+            vec![
+                GameEvent::Sound(SoundEvent {
+                    channel: SoundChannel::City,
+                    sound: GameSound::ExplosionLow,
+                    position: Some(plant),
+                }),
+                GameEvent::Callback(UiCallback::DidTool {
+                    name: "Dozr".into(),
+                    position: plant,
+                }),
+            ]
+        );
+
+        // nothing is sent when the tool fails
+        let result = apply_tool(
+            &mut rng,
+            &mut map,
+            &plant,
+            &EditingTool::Airport,
+            false,
+            false,
+            0,
+            &mut events,
+        )
+        .unwrap();
+        assert!(!result.is_success());
+        assert!(events.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    error::MicropolisError,
     map::{
         buildings::BuildingInfo, generator::trees::smooth_trees_at, tiles::TILE_ANIM_BIT,
         tiles::TILE_BULL_BIT, tiles::TILE_BURN_BIT, tiles::TILE_BURN_BULL_BIT,
@@ -21,7 +22,7 @@ pub(super) fn put_rubble(
     size: i8,
     mut effects: ToolEffects,
     animations_enabled: bool,
-) -> Result<ToolEffects, String> {
+) -> Result<ToolEffects, MicropolisError> {
     for x in anchor.x..anchor.x + size as i32 {
         for y in anchor.y..anchor.y + size as i32 {
             let current_position = MapPosition::new(x, y);
@@ -30,10 +31,7 @@ pub(super) fn put_rubble(
             }
             let tile = effects
                 .get_map_tile_at(map, &current_position)
-                .ok_or(format!(
-                    "put_rubble cannot read effects tile at {}",
-                    current_position
-                ))?;
+                .ok_or(MicropolisError::OutOfBounds(current_position))?;
             if tile.is_any_of_types(&[TileType::Radioactive, TileType::Dirt]) {
                 continue;
             }
@@ -59,7 +57,7 @@ pub(super) fn put_down_park(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let value = rng.get_random(4) as u16;
     let tile_raw = TILE_BURN_BIT
         | TILE_BULL_BIT
@@ -83,10 +81,10 @@ pub(super) fn put_down_water(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let tile = effects
         .get_map_tile_at(map, position)
-        .ok_or(format!("Cannot read tile at {}", position))?;
+        .ok_or(MicropolisError::OutOfBounds(*position))?;
     if tile.is_of_type(&TileType::River) {
         Ok(ToolResult::Failed)
     } else {
@@ -103,10 +101,10 @@ pub(super) fn put_down_land(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let tile = effects
         .get_map_tile_at(map, position)
-        .ok_or(format!("Cannot read tile at {}", position))?;
+        .ok_or(MicropolisError::OutOfBounds(*position))?;
     if tile.is_dirt() {
         Ok(ToolResult::Failed)
     } else {
@@ -126,7 +124,7 @@ pub(super) fn put_down_forest(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     effects = effects.add_modification(
         position,
         Tile::from_raw(TileType::Woods.to_u16().unwrap() | TILE_BURN_BULL_BIT)?,
@@ -155,7 +153,7 @@ fn prepare_building_site(
     size: &MapRectangle,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let (width, height) = (size.width as i8, size.height as i8);
 
     // check that the entire site fits on the map
@@ -169,7 +167,7 @@ fn prepare_building_site(
             let current_position = position.with_offset(dx, dy);
             let current_tile = effects
                 .get_map_tile_at(map, &current_position)
-                .ok_or(format!("Cannot read tile at {}", current_position))?;
+                .ok_or(MicropolisError::OutOfBounds(current_position))?;
             if current_tile.is_of_type(&TileType::Dirt) {
                 continue; // tile is buildable
             }
@@ -196,7 +194,7 @@ fn put_down_building(
     position: &MapPosition,
     building_info: &BuildingInfo,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let (width, height) = (
         building_info.size.width as i8,
         building_info.size.height as i8,
@@ -229,7 +227,7 @@ pub(super) fn build_building(
     building_info: &BuildingInfo,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     // compute top-left 'anchor'
     let anchor = center.with_offset(-1, -1);
     // prepare building site
@@ -1,8 +1,19 @@
 use crate::{
-    map::buildings::BuildingInfo, map::connect::TileMapConnector, map::tiles::TILE_ANIM_BIT,
-    map::tiles::TILE_BULL_BIT, map::tiles::TILE_BURN_BIT, map::tiles::TILE_CONDUCT_BIT,
-    map::tiles::TILE_LOW_MASK, map::tiles::TILE_ZONE_BIT, map::MapPosition, map::Tile,
-    map::TileMap, map::TileType, utils::random::MicropolisRandom,
+    city::events::{GameSound, SoundChannel},
+    error::MicropolisError,
+    map::buildings::BuildingInfo,
+    map::connect::TileMapConnector,
+    map::tiles::TILE_ANIM_BIT,
+    map::tiles::TILE_BULL_BIT,
+    map::tiles::TILE_BURN_BIT,
+    map::tiles::TILE_CONDUCT_BIT,
+    map::tiles::TILE_LOW_MASK,
+    map::tiles::TILE_ZONE_BIT,
+    map::MapPosition,
+    map::Tile,
+    map::TileMap,
+    map::TileType,
+    utils::random::MicropolisRandom,
 };
 
 use super::{
@@ -20,30 +31,24 @@ pub(super) fn tool_bulldozer(
     mut effects: ToolEffects,
     auto_bulldoze: bool,
     animations_enabled: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     if !map.in_bounds(position) {
         return Ok(ToolResult::Failed);
     }
 
-    let tile_raw = effects
-        .get_map_tile_at(map, position)
-        .ok_or(format!("toool_bulldozer: cannot read tile at {}", position))?
-        .get_raw()
-        & TILE_LOW_MASK;
+    let map_value = effects
+        .get_map_value_at(map, position)
+        .ok_or(MicropolisError::OutOfBounds(*position))?
+        .get_raw();
+    let tile_raw = map_value & TILE_LOW_MASK;
     let tile = Tile::from_raw(tile_raw)?;
-    let (delta, zone_size) = if tile_raw & TILE_ZONE_BIT != 0x00 {
+    let (delta, zone_size) = if map_value & TILE_ZONE_BIT != 0x00 {
         (
             MapPosition::new(0, 0),
-            compute_size(&tile).ok_or(format!(
-                "tool_bulldozer: cannot compute size for tile {}",
-                tile
-            ))?,
+            compute_size(&tile).ok_or(MicropolisError::InvalidTileValue(tile_raw))?,
         )
     } else {
-        check_big_zone(&tile).ok_or(format!(
-            "tool_bulldozer: cannot check big zone for tile {}",
-            tile
-        ))?
+        check_big_zone(&tile).ok_or(MicropolisError::InvalidTileValue(tile_raw))?
     };
 
     if zone_size == 0 {
@@ -58,10 +63,10 @@ pub(super) fn tool_bulldozer(
                     auto_bulldoze,
                 )?;
                 if result.is_success()
-                    && effects.get_map_tile_at(map, position).ok_or(format!(
-                        "tool_bulldozer: cannot read effects tile at {}",
-                        position
-                    ))? != tile
+                    && effects
+                        .get_map_tile_at(map, position)
+                        .ok_or(MicropolisError::OutOfBounds(*position))?
+                        != tile
                 {
                     ToolResult::Succeeded(result.effects().unwrap().add_cost(5))
                 } else {
@@ -85,7 +90,8 @@ pub(super) fn tool_bulldozer(
 
         match zone_size {
             3 => {
-                // TODO: trigger sound Explosion-High in channel city at position
+                effects =
+                    effects.make_sound(SoundChannel::City, GameSound::ExplosionHigh, position);
                 effects = put_rubble(
                     rng,
                     map,
@@ -96,7 +102,7 @@ pub(super) fn tool_bulldozer(
                 )?;
             }
             4 => {
-                // TODO: trigger sound Explosion-Low in channel city at position
+                effects = effects.make_sound(SoundChannel::City, GameSound::ExplosionLow, position);
                 effects = put_rubble(
                     rng,
                     map,
@@ -107,8 +113,9 @@ pub(super) fn tool_bulldozer(
                 )?;
             }
             6 => {
-                // TODO: trigger sound Explosion-High in channel city at position
-                // TODO: trigger sound Explosion-Low in channel city at position
+                effects = effects
+                    .make_sound(SoundChannel::City, GameSound::ExplosionHigh, position)
+                    .make_sound(SoundChannel::City, GameSound::ExplosionLow, position);
                 effects = put_rubble(
                     rng,
                     map,
@@ -122,28 +129,26 @@ pub(super) fn tool_bulldozer(
         }
     }
 
-    // TODO: send "didtool" message "Dozr, (x, y)"
-
-    Ok(ToolResult::Succeeded(effects))
+    Ok(ToolResult::Succeeded(effects.did_tool("Dozr", position)))
 }
 
 /// Build arbitrary infrastructure at the given position.
-fn tool_build_wrapper<F: FnMut(ToolEffects) -> Result<ToolResult, String>>(
+fn tool_build_wrapper<F: FnMut(ToolEffects) -> Result<ToolResult, MicropolisError>>(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
     mut apply: F,
     tool_message_id: &str,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     if !map.in_bounds(position) {
         return Ok(ToolResult::Failed);
     }
     if let Some(result) = effects.chain_or_return(apply(effects.clone())?) {
         return Ok(result);
     }
-    // TODO: send "didtool" message "(tool_message_id), (x, y)"
-    let id = tool_message_id.clone();
-    Ok(ToolResult::Succeeded(effects))
+    Ok(ToolResult::Succeeded(
+        effects.did_tool(tool_message_id, position),
+    ))
 }
 
 /// Build a road at the given position.
@@ -152,7 +157,7 @@ pub(super) fn tool_road(
     position: &MapPosition,
     effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
@@ -176,7 +181,7 @@ pub(super) fn tool_rail(
     position: &MapPosition,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
@@ -200,7 +205,7 @@ pub(super) fn tool_wire(
     position: &MapPosition,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
@@ -224,7 +229,7 @@ pub(super) fn tool_park(
     map: &TileMap,
     center: &MapPosition,
     effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         center,
@@ -239,13 +244,13 @@ pub(super) fn tool_network(
     map: &TileMap,
     position: &MapPosition,
     effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
         effects,
         |mut e| put_down_network(map, position, e),
-        "Park",
+        "Net",
     )
 }
 
@@ -254,10 +259,10 @@ fn put_down_network(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let mut tile = effects
         .get_map_tile_at(map, position)
-        .ok_or(format!("Cannot read tile at {}", position))?;
+        .ok_or(MicropolisError::OutOfBounds(*position))?;
     if !tile.is_dirt() && is_tile_auto_bulldozable(&tile).unwrap() {
         effects = effects
             .add_cost(EditingTool::Bulldozer.cost())
@@ -290,13 +295,13 @@ pub(super) fn tool_water(
     map: &TileMap,
     position: &MapPosition,
     mut effects: ToolEffects,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
         effects,
         |mut e| put_down_water(map, position, e),
-        "Net",
+        "Water",
     )
 }
 
@@ -308,7 +313,7 @@ pub(super) fn tool_land(
     effects: ToolEffects,
     auto_bulldoze: bool,
     animations_enabled: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
@@ -338,16 +343,15 @@ pub(super) fn tool_forest(
     mut effects: ToolEffects,
     auto_bulldoze: bool,
     animations_enabled: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         position,
         effects,
         |mut e| {
-            let tile = e.get_map_value_at(map, position).ok_or(format!(
-                "tool_forest: cannot read effects tile at {}",
-                position
-            ))?;
+            let tile = e
+                .get_map_value_at(map, position)
+                .ok_or(MicropolisError::OutOfBounds(*position))?;
             if tile.is_tree() {
                 // nothing to do
                 return Ok(ToolResult::Succeeded(e));
@@ -367,10 +371,9 @@ pub(super) fn tool_forest(
                 }
             }
 
-            let tile_refreshed = e.get_map_value_at(map, position).ok_or(format!(
-                "tool_forest: cannot read effects tile at {}",
-                position
-            ))?;
+            let tile_refreshed = e
+                .get_map_value_at(map, position)
+                .ok_or(MicropolisError::OutOfBounds(*position))?;
             if tile_refreshed.is_dirt() {
                 put_down_forest(map, position, e)
             } else {
@@ -389,7 +392,7 @@ pub(super) fn tool_build_building(
     mut effects: ToolEffects,
     building_info: &BuildingInfo,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     tool_build_wrapper(
         map,
         center,
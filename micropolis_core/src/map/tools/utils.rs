@@ -1,4 +1,7 @@
-use crate::map::{connect::TileMapConnector, MapPosition, MapRectangle, Tile, TileMap, TileType};
+use crate::{
+    error::MicropolisError,
+    map::{connect::TileMapConnector, MapPosition, MapRectangle, Tile, TileMap, TileType},
+};
 
 use super::{ConnectTileCommand, ToolEffects, ToolResult};
 
@@ -9,7 +12,7 @@ pub(super) fn check_border(
     zone_size: &MapRectangle,
     mut effects: ToolEffects,
     auto_bulldoze: bool,
-) -> Result<ToolResult, String> {
+) -> Result<ToolResult, MicropolisError> {
     let (zone_width, zone_height) = (zone_size.width as i8, zone_size.height as i8);
 
     for top in 0..zone_width {
@@ -1,8 +1,11 @@
 mod parser;
 
-use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::MicropolisError,
+    resources::{GameResource, ResourcePack},
+};
 
 use parser::parse_messages_resource;
 
@@ -10,6 +13,7 @@ pub type ParsedMessagesResource = Vec<String>;
 
 pub struct MessagesStorage {
     score_card_strings: ParsedMessagesResource,
+    tile_kind_strings: ParsedMessagesResource,
     game_messages: ParsedMessagesResource,
 }
 
@@ -18,6 +22,10 @@ impl MessagesStorage {
         self.score_card_strings.get(id as usize)
     }
 
+    pub fn get_tile_kind_string(&self, id: TileKindString) -> Option<&String> {
+        self.tile_kind_strings.get(id as usize)
+    }
+
     pub fn get_game_message(&self, id: GameMessage) -> Option<&String> {
         match id as usize {
             0 => None,
@@ -25,26 +33,30 @@ impl MessagesStorage {
         }
     }
 
-    pub fn load() -> Result<Self, String> {
-        Ok(MessagesStorage {
-            score_card_strings: parse_messages_resource(20, Self::load_resource_file("stri.202")?)?,
-            game_messages: parse_messages_resource(49, Self::load_resource_file("stri.301")?)?,
-        })
+    /// Load the messages embedded in the crate.
+    pub fn load() -> Result<Self, MicropolisError> {
+        Self::from_resources(&ResourcePack::embedded())
     }
 
-    fn load_resource_file(name: &'static str) -> Result<BufReader<File>, String> {
-        let mut filepath = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        filepath.pop();
-        filepath.push(format!("res/{}", name));
-        Ok(BufReader::new(File::open(&filepath).map_err(|err| {
-            format!("load_resource_file({}) error: {}", filepath.display(), err)
-        })?))
+    /// Load the messages of the given resource pack.
+    pub fn from_resources(resources: &ResourcePack) -> Result<Self, MicropolisError> {
+        Ok(MessagesStorage {
+            score_card_strings: parse_messages_resource(
+                20,
+                resources.get(GameResource::ScoreCardStrings),
+            )?,
+            tile_kind_strings: parse_messages_resource(
+                28,
+                resources.get(GameResource::TileKindStrings),
+            )?,
+            game_messages: parse_messages_resource(49, resources.get(GameResource::GameMessages))?,
+        })
     }
 }
 
 /// String literals displayed in the score card and
 /// corresponding to the "stri.202" data resource.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ScoreCardString {
     /// Low
     PopulationdensityLow = 0,
@@ -92,6 +104,69 @@ pub enum ScoreCardString {
     GrowrateFastGrowth = 19,
 }
 
+/// String literals naming the kind of a tile when querying it and
+/// corresponding to the "stri.219" data resource.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileKindString {
+    /// Clear
+    Clear = 0,
+    /// Water
+    Water = 1,
+    /// Trees
+    Trees = 2,
+    /// Rubble
+    Rubble = 3,
+    /// Flood
+    Flood = 4,
+    /// Radioactive Waste
+    RadioactiveWaste = 5,
+    /// Fire
+    Fire = 6,
+    /// Road
+    Road = 7,
+    /// Power
+    Power = 8,
+    /// Rail
+    Rail = 9,
+    /// Residential
+    Residential = 10,
+    /// Commercial
+    Commercial = 11,
+    /// Industrial
+    Industrial = 12,
+    /// Seaport
+    Seaport = 13,
+    /// Airport
+    Airport = 14,
+    /// Coal Power
+    CoalPower = 15,
+    /// Fire Department
+    FireDepartment = 16,
+    /// Police Department
+    PoliceDepartment = 17,
+    /// Stadium
+    Stadium = 18,
+    /// Nuclear Power
+    NuclearPower = 19,
+    /// Draw Bridge (horizontal)
+    HorizontalDrawBridge = 20,
+    /// Radar Dish
+    RadarDish = 21,
+    /// Fountain
+    Fountain = 22,
+    /// Industrial (animated tiles)
+    IndustrialAnimation = 23,
+    /// Steelers 38  Bears 3
+    FootballGame = 24,
+    /// Draw Bridge (vertical)
+    VerticalDrawBridge = 25,
+    /// Ur 238
+    NuclearSwirl = 26,
+    /// Unknown
+    Unknown = 27,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GameMessage {
     /// More residential zones needed.
     MessageNeedMoreResidential = 1,
@@ -213,7 +288,7 @@ pub enum GameMessage {
 
 #[cfg(test)]
 mod tests {
-    use super::{GameMessage, MessagesStorage, ScoreCardString};
+    use super::{GameMessage, MessagesStorage, ScoreCardString, TileKindString};
 
     #[test]
     fn test_string_literals_loading() {
@@ -225,6 +300,10 @@ mod tests {
         assert_eq!(
             storage.get_score_card_string(ScoreCardString::GrowrateSlowGrowth),
             Some(&"Slow Growth".to_string())
-        )
+        );
+        assert_eq!(
+            storage.get_tile_kind_string(TileKindString::NuclearSwirl),
+            Some(&"Ur 238".to_string())
+        );
     }
 }
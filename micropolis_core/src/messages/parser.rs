@@ -1,17 +1,17 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 use super::ParsedMessagesResource;
+use crate::error::MicropolisError;
 
 pub fn parse_messages_resource(
     messages_count: usize,
-    reader: BufReader<File>,
-) -> Result<ParsedMessagesResource, String> {
+    reader: impl BufRead,
+) -> Result<ParsedMessagesResource, MicropolisError> {
     let mut parsed = ParsedMessagesResource::new();
     for (i, line) in reader.lines().enumerate() {
         let trimmed = line
             .map(|l| l.trim().to_string())
-            .map_err(|err| format!("reading error at line {}: {}", i + 1, err))?;
+            .map_err(|err| MicropolisError::parse_at(i + 1, err.to_string()))?;
         if trimmed.is_empty() || &trimmed[..] == "x" {
             continue;
         }
@@ -20,10 +20,10 @@ pub fn parse_messages_resource(
     if parsed.len() == messages_count {
         Ok(parsed)
     } else {
-        Err(format!(
+        Err(MicropolisError::parse(format!(
             "expected {} message items, found {}",
             messages_count,
             parsed.len()
-        ))
+        )))
     }
 }
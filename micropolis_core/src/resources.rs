@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::error::MicropolisError;
+
+/// Data files needed by the game engine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameResource {
+    /// Strings displayed in the score card.
+    ScoreCardStrings,
+    /// Names of the kinds of tiles.
+    TileKindStrings,
+    /// Messages sent to the player.
+    GameMessages,
+    /// Animation sequences of the tiles.
+    AnimationSequences,
+}
+
+impl GameResource {
+    /// Every resource of a pack.
+    pub const ALL: [GameResource; 4] = [
+        GameResource::ScoreCardStrings,
+        GameResource::TileKindStrings,
+        GameResource::GameMessages,
+        GameResource::AnimationSequences,
+    ];
+
+    /// Name of the file of the resource, in the `res` directory.
+    pub fn file_name(&self) -> &'static str {
+        use GameResource::*;
+        match self {
+            ScoreCardStrings => "stri.202",
+            TileKindStrings => "stri.219",
+            GameMessages => "stri.301",
+            AnimationSequences => "animations_sequences.txt",
+        }
+    }
+
+    /// Contents of the resource compiled into the crate.
+    pub fn embedded_data(&self) -> &'static [u8] {
+        use GameResource::*;
+        match self {
+            ScoreCardStrings => include_bytes!("../res/stri.202"),
+            TileKindStrings => include_bytes!("../res/stri.219"),
+            GameMessages => include_bytes!("../res/stri.301"),
+            AnimationSequences => include_bytes!("../res/animations_sequences.txt"),
+        }
+    }
+}
+
+/// Set of game resources.
+///
+/// Defaults to the resources embedded in the crate, so the engine does not
+/// need the `res` directory at run time. Custom resource packs can override
+/// some or all of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourcePack {
+    overrides: HashMap<GameResource, Vec<u8>>,
+}
+
+impl ResourcePack {
+    /// Pack of the resources compiled into the crate.
+    pub fn embedded() -> Self {
+        Self::default()
+    }
+
+    /// Load a resource pack from a directory laid out like `res`.
+    ///
+    /// Missing files fall back to the embedded resources.
+    pub fn from_directory(directory: &Path) -> Result<Self, MicropolisError> {
+        let mut pack = Self::embedded();
+        for resource in GameResource::ALL.iter() {
+            let path = directory.join(resource.file_name());
+            match fs::read(&path) {
+                Ok(data) => pack = pack.with_resource(*resource, data),
+                Err(source) if source.kind() == io::ErrorKind::NotFound => {}
+                Err(source) => return Err(MicropolisError::ResourceLoad { path, source }),
+            }
+        }
+        Ok(pack)
+    }
+
+    /// Override a resource with the given in-memory contents.
+    pub fn with_resource(mut self, resource: GameResource, data: Vec<u8>) -> Self {
+        self.overrides.insert(resource, data);
+        self
+    }
+
+    /// Contents of the given resource.
+    pub fn get(&self, resource: GameResource) -> &[u8] {
+        self.overrides
+            .get(&resource)
+            .map(|data| &data[..])
+            .unwrap_or_else(|| resource.embedded_data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{GameResource, ResourcePack};
+    use crate::{
+        error::MicropolisError,
+        map::animations::TileMapAnimator,
+        messages::{GameMessage, MessagesStorage},
+    };
+
+    #[test]
+    fn test_resource_pack_overrides() {
+        let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("res");
+        let pack = ResourcePack::from_directory(&directory).unwrap();
+        for resource in GameResource::ALL.iter() {
+            assert_eq!(pack.get(*resource), resource.embedded_data());
+        }
+        assert_eq!(
+            ResourcePack::from_directory(&directory.join("missing")).unwrap(),
+            ResourcePack::embedded()
+        );
+
+        let messages = String::from_utf8(GameResource::GameMessages.embedded_data().to_vec())
+            .unwrap()
+            .replacen("More residential zones needed.", "Build more houses!", 1);
+        let pack = ResourcePack::embedded()
+            .with_resource(GameResource::GameMessages, messages.into_bytes());
+        let storage = MessagesStorage::from_resources(&pack).unwrap();
+        assert_eq!(
+            storage.get_game_message(GameMessage::MessageNeedMoreResidential),
+            Some(&"Build more houses!".to_string())
+        );
+        assert!(TileMapAnimator::from_resources(&pack).is_ok());
+
+        let pack = ResourcePack::embedded()
+            .with_resource(GameResource::AnimationSequences, b"0x10 -> 4096".to_vec());
+        assert!(matches!(
+            TileMapAnimator::from_resources(&pack),
+            Err(MicropolisError::Parse { line: Some(1), .. })
+        ));
+    }
+}
@@ -1,8 +1,10 @@
 use num_traits::Num;
+use serde::{Deserialize, Serialize};
 
+pub mod hash;
 pub mod random;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Percentage(f64);
 
 impl Percentage {
@@ -21,7 +23,7 @@ impl Percentage {
 
     /// Increment by 1%.
     pub fn increment(&mut self) {
-        self.0 += 1f64;
+        self.0 += 0.01f64;
     }
 }
 
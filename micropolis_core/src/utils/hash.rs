@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// 64-bit FNV-1a hasher producing stable values.
+///
+/// Values are fed as explicit little-endian bytes through `StableHash`, never
+/// through `std::hash::Hash`, whose encoding (length prefixes, enum
+/// discriminants...) is not guaranteed between Rust versions. So the values
+/// depend neither on the platform nor on the compiler, and can be compared
+/// between two machines.
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Hash a single value.
+    pub fn hash_one<T: StableHash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = Self::new();
+        value.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+    pub fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+    pub fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+    pub fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+    /// Sizes are hashed on 64 bits, whatever the pointer width.
+    pub fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    pub fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+    pub fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+    pub fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+    pub fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    pub fn write_bool(&mut self, b: bool) {
+        self.write_u8(b as u8);
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A value with an explicit, version-independent byte encoding for `StableHasher`.
+pub trait StableHash {
+    fn stable_hash(&self, hasher: &mut StableHasher);
+}
+
+macro_rules! impl_stable_hash_for_integer {
+    ($($integer:ty => $write:ident),*) => {
+        $(
+            impl StableHash for $integer {
+                fn stable_hash(&self, hasher: &mut StableHasher) {
+                    hasher.$write(*self);
+                }
+            }
+        )*
+    };
+}
+
+impl_stable_hash_for_integer!(
+    u8 => write_u8,
+    u16 => write_u16,
+    u32 => write_u32,
+    u64 => write_u64,
+    usize => write_usize,
+    i8 => write_i8,
+    i16 => write_i16,
+    i32 => write_i32,
+    i64 => write_i64,
+    bool => write_bool
+);
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash(hasher);
+    }
+}
+
+/// Strings are their length followed by their UTF-8 bytes.
+impl StableHash for str {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_usize(self.len());
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_str().stable_hash(hasher);
+    }
+}
+
+/// Sequences are their length followed by their elements.
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_usize(self.len());
+        for element in self {
+            element.stable_hash(hasher);
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_slice().stable_hash(hasher);
+    }
+}
+
+/// Options are a 0 byte when empty, or a 1 byte followed by their value.
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        match self {
+            None => hasher.write_u8(0),
+            Some(value) => {
+                hasher.write_u8(1);
+                value.stable_hash(hasher);
+            }
+        }
+    }
+}
+
+/// Ordered maps are their length followed by their entries, in key order.
+impl<K: StableHash, V: StableHash> StableHash for BTreeMap<K, V> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_usize(self.len());
+        for (key, value) in self {
+            key.stable_hash(hasher);
+            value.stable_hash(hasher);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{StableHash, StableHasher};
+
+    #[test]
+    fn test_stable_hasher() {
+        // reference FNV-1a values
+        assert_eq!(StableHasher::new().finish(), 0xCBF2_9CE4_8422_2325);
+        let mut hasher = StableHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xAF63_DC4C_8601_EC8C);
+        let mut hasher = StableHasher::new();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.finish(), 0x8594_4171_F739_67E8);
+
+        // integers are hashed as little-endian, whatever the platform
+        let mut hasher = StableHasher::new();
+        hasher.write_usize(0x0102);
+        let mut expected = StableHasher::new();
+        expected.write(&[0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(hasher.finish(), expected.finish());
+    }
+
+    #[test]
+    fn test_stable_hash_encoding() {
+        let mut expected = StableHasher::new();
+        expected.write(&[2, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00, 0x02, 0x01]);
+        assert_eq!(
+            StableHasher::hash_one(&vec![0x0001u16, 0x0102]),
+            expected.finish()
+        );
+
+        let mut expected = StableHasher::new();
+        expected.write(&[1, 3, 0, 0, 0, 0, 0, 0, 0, b'f', b'o', b'o']);
+        assert_eq!(StableHasher::hash_one(&Some("foo")), expected.finish());
+        assert_eq!(
+            StableHasher::hash_one(&None::<u8>),
+            StableHasher::hash_one(&0u8)
+        );
+
+        let mut map = BTreeMap::new();
+        map.insert(2u8, true);
+        map.insert(1u8, false);
+        let mut expected = StableHasher::new();
+        expected.write(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 1]);
+        assert_eq!(StableHasher::hash_one(&map), expected.finish());
+    }
+}
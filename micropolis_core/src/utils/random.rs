@@ -1,5 +1,10 @@
 use std::cmp;
 
+use serde::{Deserialize, Serialize};
+
+use super::hash::{StableHash, StableHasher};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MicropolisRandom {
     seed: i32,
     next_random: u64,
@@ -17,7 +22,8 @@ impl MicropolisRandom {
         Self::from_seed(final_seed)
     }
 
-    fn from_seed(seed: i32) -> Self {
+    /// Build a generator with a fixed seed, for reproducible games and tests.
+    pub fn from_seed(seed: i32) -> Self {
         Self {
             seed,
             next_random: seed as u64,
@@ -61,12 +67,12 @@ impl MicropolisRandom {
     }
 
     /// Draw a random signed 16-bit number.
-    pub fn get_random_16_signed(&mut self) -> u16 {
-        let random = self.get_random_16();
+    pub fn get_random_16_signed(&mut self) -> i16 {
+        let random = self.get_random_16() as u16 as i32;
         if random > 0x7FFF {
-            (0x7FFF - random) as u16
+            (0x7FFF - random) as i16
         } else {
-            random as u16
+            random as i16
         }
     }
 
@@ -76,3 +82,10 @@ impl MicropolisRandom {
         ((self.next_random & 0xFFFF00) >> 8) as i32
     }
 }
+
+impl StableHash for MicropolisRandom {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_i32(self.seed);
+        hasher.write_u64(self.next_random);
+    }
+}
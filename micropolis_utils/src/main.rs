@@ -1,10 +1,11 @@
 use core::panic;
-use std::{fs::File, io::Write, path::PathBuf};
+use std::{fs::File, io::BufReader, io::Write, path::PathBuf, process};
 
 use clap::Parser;
 use serde_json;
 
 use micropolis_rs_core::{
+    city::conformance::ConformanceFixture,
     map::{
         generator::{GeneratorCreateIsland, MapGenerator},
         tiles_type::TileType,
@@ -33,6 +34,13 @@ enum SubCommand {
     )]
     /// Generate a basic JSON TileMap (effectively a 2D **rows-first** array).
     GenerateBasicJsonTileMap(GenerateBasicJsonTileMapOptions),
+    #[clap(
+        version = "0.1.0",
+        author = "pierreyoda <pierreyoda@users.noreply.github.com>",
+        name = "check-conformance"
+    )]
+    /// Run JSON fixtures recorded with the original C++ engine, and report the differences.
+    CheckConformance(CheckConformanceOptions),
 }
 
 /// A subcommand for controlling testing
@@ -44,6 +52,14 @@ struct GenerateBasicJsonTileMapOptions {
     height: usize,
 }
 
+/// A subcommand for checking the port against the original engine
+#[derive(Parser, Debug)]
+struct CheckConformanceOptions {
+    /// Paths of the JSON fixtures to run.
+    #[clap(required = true)]
+    fixtures: Vec<PathBuf>,
+}
+
 fn main() {
     let opts: Opts = Opts::parse();
 
@@ -92,5 +108,28 @@ fn main() {
                 Ok(_) => println!("successfully wrote to file {}", filepath.display()),
             };
         }
+        SubCommand::CheckConformance(options) => {
+            let mut conformant = true;
+            for filepath in options.fixtures.iter() {
+                let file = match File::open(filepath) {
+                    Err(why) => panic!("could not open file {}: {}", filepath.display(), why),
+                    Ok(file) => file,
+                };
+                let fixture: ConformanceFixture =
+                    match serde_json::from_reader(BufReader::new(file)) {
+                        Err(why) => panic!("could not parse file {}: {}", filepath.display(), why),
+                        Ok(fixture) => fixture,
+                    };
+                let report = match fixture.run() {
+                    Err(why) => panic!("could not run fixture {}: {}", filepath.display(), why),
+                    Ok(report) => report,
+                };
+                print!("{}", report);
+                conformant &= report.is_conformant();
+            }
+            if !conformant {
+                process::exit(1);
+            }
+        }
     }
 }
@@ -41,6 +41,6 @@ pub fn generate_new_map(
         let tilemap = generated.generated_terrain.tiles();
         Ok(JsValue::from_serde(&tilemap).unwrap())
     } else {
-        Err(JsValue::from_str(&result.err().unwrap()[..]))
+        Err(JsValue::from_str(&result.err().unwrap().to_string()))
     }
 }